@@ -0,0 +1,61 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use cid::Cid;
+
+use crate::basic_block::{verify_hash, BasicBlock, Block};
+use crate::error::Result;
+
+/// The block built from an `Arc<[u8]>`, e.g. for blocks kept in memory by a cache or an arena.
+///
+/// The data is held in a single shared buffer, the clones of the block and the `Bytes`
+/// returned by `raw_data`/`to_bytes` don't copy it.
+#[derive(Clone, Debug)]
+pub struct ArcBlock {
+    cid: Cid,
+    data: Bytes,
+}
+
+impl ArcBlock {
+    /// Creates a new `ArcBlock` with given data and CID.
+    ///
+    /// `Bytes` can't borrow the allocation of an `Arc<[u8]>`,
+    /// so the data is copied once into the shared buffer of the block.
+    pub fn new_with_cid(data: Arc<[u8]>, cid: Cid) -> Result<ArcBlock> {
+        verify_hash(&data, &cid)?;
+        Ok(ArcBlock {
+            cid,
+            data: Bytes::from(data.to_vec()),
+        })
+    }
+}
+
+impl Block for ArcBlock {
+    fn raw_data(&self) -> &Bytes {
+        &self.data
+    }
+}
+
+impl AsRef<Cid> for ArcBlock {
+    fn as_ref(&self) -> &Cid {
+        &self.cid
+    }
+}
+
+// The data of the basic block is shared without copying.
+impl From<BasicBlock> for ArcBlock {
+    fn from(block: BasicBlock) -> Self {
+        ArcBlock {
+            data: block.to_bytes(),
+            cid: block.cid().clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for ArcBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[Block {:?}]", self)
+    }
+}
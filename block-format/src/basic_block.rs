@@ -2,20 +2,30 @@
 
 use bytes::Bytes;
 
-use cid::{Cid, ExtMultihashRef, IntoExt};
+use cid::{Cid, Codec, ExtMultihashRef, IntoExt, Prefix};
 use multihash::Sha2_256;
 
-use crate::error::Result;
+use crate::error::{BlockFormatError, Result};
 
 /// The trait for getting raw data and cid of block.
 pub trait Block: AsRef<Cid> {
     /// Get the raw data of block.
     fn raw_data(&self) -> &Bytes;
 
+    /// Get the raw data of block as owned `Bytes`, sharing the data of the block.
+    fn to_bytes(&self) -> Bytes {
+        self.raw_data().clone()
+    }
+
     /// Get the cid.
     fn cid(&self) -> &Cid {
         self.as_ref()
     }
+
+    /// Get the codec of the cid.
+    fn codec(&self) -> Codec {
+        self.cid().codec()
+    }
 }
 
 impl Block for BasicBlock {
@@ -49,24 +59,35 @@ impl BasicBlock {
 
     /// Creates a new `BasicBlock` with given bytes and CID.
     pub fn new_with_cid(data: Bytes, cid: Cid) -> Result<BasicBlock> {
-        use crate::error::BlockFormatError;
-        let hash1 = cid.hash();
-        let hash2 = hash1.algorithm().digest(data.as_ref());
-        if hash1 != hash2 {
-            return Err(BlockFormatError::WrongHash(
-                hash1.as_bytes().to_vec(),
-                hash2.as_bytes().to_vec(),
-            ));
-        }
+        verify_hash(&data, &cid)?;
         Ok(BasicBlock { data, cid })
     }
 
+    /// Creates a new `BasicBlock` with given bytes, and its CID is built from the prefix.
+    pub fn new_with_prefix(data: Bytes, prefix: &Prefix) -> BasicBlock {
+        let cid = Cid::new_from_prefix(prefix, data.as_ref());
+        BasicBlock { data, cid }
+    }
+
     /// Get the multihash of cid of the basic block.
     pub fn multihash(&self) -> ExtMultihashRef {
         self.cid.hash()
     }
 }
 
+/// Checks that the data match the hash of the CID.
+pub(crate) fn verify_hash(data: &[u8], cid: &Cid) -> Result<()> {
+    let hash1 = cid.hash();
+    let hash2 = hash1.algorithm().digest(data);
+    if hash1 != hash2 {
+        return Err(BlockFormatError::WrongHash(
+            hash1.as_bytes().to_vec(),
+            hash2.as_bytes().to_vec(),
+        ));
+    }
+    Ok(())
+}
+
 impl std::fmt::Display for BasicBlock {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[Block {:?}]", self)
@@ -0,0 +1,115 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use cid::{Cid, Codec, ExtCode, Version};
+use multihash::Code;
+
+use crate::arc_block::ArcBlock;
+use crate::basic_block::BasicBlock;
+use crate::error::{BlockFormatError, Result};
+
+/// BlockBuilder builds blocks whose CID version, codec and hash function
+/// are chosen from its configuration.
+///
+/// The default configuration builds CIDv0 `dag-pb` blocks hashed with SHA2-256.
+#[derive(Clone, Debug)]
+pub struct BlockBuilder {
+    version: Version,
+    codec: Codec,
+    hash: ExtCode,
+}
+
+impl Default for BlockBuilder {
+    fn default() -> Self {
+        BlockBuilder {
+            version: Version::V0,
+            codec: Codec::DagProtobuf,
+            hash: Code::Sha2_256.into(),
+        }
+    }
+}
+
+impl BlockBuilder {
+    /// Creates a new `BlockBuilder` with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `BlockBuilder` building CIDv1 blocks with given codec and hash function.
+    pub fn new_v1(codec: Codec, hash: ExtCode) -> Self {
+        BlockBuilder {
+            version: Version::V1,
+            codec,
+            hash,
+        }
+    }
+
+    /// Sets the CID version of the built blocks.
+    pub fn with_version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets the codec of the built blocks.
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sets the hash function of the built blocks.
+    pub fn with_hash(mut self, hash: ExtCode) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Computes the CID of the data.
+    pub fn cid(&self, data: &[u8]) -> Result<Cid> {
+        let hash = self.hash.digest(data);
+        match self.version {
+            Version::V0 => {
+                if self.codec != Codec::DagProtobuf {
+                    return Err(BlockFormatError::IncompatibleCidV0(self.codec));
+                }
+                Ok(Cid::new_v0(hash)?)
+            }
+            Version::V1 => Ok(Cid::new_v1(self.codec, hash)),
+        }
+    }
+
+    /// Builds a `BasicBlock` with given bytes.
+    pub fn build(&self, data: Bytes) -> Result<BasicBlock> {
+        let cid = self.cid(&data)?;
+        BasicBlock::new_with_cid(data, cid)
+    }
+
+    /// Builds an `ArcBlock` with given data.
+    pub fn build_arc(&self, data: Arc<[u8]>) -> Result<ArcBlock> {
+        let cid = self.cid(&data)?;
+        ArcBlock::new_with_cid(data, cid)
+    }
+}
+
+/// Converts the CID into CIDv1, CIDv1 is returned as is.
+pub fn cid_to_v1(cid: &Cid) -> Cid {
+    match cid.version() {
+        Version::V0 => Cid::new_v1(cid.codec(), cid.hash().to_owned()),
+        Version::V1 => cid.clone(),
+    }
+}
+
+/// Converts the CID into CIDv0, CIDv0 is returned as is.
+///
+/// Only the `dag-pb` CIDs hashed with SHA2-256 can be converted.
+pub fn cid_to_v0(cid: &Cid) -> Result<Cid> {
+    match cid.version() {
+        Version::V0 => Ok(cid.clone()),
+        Version::V1 => {
+            if cid.codec() != Codec::DagProtobuf {
+                return Err(BlockFormatError::IncompatibleCidV0(cid.codec()));
+            }
+            Ok(Cid::new_v0(cid.hash().to_owned())?)
+        }
+    }
+}
@@ -9,6 +9,9 @@ pub enum BlockFormatError {
     /// The data of block is not match given hash.
     #[error("data is not match given hash, fst: {0:?}, snd: {1:?}")]
     WrongHash(Vec<u8>, Vec<u8>),
+    /// The CID can't be represented as CIDv0, which only supports `dag-pb`.
+    #[error("cid with codec {0:?} can't be represented as CIDv0")]
+    IncompatibleCidV0(cid::Codec),
     /// Cid error.
     #[error("cid error: {0}")]
     CidError(#[from] cid::Error),
@@ -1,16 +1,21 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
 //! Implementation of `block format` in Rust,
-//! which provides the `BasicBlock` structure and the `Block` trait.
+//! which provides the `BasicBlock` and `ArcBlock` structures, the `Block` trait
+//! and the `BlockBuilder` choosing the CID of blocks.
 
 #![deny(missing_docs)]
 
+mod arc_block;
 mod basic_block;
+mod builder;
 mod error;
 
 pub use bytes::{Buf, Bytes};
 pub use cid::Cid;
 pub use multihash::MultihashRef;
 
+pub use self::arc_block::ArcBlock;
 pub use self::basic_block::{BasicBlock, Block};
+pub use self::builder::{cid_to_v0, cid_to_v1, BlockBuilder};
 pub use self::error::{BlockFormatError, Result};
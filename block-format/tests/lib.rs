@@ -1,9 +1,14 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
-use multihash::Sha2_256;
+use std::sync::Arc;
 
-use block_format::{BasicBlock, Block};
-use cid::IntoExt;
+use cid::{Cid, Codec, ExtCode, IntoExt, Version};
+use matches::matches;
+use multihash::{Code, Sha2_256};
+
+use block_format::{
+    cid_to_v0, cid_to_v1, ArcBlock, BasicBlock, Block, BlockBuilder, BlockFormatError,
+};
 
 #[test]
 fn test_blocks_basic() {
@@ -40,3 +45,92 @@ fn test_cid() {
 
     assert_eq!(block.multihash(), cid.hash());
 }
+
+#[test]
+fn test_codec() {
+    let block = BasicBlock::new(b"codec".as_ref().into());
+    assert_eq!(block.codec(), Codec::DagProtobuf);
+
+    let block = BlockBuilder::new_v1(Codec::DagCBOR, Code::Blake2b256.into())
+        .build(b"codec".as_ref().into())
+        .unwrap();
+    assert_eq!(block.codec(), Codec::DagCBOR);
+    assert_eq!(block.cid().version(), Version::V1);
+    let code: ExtCode = Code::Blake2b256.into();
+    assert_eq!(block.multihash().algorithm(), code);
+}
+
+#[test]
+fn test_new_with_prefix() {
+    let data = b"prefix data";
+    let expect = BlockBuilder::new_v1(Codec::Raw, Code::Sha2_256.into())
+        .build(data.as_ref().into())
+        .unwrap();
+    let block = BasicBlock::new_with_prefix(data.as_ref().into(), &expect.cid().prefix());
+    assert_eq!(block.cid(), expect.cid());
+    assert_eq!(block.raw_data(), expect.raw_data());
+}
+
+#[test]
+fn test_block_builder() {
+    let data = b"builder data";
+    // the default builder is compatible with `BasicBlock::new`
+    let block = BlockBuilder::new().build(data.as_ref().into()).unwrap();
+    assert_eq!(block.cid(), BasicBlock::new(data.as_ref().into()).cid());
+
+    let builder = BlockBuilder::new().with_codec(Codec::DagCBOR);
+    assert!(matches!(
+        builder.cid(data),
+        Err(BlockFormatError::IncompatibleCidV0(Codec::DagCBOR))
+    ));
+    let builder = builder
+        .with_version(Version::V1)
+        .with_hash(Code::Sha3_256.into());
+    let cid = builder.cid(data).unwrap();
+    assert_eq!(cid.codec(), Codec::DagCBOR);
+    assert_eq!(cid.hash(), Code::Sha3_256.digest(data).into_ext());
+
+    let block = builder.build_arc(data.as_ref().into()).unwrap();
+    assert_eq!(block.cid(), &cid);
+    assert_eq!(block.raw_data(), &data[..]);
+    assert_eq!(block.to_bytes().as_ref(), data.as_ref());
+}
+
+#[test]
+fn test_arc_block() {
+    let data: Arc<[u8]> = b"arc data".as_ref().into();
+    let basic = BasicBlock::new(b"arc data".as_ref().into());
+    let block = ArcBlock::new_with_cid(data.clone(), basic.cid().clone()).unwrap();
+    assert_eq!(block.raw_data(), basic.raw_data());
+    // the clones share the data of the block
+    let clone = block.clone();
+    assert_eq!(clone.raw_data().as_ptr(), block.raw_data().as_ptr());
+    assert_eq!(clone.to_bytes().as_ptr(), block.raw_data().as_ptr());
+
+    let other = BasicBlock::new(b"other data".as_ref().into());
+    assert!(ArcBlock::new_with_cid(data, other.cid().clone()).is_err());
+
+    let block = ArcBlock::from(basic.clone());
+    assert_eq!(block.cid(), basic.cid());
+    // the bytes of the basic block are shared
+    assert_eq!(block.raw_data().as_ptr(), basic.raw_data().as_ptr());
+}
+
+#[test]
+fn test_cid_version_conversion() {
+    let block = BasicBlock::new(b"v0 data".as_ref().into());
+    let v0 = block.cid();
+    let v1 = cid_to_v1(v0);
+    assert_eq!(v1.version(), Version::V1);
+    assert_eq!(v1.codec(), Codec::DagProtobuf);
+    assert_eq!(v1.hash(), v0.hash());
+    assert_eq!(&cid_to_v0(&v1).unwrap(), v0);
+    assert_eq!(&cid_to_v0(v0).unwrap(), v0);
+    assert_eq!(cid_to_v1(&v1), v1);
+
+    let raw = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(b"raw").into_ext());
+    assert!(matches!(
+        cid_to_v0(&raw),
+        Err(BlockFormatError::IncompatibleCidV0(Codec::Raw))
+    ));
+}
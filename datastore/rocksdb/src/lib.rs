@@ -10,6 +10,7 @@ use std::iter::FromIterator;
 use std::result;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use datastore::{
     key::{self, Key},
     query, Batching, Datastore, Read, SyncQuery, TxnDatastore, Write,
@@ -147,32 +148,97 @@ impl RocksDB {
     }
 }
 
+/// Passes the value to `f` without copying it out of the database,
+/// `f` runs while the read locks of the database are held.
 #[inline]
-fn inner_get(db: &RocksDB, key: &Key) -> DSResult<Vec<u8>> {
+fn inner_view<F, T>(db: &RocksDB, key: &Key, f: F) -> DSResult<T>
+where
+    F: FnOnce(&[u8]) -> T,
+{
     let (prefix, key) = pre_process_key(&db.inner.cols, &key);
-    let value = db.inner.db.get(prefix, key.as_bytes())?;
+    let value = db.inner.db.view(prefix, key.as_bytes(), f)?;
     value.ok_or(datastore::DSError::NotFound(key.to_string()))
 }
 
 impl Read for RocksDB {
     fn get(&self, key: &Key) -> DSResult<Vec<u8>> {
-        inner_get(self, key).map(|v| v)
+        inner_view(self, key, |v| v.to_vec())
+    }
+
+    fn get_bytes(&self, key: &Key) -> DSResult<Bytes> {
+        let (prefix, key) = pre_process_key(&self.inner.cols, &key);
+        let value = self.inner.db.get_bytes(prefix, key.as_bytes())?;
+        value.ok_or(datastore::DSError::NotFound(key.to_string()))
     }
 
     fn has(&self, key: &Key) -> DSResult<bool> {
         let (prefix, key) = pre_process_key(&self.inner.cols, &key);
-        let value = self.inner.db.get(prefix, key.as_bytes())?;
+        let value = self.inner.db.view(prefix, key.as_bytes(), |_| ())?;
         Ok(value.is_some())
     }
 
     fn get_size(&self, key: &Key) -> DSResult<usize> {
-        inner_get(self, key).map(|v| v.len())
+        inner_view(self, key, |v| v.len())
     }
 }
 
 impl SyncQuery for RocksDB {
-    fn query<R: query::SyncResults>(&self, _query: query::Query) -> DSResult<R> {
-        todo!()
+    /// returns the entries whose keys are under the prefix of the query. the root prefix "/"
+    /// walks every column, any other prefix only walks the column its keys are stored in.
+    fn query<R: query::SyncResults>(&self, query: query::Query) -> DSResult<R> {
+        let prefix = Key::new(&query.prefix);
+        let cols: Vec<&str> = if prefix.as_str() == key::LEFT_SLASH_STR {
+            self.inner.cols.iter().map(|col| col.as_str()).collect()
+        } else if self.inner.cols.contains(prefix.as_str()) {
+            vec![prefix.as_str()]
+        } else {
+            vec![pre_process_key(&self.inner.cols, &prefix).0]
+        };
+        // like go-datastore, only the keys strictly under the prefix are matched
+        let namespace = if prefix.as_str() == key::LEFT_SLASH_STR {
+            prefix.as_str().to_string()
+        } else {
+            format!("{}{}", prefix.as_str(), key::LEFT_SLASH_STR)
+        };
+
+        let mut entries = vec![];
+        for col in cols {
+            for (k, v) in self.inner.db.iter(col) {
+                let k = String::from_utf8(k.into_vec())
+                    .map_err(|e| datastore::DSError::Other(Box::new(e)))?;
+                let k = if col == DEFAULT_COLUMN_NAME {
+                    k
+                } else {
+                    format!("{}{}", col, k)
+                };
+                if !k.starts_with(&namespace) {
+                    continue;
+                }
+                let entry = query::Entry {
+                    key: k,
+                    size: v.len(),
+                    value: if query.keys_only {
+                        vec![]
+                    } else {
+                        v.into_vec()
+                    },
+                };
+                if query.filters.iter().all(|f| f.filter(&entry)) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        if !query.orders.is_empty() {
+            entries.sort_by(|a, b| query::order::less(&query.orders, a, b));
+        }
+        let limit = if query.limit == 0 {
+            usize::max_value()
+        } else {
+            query.limit
+        };
+        let entries = entries.into_iter().skip(query.offset).take(limit).collect();
+        Ok(R::new(query, entries))
     }
 }
 
@@ -1,4 +1,4 @@
-use datastore::{key::Key, Batch, Txn};
+use datastore::{key::Key, query::SyncResults, Batch, Txn};
 use matches::matches;
 use rand::{self, Rng};
 use std::collections::HashMap;
@@ -54,9 +54,81 @@ fn add_test_cases(db: &RocksDB, testcase: &HashMap<&'static str, &'static str>)
     }
 }
 
+fn query_keys(db: &RocksDB, q: query::Query) -> Vec<String> {
+    let res: query::SyncResult = db.query(q).unwrap();
+    res.rest().into_iter().map(|e| e.key).collect()
+}
+
 #[test]
 fn test_query() {
-    // todo
+    let (db, _) = new_db();
+    let testcase = testcase();
+    add_test_cases(&db, &testcase);
+
+    let q = query::Query {
+        prefix: "/a".to_string(),
+        orders: vec![Box::new(query::order::OrderByKey)],
+        ..Default::default()
+    };
+    let res: query::SyncResult = db.query(q).unwrap();
+    let entries = res.rest();
+    let keys = entries.iter().map(|e| e.key.as_str()).collect::<Vec<_>>();
+    assert_eq!(keys, vec!["/a/b", "/a/b/c", "/a/b/d", "/a/c", "/a/d"]);
+    for e in entries.iter() {
+        assert_eq!(e.value.as_slice(), testcase[e.key.as_str()].as_bytes());
+        assert_eq!(e.size, e.value.len());
+    }
+
+    let q = query::Query {
+        prefix: "/a/".to_string(),
+        orders: vec![Box::new(query::order::OrderByKey)],
+        offset: 1,
+        limit: 2,
+        keys_only: true,
+        ..Default::default()
+    };
+    let res: query::SyncResult = db.query(q).unwrap();
+    let entries = res.rest();
+    let keys = entries.iter().map(|e| e.key.as_str()).collect::<Vec<_>>();
+    assert_eq!(keys, vec!["/a/b/c", "/a/b/d"]);
+    assert!(entries.iter().all(|e| e.value.is_empty()));
+
+    let mut keys = query_keys(&db, Default::default());
+    keys.sort();
+    let mut expect = testcase.keys().map(|k| k.to_string()).collect::<Vec<_>>();
+    expect.sort();
+    assert_eq!(keys, expect);
+}
+
+#[test]
+fn test_query_column() {
+    let dir = tempfile::Builder::new()
+        .prefix("rocksdb")
+        .tempdir()
+        .unwrap();
+    let config = DatabaseConfig::with_columns(vec!["/blocks".to_owned()]);
+    let db = RocksDB::new(dir.path().to_str().unwrap(), &config).unwrap();
+    db.put(Key::new("/blocks/a"), b"a".to_vec()).unwrap();
+    db.put(Key::new("/blocks/b/c"), b"bc".to_vec()).unwrap();
+    db.put(Key::new("/blocksx"), b"x".to_vec()).unwrap();
+    db.put(Key::new("/c"), b"c".to_vec()).unwrap();
+
+    let q = query::Query {
+        prefix: "/blocks".to_string(),
+        orders: vec![Box::new(query::order::OrderByKey)],
+        ..Default::default()
+    };
+    assert_eq!(query_keys(&db, q), vec!["/blocks/a", "/blocks/b/c"]);
+
+    let q = query::Query {
+        prefix: "/blocks/b".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(query_keys(&db, q), vec!["/blocks/b/c"]);
+
+    let mut keys = query_keys(&db, Default::default());
+    keys.sort();
+    assert_eq!(keys, vec!["/blocks/a", "/blocks/b/c", "/blocksx", "/c"]);
 }
 
 #[test]
@@ -156,6 +228,8 @@ fn test_basic_put_get() {
 
     let out = db.get(&k).unwrap();
     assert_eq!(out.as_slice(), v.as_bytes());
+    let out = db.get_bytes(&k).unwrap();
+    assert_eq!(out.as_ref(), v.as_bytes());
 
     let has = db.has(&k).unwrap();
     assert!(has);
@@ -171,6 +245,8 @@ fn test_not_founds() {
     let k = Key::new("notreal");
     let out = db.get(&k);
     assert!(matches!(out, Err(datastore::DSError::NotFound(_))));
+    let out = db.get_bytes(&k);
+    assert!(matches!(out, Err(datastore::DSError::NotFound(_))));
     let has = db.has(&k).unwrap();
     assert!(!has);
 }
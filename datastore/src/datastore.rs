@@ -2,6 +2,8 @@
 
 use std::time::Duration;
 
+use bytes::Bytes;
+
 use crate::error::*;
 use crate::key::Key;
 use crate::query::{self, SyncResults};
@@ -15,6 +17,15 @@ pub trait Write {
 /// Read is the read-side of the Datastore interface.
 pub trait Read {
     fn get(&self, key: &Key) -> Result<Vec<u8>>;
+
+    /// GetBytes returns the value as `Bytes`, which can be shared without copying.
+    /// The default takes the ownership of the value returned by `get`, datastores which
+    /// keep their values as `Bytes`, like the buffered writes of RocksDB, override it to
+    /// share them instead.
+    fn get_bytes(&self, key: &Key) -> Result<Bytes> {
+        self.get(key).map(Bytes::from)
+    }
+
     fn has(&self, key: &Key) -> Result<bool>;
     fn get_size(&self, key: &Key) -> Result<usize>;
 }
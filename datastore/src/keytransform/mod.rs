@@ -4,6 +4,8 @@ mod transforms;
 
 use std::ops::{Deref, DerefMut};
 
+use bytes::Bytes;
+
 use crate::datastore::{Batch, Batching, Datastore as DatastoreT, Read, Write};
 use crate::error::*;
 use crate::key::Key;
@@ -52,6 +54,10 @@ impl<D: DatastoreT, K: KeyTransform> Read for Datastore<D, K> {
         self.child.get(&self.key_transform.convert_key(key))
     }
 
+    fn get_bytes(&self, key: &Key) -> Result<Bytes> {
+        self.child.get_bytes(&self.key_transform.convert_key(key))
+    }
+
     fn has(&self, key: &Key) -> Result<bool> {
         self.child.has(&self.key_transform.convert_key(key))
     }
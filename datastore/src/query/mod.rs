@@ -26,6 +26,7 @@ pub struct Entry {
     // expiration
 }
 
+#[derive(Default)]
 pub struct Query {
     /// namespaces the query to results whose keys have Prefix
     pub prefix: String,
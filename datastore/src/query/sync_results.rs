@@ -1,6 +1,8 @@
 use super::{Entry, Query};
 
 pub trait SyncResults {
+    /// creates the results of `query` from the entries it matched.
+    fn new(query: Query, entries: Vec<Entry>) -> Self;
     fn query(&self) -> &Query;
     fn rest(self) -> Vec<Entry>;
 }
@@ -11,6 +13,13 @@ pub struct SyncResult {
 }
 
 impl SyncResults for SyncResult {
+    fn new(query: Query, entries: Vec<Entry>) -> Self {
+        SyncResult {
+            query,
+            res: entries,
+        }
+    }
+
     fn query(&self) -> &Query {
         &self.query
     }
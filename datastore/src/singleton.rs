@@ -1,3 +1,4 @@
+use bytes::Bytes;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::sync::Arc;
 
@@ -39,6 +40,10 @@ impl<T: Datastore + Sync> Read for SingletonDS<T> {
         self.read().get(key)
     }
 
+    fn get_bytes(&self, key: &Key) -> Result<Bytes> {
+        self.read().get_bytes(key)
+    }
+
     fn has(&self, key: &Key) -> Result<bool> {
         self.read().has(key)
     }
@@ -17,6 +17,8 @@ pub fn test_basic_put_get<D: Datastore>(ds: &D) {
 
     let out = ds.get(&k).unwrap();
     assert_eq!(out.as_slice(), v.as_ref());
+    let out = ds.get_bytes(&k).unwrap();
+    assert_eq!(out.as_ref(), v.as_ref());
     // again after get
     let have = ds.has(&k).unwrap();
     assert!(have);
@@ -36,6 +38,8 @@ pub fn test_not_founds<D: Datastore>(ds: &D) {
     let badk = Key::new("notreal");
     let r = ds.get(&badk);
     assert!(matches!(r, Err(DSError::NotFound(_))));
+    let r = ds.get_bytes(&badk);
+    assert!(matches!(r, Err(DSError::NotFound(_))));
 
     let has = ds.has(&badk).unwrap();
     assert!(!has);
@@ -0,0 +1,132 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::convert::TryFrom;
+
+use block_format::{BasicBlock, Block, BlockFormatError};
+use bytes::Bytes;
+use cid::Cid;
+use datastore::key::Key;
+use datastore::query::{Query, SyncResult, SyncResults};
+use datastore::{DSError, Datastore, SyncQuery};
+
+use crate::error::{BlockstoreError, Result};
+use crate::validating::verify_hash;
+use crate::Blockstore;
+
+/// The prefix of the keys of the blocks in the datastore.
+pub const BLOCK_PREFIX: &str = "/blocks";
+
+/// DatastoreBlockstore stores the blocks in a datastore, each block under the key
+/// `/blocks/<cid>`.
+///
+/// `get_bytes` returns the value of the datastore with `datastore::Read::get_bytes`,
+/// so that the data isn't copied again on the way to the decoders.
+pub struct DatastoreBlockstore<D: Datastore> {
+    ds: D,
+    hash_on_read: bool,
+}
+
+impl<D: Datastore> DatastoreBlockstore<D> {
+    /// Creates a new `DatastoreBlockstore` storing the blocks in `ds`.
+    pub fn new(ds: D) -> Self {
+        DatastoreBlockstore {
+            ds,
+            hash_on_read: false,
+        }
+    }
+
+    /// Returns the underlying datastore.
+    pub fn into_inner(self) -> D {
+        self.ds
+    }
+}
+
+fn block_key(cid: &Cid) -> Key {
+    Key::new(format!("{}/{}", BLOCK_PREFIX, cid))
+}
+
+fn map_err(cid: &Cid, e: DSError) -> BlockstoreError {
+    match e {
+        DSError::NotFound(_) => BlockstoreError::NotFound(cid.clone()),
+        e => BlockstoreError::Datastore(e),
+    }
+}
+
+impl<D: Datastore + SyncQuery> Blockstore for DatastoreBlockstore<D> {
+    fn delete_block(&self, cid: &Cid) -> Result<()> {
+        self.ds.delete(&block_key(cid)).map_err(|e| map_err(cid, e))
+    }
+
+    fn has(&self, cid: &Cid) -> Result<()> {
+        if self.ds.has(&block_key(cid))? {
+            Ok(())
+        } else {
+            Err(BlockstoreError::NotFound(cid.clone()))
+        }
+    }
+
+    /// Get returns the block, its data is always verified when it's wrapped into the block.
+    fn get(&self, cid: &Cid) -> Result<BasicBlock> {
+        let data = self.get_bytes(cid)?;
+        BasicBlock::new_with_cid(data, cid.clone()).map_err(|e| match e {
+            BlockFormatError::WrongHash(_, _) => BlockstoreError::HashMismatch,
+            e => BlockstoreError::Other(Box::new(e)),
+        })
+    }
+
+    /// GetBytes returns the data of the block without copying it again,
+    /// its hash is verified if `hash_on_read` is enabled.
+    fn get_bytes(&self, cid: &Cid) -> Result<Bytes> {
+        let data = self
+            .ds
+            .get_bytes(&block_key(cid))
+            .map_err(|e| map_err(cid, e))?;
+        if self.hash_on_read {
+            verify_hash(cid, &data)?;
+        }
+        Ok(data)
+    }
+
+    fn get_size(&self, cid: &Cid) -> Result<usize> {
+        self.ds
+            .get_size(&block_key(cid))
+            .map_err(|e| map_err(cid, e))
+    }
+
+    /// Put stores the block, unless it's already in the datastore.
+    fn put(&mut self, block: BasicBlock) -> Result<()> {
+        let key = block_key(block.cid());
+        if self.ds.has(&key)? {
+            return Ok(());
+        }
+        self.ds.put(key, block.raw_data().to_vec())?;
+        Ok(())
+    }
+
+    fn put_many(&mut self, blocks: &[BasicBlock]) -> Result<()> {
+        for block in blocks {
+            self.put(block.clone())?;
+        }
+        Ok(())
+    }
+
+    fn hash_on_read(&mut self, enable: bool) {
+        self.hash_on_read = enable;
+    }
+
+    fn all_keys(&self) -> Result<Vec<Cid>> {
+        let query = Query {
+            prefix: BLOCK_PREFIX.to_string(),
+            keys_only: true,
+            ..Default::default()
+        };
+        let entries = self.ds.query::<SyncResult>(query)?.rest();
+        entries
+            .iter()
+            .map(|entry| {
+                let key = Key::new(&entry.key);
+                Cid::try_from(key.base_namespace()).map_err(|e| BlockstoreError::Other(Box::new(e)))
+            })
+            .collect()
+    }
+}
@@ -1,6 +1,6 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
-use cid::Cid;
+use cid::{Cid, Codec, ExtCode};
 
 /// Type alias to use this library's [`BlockstoreError`] type in a `Result`.
 pub type Result<T> = std::result::Result<T, BlockstoreError>;
@@ -17,6 +17,22 @@ pub enum BlockstoreError {
     #[error("block in storage has different hash than requested")]
     HashMismatch,
 
+    /// The codec of the block's CID is not allowed.
+    #[error("codec of block is not allowed: {0:?}")]
+    CodecNotAllowed(Codec),
+
+    /// The hash function of the block's CID is not allowed.
+    #[error("hash function of block is not allowed: {0:?}")]
+    HashNotAllowed(ExtCode),
+
+    /// The block is larger than the maximum block size.
+    #[error("block is too large, size: {0}, max size: {1}")]
+    BlockTooLarge(usize, usize),
+
+    /// The error of the underlying datastore.
+    #[error("datastore error: {0}")]
+    Datastore(#[from] datastore::DSError),
+
     #[error("other err: {0}")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
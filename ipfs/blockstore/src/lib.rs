@@ -1,24 +1,52 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
+mod datastore_blockstore;
 mod error;
+#[cfg(feature = "test-util")]
+mod mem;
+mod validating;
 
-use block_format::BasicBlock;
+use block_format::{BasicBlock, Block};
+use bytes::Bytes;
 use cid::Cid;
 
+pub use crate::datastore_blockstore::{DatastoreBlockstore, BLOCK_PREFIX};
 pub use crate::error::*;
+#[cfg(feature = "test-util")]
+pub use crate::mem::MemBlockstore;
+pub use crate::validating::{ValidatingBlockstore, DEFAULT_MAX_BLOCK_SIZE};
 
 /// Blockstore wraps a Datastore block-centered methods and provides a layer
 /// of abstraction which allows to add different caching strategies.
 // TODO need to decide BasicBlock or BlockT for params
 pub trait Blockstore {
     fn delete_block(&self, cid: &Cid) -> Result<()>;
+
+    /// DeleteMany removes a batch of blocks, implementations backed by a batching
+    /// datastore should override it to commit the deletions at once.
+    fn delete_many(&self, cids: &[Cid]) -> Result<()> {
+        for cid in cids {
+            self.delete_block(cid)?;
+        }
+        Ok(())
+    }
+
     fn has(&self, cid: &Cid) -> Result<()>;
     fn get(&self, cid: &Cid) -> Result<BasicBlock>;
 
+    /// GetBytes returns the raw data of the block, implementations backed by a datastore
+    /// should override it with `datastore::Read::get_bytes` to avoid copying the data.
+    fn get_bytes(&self, cid: &Cid) -> Result<Bytes> {
+        self.get(cid).map(|block| block.to_bytes())
+    }
+
     /// GetSize returns the CIDs mapped BlockSize
     fn get_size(&self, cid: &Cid) -> Result<usize>;
 
     fn put(&mut self, block: BasicBlock) -> Result<()>;
     fn put_many(&mut self, block: &[BasicBlock]) -> Result<()>;
     fn hash_on_read(&mut self, enable: bool);
+
+    /// AllKeys returns the CIDs of all the blocks in the blockstore.
+    fn all_keys(&self) -> Result<Vec<Cid>>;
 }
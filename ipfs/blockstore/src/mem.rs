@@ -0,0 +1,98 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use block_format::{BasicBlock, Block};
+use cid::Cid;
+
+use crate::error::{BlockstoreError, Result};
+use crate::Blockstore;
+
+/// MemBlockstore keeps the blocks in memory, it's shared by the tests of the crates
+/// using a blockstore, with the `test-util` feature.
+#[derive(Default)]
+pub struct MemBlockstore {
+    blocks: RwLock<HashMap<Cid, BasicBlock>>,
+}
+
+impl MemBlockstore {
+    /// Creates an empty `MemBlockstore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of blocks.
+    pub fn len(&self) -> usize {
+        self.blocks
+            .read()
+            .expect("get instance read lock failed")
+            .len()
+    }
+
+    /// Returns whether there is no block.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts the block under the CID without checking that it's the CID of the block,
+    /// e.g. to simulate a corrupted blockstore.
+    pub fn insert_unchecked(&self, cid: Cid, block: BasicBlock) {
+        self.blocks
+            .write()
+            .expect("get instance write lock failed")
+            .insert(cid, block);
+    }
+}
+
+impl Blockstore for MemBlockstore {
+    fn delete_block(&self, cid: &Cid) -> Result<()> {
+        self.blocks
+            .write()
+            .expect("get instance write lock failed")
+            .remove(cid)
+            .map(|_| ())
+            .ok_or_else(|| BlockstoreError::NotFound(cid.clone()))
+    }
+
+    fn has(&self, cid: &Cid) -> Result<()> {
+        self.get(cid).map(|_| ())
+    }
+
+    fn get(&self, cid: &Cid) -> Result<BasicBlock> {
+        self.blocks
+            .read()
+            .expect("get instance read lock failed")
+            .get(cid)
+            .cloned()
+            .ok_or_else(|| BlockstoreError::NotFound(cid.clone()))
+    }
+
+    fn get_size(&self, cid: &Cid) -> Result<usize> {
+        self.get(cid).map(|block| block.raw_data().len())
+    }
+
+    fn put(&mut self, block: BasicBlock) -> Result<()> {
+        self.insert_unchecked(block.cid().clone(), block);
+        Ok(())
+    }
+
+    fn put_many(&mut self, blocks: &[BasicBlock]) -> Result<()> {
+        for block in blocks {
+            self.put(block.clone())?;
+        }
+        Ok(())
+    }
+
+    fn hash_on_read(&mut self, _enable: bool) {}
+
+    fn all_keys(&self) -> Result<Vec<Cid>> {
+        Ok(self
+            .blocks
+            .read()
+            .expect("get instance read lock failed")
+            .keys()
+            .cloned()
+            .collect())
+    }
+}
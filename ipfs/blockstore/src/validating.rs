@@ -0,0 +1,158 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::HashSet;
+
+use block_format::{BasicBlock, Block};
+use bytes::Bytes;
+use cid::{Cid, Codec, ExtCode};
+use multihash::Code;
+
+use crate::error::{BlockstoreError, Result};
+use crate::Blockstore;
+
+/// The default maximum size of a block, 1 MiB.
+pub const DEFAULT_MAX_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// ValidatingBlockstore wraps a Blockstore and validates every block before it's written,
+/// so that untrusted imports can't poison the underlying blockstore.
+///
+/// A block is accepted only if its size doesn't exceed the maximum block size,
+/// the codec and the hash function of its CID are allowed,
+/// and its data re-hashed with the hash function of its CID matches the CID.
+pub struct ValidatingBlockstore<B: Blockstore> {
+    inner: B,
+    codecs: HashSet<Codec>,
+    hashes: HashSet<ExtCode>,
+    max_block_size: usize,
+    hash_on_read: bool,
+}
+
+impl<B: Blockstore> ValidatingBlockstore<B> {
+    /// Creates a new `ValidatingBlockstore` wrapping the `inner` blockstore,
+    /// which allows `raw`, `dag-pb` and `dag-cbor` blocks hashed with SHA2-256 or Blake2b-256,
+    /// up to `DEFAULT_MAX_BLOCK_SIZE` bytes.
+    pub fn new(inner: B) -> Self {
+        ValidatingBlockstore {
+            inner,
+            codecs: [Codec::Raw, Codec::DagProtobuf, Codec::DagCBOR]
+                .iter()
+                .cloned()
+                .collect(),
+            hashes: [Code::Sha2_256, Code::Blake2b256]
+                .iter()
+                .map(|code| (*code).into())
+                .collect(),
+            max_block_size: DEFAULT_MAX_BLOCK_SIZE,
+            hash_on_read: false,
+        }
+    }
+
+    /// Replaces the allowlist of codecs.
+    pub fn with_codecs<I: IntoIterator<Item = Codec>>(mut self, codecs: I) -> Self {
+        self.codecs = codecs.into_iter().collect();
+        self
+    }
+
+    /// Replaces the allowlist of hash functions.
+    pub fn with_hashes<I: IntoIterator<Item = ExtCode>>(mut self, hashes: I) -> Self {
+        self.hashes = hashes.into_iter().collect();
+        self
+    }
+
+    /// Replaces the maximum size of a block.
+    pub fn with_max_block_size(mut self, max_block_size: usize) -> Self {
+        self.max_block_size = max_block_size;
+        self
+    }
+
+    /// Returns the wrapped blockstore.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// Validates the block against the size limit, the allowlists and its CID.
+    pub fn validate(&self, block: &BasicBlock) -> Result<()> {
+        let size = block.raw_data().len();
+        if size > self.max_block_size {
+            return Err(BlockstoreError::BlockTooLarge(size, self.max_block_size));
+        }
+
+        let cid = block.cid();
+        let codec = cid.codec();
+        if !self.codecs.contains(&codec) {
+            return Err(BlockstoreError::CodecNotAllowed(codec));
+        }
+        let hash = cid.hash();
+        let code = hash.algorithm();
+        if !self.hashes.contains(&code) {
+            return Err(BlockstoreError::HashNotAllowed(code));
+        }
+        verify_hash(cid, block.raw_data())
+    }
+}
+
+/// Re-hashes the data of block with the hash function named in the CID.
+pub(crate) fn verify_hash(cid: &Cid, data: &[u8]) -> Result<()> {
+    let hash = cid.hash();
+    if hash != hash.algorithm().digest(data) {
+        return Err(BlockstoreError::HashMismatch);
+    }
+    Ok(())
+}
+
+impl<B: Blockstore> Blockstore for ValidatingBlockstore<B> {
+    fn delete_block(&self, cid: &Cid) -> Result<()> {
+        self.inner.delete_block(cid)
+    }
+
+    fn delete_many(&self, cids: &[Cid]) -> Result<()> {
+        self.inner.delete_many(cids)
+    }
+
+    fn has(&self, cid: &Cid) -> Result<()> {
+        self.inner.has(cid)
+    }
+
+    /// Get returns the block from the wrapped blockstore,
+    /// its hash is verified if `hash_on_read` is enabled.
+    fn get(&self, cid: &Cid) -> Result<BasicBlock> {
+        let block = self.inner.get(cid)?;
+        if self.hash_on_read {
+            verify_hash(cid, block.raw_data())?;
+        }
+        Ok(block)
+    }
+
+    fn get_bytes(&self, cid: &Cid) -> Result<Bytes> {
+        let data = self.inner.get_bytes(cid)?;
+        if self.hash_on_read {
+            verify_hash(cid, &data)?;
+        }
+        Ok(data)
+    }
+
+    fn get_size(&self, cid: &Cid) -> Result<usize> {
+        self.inner.get_size(cid)
+    }
+
+    fn put(&mut self, block: BasicBlock) -> Result<()> {
+        self.validate(&block)?;
+        self.inner.put(block)
+    }
+
+    /// PutMany validates all the blocks first, none of them is written if any is rejected.
+    fn put_many(&mut self, blocks: &[BasicBlock]) -> Result<()> {
+        for block in blocks {
+            self.validate(block)?;
+        }
+        self.inner.put_many(blocks)
+    }
+
+    fn hash_on_read(&mut self, enable: bool) {
+        self.hash_on_read = enable;
+    }
+
+    fn all_keys(&self) -> Result<Vec<Cid>> {
+        self.inner.all_keys()
+    }
+}
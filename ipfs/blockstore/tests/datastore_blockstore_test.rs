@@ -0,0 +1,101 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use bytes::Bytes;
+use cid::{Cid, Codec, IntoExt};
+use matches::matches;
+use multihash::Code;
+use tempfile::TempDir;
+
+use block_format::{BasicBlock, Block};
+use datastore::{key::Key, Write};
+use ds_rocksdb::RocksDB;
+use ipfs_blockstore::{Blockstore, BlockstoreError, DatastoreBlockstore, BLOCK_PREFIX};
+
+fn new_blockstore() -> (DatastoreBlockstore<RocksDB>, TempDir) {
+    let tempdir = tempfile::Builder::new()
+        .prefix("blockstore")
+        .tempdir()
+        .unwrap();
+    let db = RocksDB::new_with_default(tempdir.path().to_str().unwrap()).unwrap();
+    (DatastoreBlockstore::new(db), tempdir)
+}
+
+fn new_block(data: &'static [u8]) -> BasicBlock {
+    let cid = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(data).into_ext());
+    BasicBlock::new_with_cid(Bytes::from_static(data), cid).unwrap()
+}
+
+#[test]
+fn test_put_get() {
+    let (mut bs, _dir) = new_blockstore();
+    let block = new_block(b"block");
+    let other = new_block(b"other");
+    bs.put(block.clone()).unwrap();
+    bs.put_many(&[block.clone(), other.clone()]).unwrap();
+
+    for block in &[&block, &other] {
+        bs.has(block.cid()).unwrap();
+        assert_eq!(bs.get(block.cid()).unwrap().raw_data(), block.raw_data());
+        assert_eq!(bs.get_bytes(block.cid()).unwrap(), block.raw_data());
+        assert_eq!(bs.get_size(block.cid()).unwrap(), block.raw_data().len());
+    }
+
+    bs.delete_block(block.cid()).unwrap();
+    assert!(matches!(
+        bs.has(block.cid()),
+        Err(BlockstoreError::NotFound(_))
+    ));
+    assert!(matches!(
+        bs.get_bytes(block.cid()),
+        Err(BlockstoreError::NotFound(_))
+    ));
+    assert!(bs.get(other.cid()).is_ok());
+}
+
+#[test]
+fn test_hash_on_read() {
+    let (mut bs, _dir) = new_blockstore();
+    let block = new_block(b"block");
+    let ds = bs.into_inner();
+    // a corrupted datastore
+    let key = Key::new(format!("{}/{}", BLOCK_PREFIX, block.cid()));
+    ds.put(key, b"other".to_vec()).unwrap();
+    bs = DatastoreBlockstore::new(ds);
+
+    assert_eq!(bs.get_bytes(block.cid()).unwrap(), &b"other"[..]);
+    assert!(matches!(
+        bs.get(block.cid()),
+        Err(BlockstoreError::HashMismatch)
+    ));
+    bs.hash_on_read(true);
+    assert!(matches!(
+        bs.get_bytes(block.cid()),
+        Err(BlockstoreError::HashMismatch)
+    ));
+}
+
+#[test]
+fn test_all_keys() {
+    let (mut bs, _dir) = new_blockstore();
+    assert!(bs.all_keys().unwrap().is_empty());
+
+    let mut blocks = vec![new_block(b"a"), new_block(b"b"), new_block(b"c")];
+    bs.put_many(&blocks).unwrap();
+    // the keys out of the block namespace are ignored
+    let ds = bs.into_inner();
+    ds.put(Key::new("/blocksx"), b"x".to_vec()).unwrap();
+    ds.put(Key::new("/other/a"), b"a".to_vec()).unwrap();
+    bs = DatastoreBlockstore::new(ds);
+
+    let mut keys = bs.all_keys().unwrap();
+    keys.sort_by_key(|cid| cid.to_string());
+    blocks.sort_by_key(|block| block.cid().to_string());
+    let expect = blocks
+        .iter()
+        .map(|block| block.cid().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, expect);
+
+    bs.delete_block(blocks[0].cid()).unwrap();
+    assert_eq!(bs.all_keys().unwrap(), &expect[1..]);
+}
@@ -0,0 +1,84 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use bytes::Bytes;
+use cid::{Cid, Codec, IntoExt};
+use matches::matches;
+use multihash::Code;
+
+use block_format::{BasicBlock, Block};
+use ipfs_blockstore::{Blockstore, BlockstoreError, MemBlockstore, ValidatingBlockstore};
+
+fn new_block(codec: Codec, code: Code, data: &'static [u8]) -> BasicBlock {
+    let cid = Cid::new_v1(codec, code.digest(data).into_ext());
+    BasicBlock::new_with_cid(Bytes::from_static(data), cid).unwrap()
+}
+
+#[test]
+fn test_put_valid_blocks() {
+    let mut bs = ValidatingBlockstore::new(MemBlockstore::default());
+    let v0 = BasicBlock::new(Bytes::from_static(b"v0"));
+    let raw = new_block(Codec::Raw, Code::Sha2_256, b"raw");
+    let cbor = new_block(Codec::DagCBOR, Code::Blake2b256, b"cbor");
+    bs.put(v0.clone()).unwrap();
+    bs.put_many(&[raw.clone(), cbor.clone()]).unwrap();
+    for block in &[v0, raw, cbor] {
+        assert_eq!(bs.get(block.cid()).unwrap().raw_data(), block.raw_data());
+    }
+}
+
+#[test]
+fn test_reject_codec_and_hash() {
+    let mut bs = ValidatingBlockstore::new(MemBlockstore::default());
+    let git = new_block(Codec::GitRaw, Code::Sha2_256, b"git");
+    let sha3 = new_block(Codec::Raw, Code::Sha3_256, b"sha3");
+    assert!(matches!(
+        bs.put(git.clone()),
+        Err(BlockstoreError::CodecNotAllowed(Codec::GitRaw))
+    ));
+    assert!(matches!(
+        bs.put(sha3.clone()),
+        Err(BlockstoreError::HashNotAllowed(_))
+    ));
+    // nothing is written when a block of the batch is rejected
+    let raw = new_block(Codec::Raw, Code::Sha2_256, b"raw");
+    assert!(bs.put_many(&[raw.clone(), sha3.clone()]).is_err());
+    assert!(bs.has(raw.cid()).is_err());
+
+    let mut bs = bs
+        .with_codecs(vec![Codec::Raw, Codec::GitRaw])
+        .with_hashes(vec![Code::Sha2_256.into(), Code::Sha3_256.into()]);
+    bs.put(git).unwrap();
+    bs.put(sha3).unwrap();
+}
+
+#[test]
+fn test_reject_large_block() {
+    let mut bs = ValidatingBlockstore::new(MemBlockstore::default()).with_max_block_size(4);
+    bs.put(new_block(Codec::Raw, Code::Sha2_256, b"1234"))
+        .unwrap();
+    assert!(matches!(
+        bs.put(new_block(Codec::Raw, Code::Sha2_256, b"12345")),
+        Err(BlockstoreError::BlockTooLarge(5, 4))
+    ));
+}
+
+#[test]
+fn test_hash_on_read() {
+    let block = new_block(Codec::Raw, Code::Sha2_256, b"block");
+    let other = new_block(Codec::Raw, Code::Sha2_256, b"other");
+    let inner = MemBlockstore::default();
+    // a corrupted underlying blockstore
+    inner.insert_unchecked(block.cid().clone(), other);
+
+    let mut bs = ValidatingBlockstore::new(inner);
+    assert!(bs.get(block.cid()).is_ok());
+    bs.hash_on_read(true);
+    assert!(matches!(
+        bs.get(block.cid()),
+        Err(BlockstoreError::HashMismatch)
+    ));
+    assert!(matches!(
+        bs.get_bytes(block.cid()),
+        Err(BlockstoreError::HashMismatch)
+    ));
+}
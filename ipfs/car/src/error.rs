@@ -0,0 +1,51 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+/// Type alias to use this library's [`CarError`] type in a `Result`.
+pub type Result<T> = std::result::Result<T, CarError>;
+
+/// Errors generated from this library.
+#[derive(Debug, thiserror::Error)]
+pub enum CarError {
+    /// IO error.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// CBOR serialization error.
+    #[error("cbor encode error: {0}")]
+    CborEncode(#[from] minicbor::encode::Error<std::io::Error>),
+    /// CBOR deserialization error.
+    #[error("cbor decode error: {0}")]
+    CborDecode(#[from] minicbor::decode::Error),
+    /// CID error.
+    #[error("cid error: {0}")]
+    Cid(#[from] cid::Error),
+    /// Block format error, e.g. the data of a block doesn't match its CID.
+    #[error("block format error: {0}")]
+    BlockFormat(#[from] block_format::BlockFormatError),
+    /// Blockstore error.
+    #[error("blockstore error: {0}")]
+    Blockstore(#[from] ipfs_blockstore::BlockstoreError),
+    /// IPLD format error.
+    #[error("ipld format error: {0}")]
+    Format(#[from] ipld_format::FormatError),
+    /// The varint is invalid or overflows.
+    #[error("invalid varint")]
+    InvalidVarint,
+    /// The header is invalid.
+    #[error("invalid car header: {0}")]
+    InvalidHeader(String),
+    /// The version of the CAR is not supported.
+    #[error("unsupported car version: {0}")]
+    UnsupportedVersion(u64),
+    /// The section is larger than the maximum allowed size.
+    #[error("section is too large, size: {0}, max size: {1}")]
+    SectionTooLarge(u64, u64),
+    /// The section is invalid.
+    #[error("invalid car section: {0}")]
+    InvalidSection(String),
+    /// The codec of the index is not supported.
+    #[error("unsupported index codec: {0:#x}")]
+    UnsupportedIndex(u64),
+    /// Other error.
+    #[error("other err: {0}")]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
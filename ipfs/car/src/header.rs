@@ -0,0 +1,141 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::io::{Read, Write};
+
+use cid::Cid;
+use minicbor::{decode, encode, Decoder, Encoder};
+
+use crate::error::{CarError, Result};
+use crate::util::{read_length_prefixed, write_varint};
+
+/// The pragma at the start of a CARv2, i.e. the length-prefixed CBOR of `{"version": 2}`,
+/// which a CARv1 reader decodes as a header of an unsupported version.
+pub const CAR_V2_PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+];
+
+/// The header of a CARv1, a DAG-CBOR map of the roots and the version.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CarHeader {
+    /// The root CIDs of the DAGs in the CAR.
+    pub roots: Vec<Cid>,
+    /// The version of the CAR.
+    pub version: u64,
+}
+
+impl CarHeader {
+    /// Creates a new CARv1 header with given roots.
+    pub fn new(roots: Vec<Cid>) -> Self {
+        CarHeader { roots, version: 1 }
+    }
+
+    /// Writes the length-prefixed header, returns the number of written bytes.
+    pub(crate) fn write<W: Write>(&self, w: &mut W) -> Result<u64> {
+        let header = minicbor::to_vec(self)?;
+        let len = write_varint(w, header.len() as u64)?;
+        w.write_all(&header)?;
+        Ok((len + header.len()) as u64)
+    }
+
+    /// Reads the length-prefixed header.
+    pub(crate) fn read<R: Read>(r: &mut R) -> Result<Self> {
+        let header =
+            read_length_prefixed(r)?.ok_or_else(|| CarError::InvalidHeader("empty car".into()))?;
+        Ok(minicbor::decode(&header)?)
+    }
+}
+
+// The keys are in the canonical DAG-CBOR order, i.e. shorter keys first.
+impl encode::Encode for CarHeader {
+    fn encode<W: encode::Write>(&self, e: &mut Encoder<W>) -> Result<(), encode::Error<W::Error>> {
+        e.map(2)?.str("roots")?.array(self.roots.len() as u64)?;
+        for root in &self.roots {
+            e.encode(root)?;
+        }
+        e.str("version")?.u64(self.version)?.ok()
+    }
+}
+
+impl<'b> decode::Decode<'b> for CarHeader {
+    fn decode(d: &mut Decoder<'b>) -> Result<Self, decode::Error> {
+        let len = d
+            .map()?
+            .ok_or(decode::Error::Message("indefinite-length header"))?;
+        let mut roots = None;
+        let mut version = None;
+        for _ in 0..len {
+            match d.str()? {
+                "roots" => {
+                    let len = d
+                        .array()?
+                        .ok_or(decode::Error::Message("indefinite-length roots"))?;
+                    let mut cids = Vec::with_capacity(len as usize);
+                    for _ in 0..len {
+                        cids.push(d.decode::<Cid>()?);
+                    }
+                    roots = Some(cids);
+                }
+                "version" => version = Some(d.u64()?),
+                _ => d.skip()?,
+            }
+        }
+        let version = version.ok_or(decode::Error::Message("missing version"))?;
+        // the CARv2 pragma has no roots.
+        if roots.is_none() && version == 1 {
+            return Err(decode::Error::Message("missing roots"));
+        }
+        Ok(CarHeader {
+            roots: roots.unwrap_or_default(),
+            version,
+        })
+    }
+}
+
+/// The fixed-size header of a CARv2, following the pragma.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CarV2Header {
+    /// The characteristics bitfield.
+    pub characteristics: [u8; 16],
+    /// The offset of the inner CARv1 from the start of the CARv2.
+    pub data_offset: u64,
+    /// The size of the inner CARv1.
+    pub data_size: u64,
+    /// The offset of the index from the start of the CARv2, 0 if there's no index.
+    pub index_offset: u64,
+}
+
+impl CarV2Header {
+    /// The size of the encoded header.
+    pub const SIZE: u64 = 40;
+
+    pub(crate) fn write<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&self.characteristics)?;
+        w.write_all(&self.data_offset.to_le_bytes())?;
+        w.write_all(&self.data_size.to_le_bytes())?;
+        w.write_all(&self.index_offset.to_le_bytes())?;
+        Ok(())
+    }
+
+    pub(crate) fn read<R: Read>(r: &mut R) -> Result<Self> {
+        let mut characteristics = [0u8; 16];
+        r.read_exact(&mut characteristics)?;
+        Ok(CarV2Header {
+            characteristics,
+            data_offset: read_u64(r)?,
+            data_size: read_u64(r)?,
+            index_offset: read_u64(r)?,
+        })
+    }
+}
+
+pub(crate) fn read_u64<R: Read>(r: &mut R) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn read_u32<R: Read>(r: &mut R) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
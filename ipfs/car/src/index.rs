@@ -0,0 +1,114 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use cid::Cid;
+
+use crate::error::{CarError, Result};
+use crate::header::{read_u32, read_u64};
+use crate::util::{read_varint, write_varint};
+
+/// The multicodec of the sorted index, `car-index-sorted`.
+pub const INDEX_SORTED_CODEC: u64 = 0x0400;
+
+/// The maximum width of the entries of a bucket, i.e. the largest multihash digest,
+/// 64 bytes, followed by the 8 bytes offset.
+const MAX_ENTRY_WIDTH: u64 = 64 + 8;
+
+/// The index of a CARv2, which maps the multihash digests of the blocks to the offsets
+/// of their sections in the inner CARv1.
+///
+/// It's encoded as `car-index-sorted`, i.e. buckets of entries grouped by width,
+/// each entry being a digest followed by its offset.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Index {
+    entries: BTreeMap<Vec<u8>, Vec<u64>>,
+}
+
+impl Index {
+    /// Creates a new empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of indexed blocks.
+    pub fn len(&self) -> usize {
+        self.entries.values().map(Vec::len).sum()
+    }
+
+    /// Returns true if no block is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds the offset of the section of the block.
+    pub fn insert(&mut self, cid: &Cid, offset: u64) {
+        let digest = cid.hash().digest().to_vec();
+        let offsets = self.entries.entry(digest).or_default();
+        if !offsets.contains(&offset) {
+            offsets.push(offset);
+        }
+    }
+
+    /// Returns the offsets of the sections whose CID has the same digest as `cid`.
+    pub fn get(&self, cid: &Cid) -> &[u64] {
+        self.entries
+            .get(cid.hash().digest())
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Writes the index with its codec.
+    pub(crate) fn write<W: Write>(&self, w: &mut W) -> Result<()> {
+        write_varint(w, INDEX_SORTED_CODEC)?;
+        let mut buckets = BTreeMap::<usize, Vec<(&[u8], u64)>>::new();
+        for (digest, offsets) in &self.entries {
+            let bucket = buckets.entry(digest.len() + 8).or_default();
+            bucket.extend(offsets.iter().map(|offset| (digest.as_slice(), *offset)));
+        }
+        w.write_all(&(buckets.len() as u32).to_le_bytes())?;
+        for (width, entries) in buckets {
+            w.write_all(&(width as u32).to_le_bytes())?;
+            w.write_all(&((width * entries.len()) as u64).to_le_bytes())?;
+            for (digest, offset) in entries {
+                w.write_all(digest)?;
+                w.write_all(&offset.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the index with its codec.
+    pub(crate) fn read<R: Read>(r: &mut R) -> Result<Self> {
+        let codec = read_varint(r)?.ok_or(CarError::InvalidVarint)?;
+        if codec != INDEX_SORTED_CODEC {
+            return Err(CarError::UnsupportedIndex(codec));
+        }
+        let mut index = Index::new();
+        let buckets = read_u32(r)?;
+        for _ in 0..buckets {
+            let width = u64::from(read_u32(r)?);
+            let size = read_u64(r)?;
+            if width <= 8 || width > MAX_ENTRY_WIDTH || size % width != 0 {
+                return Err(CarError::InvalidSection(format!(
+                    "invalid index bucket, width: {}, size: {}",
+                    width, size
+                )));
+            }
+            let mut entry = vec![0u8; width as usize];
+            for _ in 0..size / width {
+                r.read_exact(&mut entry)?;
+                let (digest, offset) = entry.split_at(entry.len() - 8);
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(offset);
+                index
+                    .entries
+                    .entry(digest.to_vec())
+                    .or_default()
+                    .push(u64::from_le_bytes(buf));
+            }
+        }
+        Ok(index)
+    }
+}
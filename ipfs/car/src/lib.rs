@@ -0,0 +1,25 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+//! Implementation of the CAR (Content Addressable aRchive) format in Rust,
+//! which moves DAGs between blockstores as files.
+//! Port from the [Go implementation](https://github.com/ipld/go-car).
+//!
+//! A CARv1 is a DAG-CBOR header with the roots, followed by the length-prefixed sections
+//! of the blocks. A CARv2 wraps a CARv1 with a fixed header and an index of the blocks,
+//! which allows random access to the blocks.
+
+#![deny(missing_docs)]
+
+mod error;
+mod header;
+mod index;
+mod reader;
+mod util;
+mod writer;
+
+pub use self::error::{CarError, Result};
+pub use self::header::{CarHeader, CarV2Header, CAR_V2_PRAGMA};
+pub use self::index::{Index, INDEX_SORTED_CODEC};
+pub use self::reader::{load_car, CarReader, IndexedCarReader};
+pub use self::util::MAX_SECTION_SIZE;
+pub use self::writer::{write_car, write_car_v2, CarWriter};
@@ -0,0 +1,220 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::io::{self, Read, Seek, SeekFrom, Take};
+
+use cid::Cid;
+
+use block_format::BasicBlock;
+use ipfs_blockstore::Blockstore;
+
+use crate::error::{CarError, Result};
+use crate::header::{CarHeader, CarV2Header, CAR_V2_PRAGMA};
+use crate::index::Index;
+use crate::util::{read_length_prefixed, read_section, split_section};
+
+const LOAD_BATCH_SIZE: usize = 128;
+
+/// CarReader reads the blocks of a CARv1 or of the inner CARv1 of a CARv2 sequentially,
+/// the data of every block is verified against its CID.
+pub struct CarReader<R: Read> {
+    reader: Take<R>,
+    header: CarHeader,
+    v2_header: Option<CarV2Header>,
+}
+
+impl<R: Read> CarReader<R> {
+    /// Creates a new `CarReader`, the header is read immediately.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let header = CarHeader::read(&mut reader)?;
+        match header.version {
+            1 => Ok(CarReader {
+                reader: reader.take(u64::max_value()),
+                header,
+                v2_header: None,
+            }),
+            2 => {
+                let v2_header = CarV2Header::read(&mut reader)?;
+                let skip = v2_header
+                    .data_offset
+                    .checked_sub(CAR_V2_PRAGMA.len() as u64 + CarV2Header::SIZE)
+                    .ok_or_else(|| CarError::InvalidHeader("invalid data offset".into()))?;
+                io::copy(&mut (&mut reader).take(skip), &mut io::sink())?;
+                let mut reader = reader.take(v2_header.data_size);
+                let header = read_v1_header(&mut reader)?;
+                Ok(CarReader {
+                    reader,
+                    header,
+                    v2_header: Some(v2_header),
+                })
+            }
+            version => Err(CarError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Returns the header of the CARv1.
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// Returns the roots of the CAR.
+    pub fn roots(&self) -> &[Cid] {
+        &self.header.roots
+    }
+
+    /// Returns the header of the CARv2, if the CAR is a CARv2.
+    pub fn v2_header(&self) -> Option<&CarV2Header> {
+        self.v2_header.as_ref()
+    }
+
+    /// Reads the next block, returns `None` at the end of the CAR.
+    pub fn next_block(&mut self) -> Result<Option<BasicBlock>> {
+        read_section(&mut self.reader)
+    }
+}
+
+impl<R: Read> Iterator for CarReader<R> {
+    type Item = Result<BasicBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+/// LoadCar reads all the blocks of a CARv1 or CARv2 into the blockstore,
+/// returns the header of the CARv1.
+pub fn load_car<BS, R>(bs: &mut BS, reader: R) -> Result<CarHeader>
+where
+    BS: Blockstore + ?Sized,
+    R: Read,
+{
+    let mut car = CarReader::new(reader)?;
+    let mut batch = Vec::with_capacity(LOAD_BATCH_SIZE);
+    while let Some(block) = car.next_block()? {
+        batch.push(block);
+        if batch.len() == LOAD_BATCH_SIZE {
+            bs.put_many(&batch)?;
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        bs.put_many(&batch)?;
+    }
+    Ok(car.header)
+}
+
+/// IndexedCarReader gives random access to the blocks of a CARv2 with its index,
+/// without reading the whole CAR.
+///
+/// If the CARv2 has no index, it's built by scanning the sections of the inner CARv1,
+/// skipping the blocks as they are not verified.
+pub struct IndexedCarReader<R: Read + Seek> {
+    reader: R,
+    start: u64,
+    header: CarHeader,
+    v2_header: CarV2Header,
+    index: Index,
+}
+
+impl<R: Read + Seek> IndexedCarReader<R> {
+    /// Creates a new `IndexedCarReader`, the headers and the index are read immediately.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let start = reader.seek(SeekFrom::Current(0))?;
+        let pragma = CarHeader::read(&mut reader)?;
+        if pragma.version != 2 {
+            return Err(CarError::UnsupportedVersion(pragma.version));
+        }
+        let v2_header = CarV2Header::read(&mut reader)?;
+        let data_start = add_offset(start, v2_header.data_offset)?;
+        reader.seek(SeekFrom::Start(data_start))?;
+        let header = read_v1_header(&mut reader)?;
+
+        let index = if v2_header.index_offset != 0 {
+            reader.seek(SeekFrom::Start(add_offset(start, v2_header.index_offset)?))?;
+            Index::read(&mut reader)?
+        } else {
+            let mut index = Index::new();
+            let mut offset = sub_offset(reader.seek(SeekFrom::Current(0))?, data_start)?;
+            let mut data = (&mut reader).take(sub_offset(v2_header.data_size, offset)?);
+            while let Some(section) = read_length_prefixed(&mut data)? {
+                let (cid, _) = split_section(section)?;
+                index.insert(&cid, offset);
+                offset = sub_offset(v2_header.data_size, data.limit())?;
+            }
+            index
+        };
+
+        Ok(IndexedCarReader {
+            reader,
+            start,
+            header,
+            v2_header,
+            index,
+        })
+    }
+
+    /// Returns the header of the inner CARv1.
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// Returns the roots of the CAR.
+    pub fn roots(&self) -> &[Cid] {
+        &self.header.roots
+    }
+
+    /// Returns the header of the CARv2.
+    pub fn v2_header(&self) -> &CarV2Header {
+        &self.v2_header
+    }
+
+    /// Returns the index of the blocks.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Reads the block with given CID, returns `None` if it's not in the CAR.
+    pub fn get(&mut self, cid: &Cid) -> Result<Option<BasicBlock>> {
+        let data_start = add_offset(self.start, self.v2_header.data_offset)?;
+        for offset in self.index.get(cid).to_vec() {
+            self.reader
+                .seek(SeekFrom::Start(add_offset(data_start, offset)?))?;
+            let section = read_length_prefixed(&mut self.reader)?
+                .ok_or_else(|| CarError::InvalidSection("index offset out of range".into()))?;
+            let (found, data) = split_section(section)?;
+            // the index is keyed by digests, the codec may not match.
+            if &found == cid {
+                return Ok(Some(BasicBlock::new_with_cid(data, found)?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Adds the offset read from the CAR to the position, which must not overflow.
+fn add_offset(pos: u64, offset: u64) -> Result<u64> {
+    pos.checked_add(offset)
+        .ok_or_else(|| CarError::InvalidSection(format!("offset out of range: {}", offset)))
+}
+
+/// Subtracts the offset read from the CAR from the position, which must not underflow.
+fn sub_offset(pos: u64, offset: u64) -> Result<u64> {
+    pos.checked_sub(offset)
+        .ok_or_else(|| CarError::InvalidSection(format!("offset out of range: {}", offset)))
+}
+
+/// Reads the header of a CARv1, which must be version 1.
+fn read_v1_header<R: Read>(reader: &mut R) -> Result<CarHeader> {
+    let header = CarHeader::read(reader)?;
+    if header.version != 1 {
+        return Err(CarError::InvalidHeader(format!(
+            "inner car has version {}",
+            header.version
+        )));
+    }
+    Ok(header)
+}
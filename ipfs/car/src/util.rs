@@ -0,0 +1,138 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use bytes::Bytes;
+use cid::Cid;
+
+use block_format::BasicBlock;
+
+use crate::error::{CarError, Result};
+
+/// The maximum size of a header or a section, 32 MiB.
+pub const MAX_SECTION_SIZE: u64 = 32 << 20;
+
+/// The maximum length of an unsigned varint encoding a u64.
+const MAX_VARINT_LEN: usize = 10;
+
+/// Writes the unsigned varint, returns the number of written bytes.
+pub(crate) fn write_varint<W: Write>(w: &mut W, mut n: u64) -> Result<usize> {
+    let mut buf = [0u8; MAX_VARINT_LEN];
+    let mut len = 0;
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    w.write_all(&buf[..len])?;
+    Ok(len)
+}
+
+/// Reads the unsigned varint, returns `None` if the reader is at EOF.
+pub(crate) fn read_varint<R: Read>(r: &mut R) -> Result<Option<u64>> {
+    let mut n = 0u64;
+    for i in 0..MAX_VARINT_LEN {
+        let mut byte = [0u8];
+        if let Err(e) = r.read_exact(&mut byte) {
+            return match e.kind() {
+                io::ErrorKind::UnexpectedEof if i == 0 => Ok(None),
+                io::ErrorKind::UnexpectedEof => Err(CarError::InvalidVarint),
+                _ => Err(e.into()),
+            };
+        }
+        let byte = byte[0];
+        if i == MAX_VARINT_LEN - 1 && byte > 1 {
+            return Err(CarError::InvalidVarint);
+        }
+        n |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(Some(n));
+        }
+    }
+    Err(CarError::InvalidVarint)
+}
+
+/// Decodes the unsigned varint at the start of the buffer,
+/// returns the value and the number of bytes read.
+fn decode_varint(buf: &[u8]) -> Result<(u64, usize)> {
+    let mut reader = buf;
+    let n = read_varint(&mut reader)?.ok_or(CarError::InvalidVarint)?;
+    Ok((n, buf.len() - reader.len()))
+}
+
+/// Reads the length-prefixed data, returns `None` if the reader is at EOF.
+pub(crate) fn read_length_prefixed<R: Read>(r: &mut R) -> Result<Option<Vec<u8>>> {
+    let len = match read_varint(r)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+    if len > MAX_SECTION_SIZE {
+        return Err(CarError::SectionTooLarge(len, MAX_SECTION_SIZE));
+    }
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+    Ok(Some(buf))
+}
+
+/// Returns the length of the binary CID at the start of the buffer.
+fn cid_len(buf: &[u8]) -> Result<usize> {
+    // CIDv0 is a bare SHA2-256 multihash.
+    if buf.len() >= 2 && buf[0] == 0x12 && buf[1] == 0x20 {
+        return Ok(34);
+    }
+    let mut offset = 0;
+    let (version, n) = decode_varint(buf)?;
+    if version != 1 {
+        return Err(CarError::InvalidSection(format!(
+            "invalid cid version: {}",
+            version
+        )));
+    }
+    offset += n;
+    // codec and multihash code
+    for _ in 0..2 {
+        offset += decode_varint(&buf[offset..])?.1;
+    }
+    let (digest_len, n) = decode_varint(&buf[offset..])?;
+    usize::try_from(digest_len)
+        .ok()
+        .and_then(|digest_len| (offset + n).checked_add(digest_len))
+        .filter(|offset| *offset <= buf.len())
+        .ok_or_else(|| CarError::InvalidSection("truncated cid".into()))
+}
+
+/// Writes the section of the block, i.e. the varint length, the binary CID and the data.
+/// Returns the number of written bytes.
+pub(crate) fn write_section<W: Write>(w: &mut W, cid: &Cid, data: &[u8]) -> Result<u64> {
+    let cid = cid.to_bytes();
+    let len = write_varint(w, (cid.len() + data.len()) as u64)?;
+    w.write_all(&cid)?;
+    w.write_all(data)?;
+    Ok((len + cid.len() + data.len()) as u64)
+}
+
+/// Splits the section into the CID and the data of the block, without verifying the data.
+pub(crate) fn split_section(section: Vec<u8>) -> Result<(Cid, Bytes)> {
+    let len = cid_len(&section)?;
+    let cid = Cid::try_from(&section[..len])?;
+    Ok((cid, Bytes::from(section).slice(len..)))
+}
+
+/// Reads the section of a block, returns `None` if the reader is at EOF.
+/// The data of the block is verified against its CID.
+pub(crate) fn read_section<R: Read>(r: &mut R) -> Result<Option<BasicBlock>> {
+    match read_length_prefixed(r)? {
+        Some(section) => {
+            let (cid, data) = split_section(section)?;
+            Ok(Some(BasicBlock::new_with_cid(data, cid)?))
+        }
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::HashSet;
+use std::io::{Seek, SeekFrom, Write};
+
+use cid::{Cid, Codec};
+
+use block_format::{BasicBlock, Block};
+use ipfs_blockstore::Blockstore;
+
+use crate::error::Result;
+use crate::header::{CarHeader, CarV2Header, CAR_V2_PRAGMA};
+use crate::index::Index;
+use crate::util::write_section;
+
+/// CarWriter writes a CARv1, i.e. the header followed by the sections of the blocks.
+pub struct CarWriter<W: Write> {
+    w: W,
+    offset: u64,
+}
+
+impl<W: Write> CarWriter<W> {
+    /// Creates a new `CarWriter`, the header with given roots is written immediately.
+    pub fn new(mut w: W, roots: Vec<Cid>) -> Result<Self> {
+        let offset = CarHeader::new(roots).write(&mut w)?;
+        Ok(CarWriter { w, offset })
+    }
+
+    /// Writes the section of the block,
+    /// returns the offset of the section from the start of the CARv1.
+    pub fn write_block<B: Block + ?Sized>(&mut self, block: &B) -> Result<u64> {
+        let offset = self.offset;
+        self.offset += write_section(&mut self.w, block.cid(), block.raw_data())?;
+        Ok(offset)
+    }
+
+    /// Returns the number of bytes written so far.
+    pub fn size(&self) -> u64 {
+        self.offset
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.w.flush()?;
+        Ok(self.w)
+    }
+}
+
+/// WriteCar writes the DAGs of the roots from the blockstore as a CARv1.
+///
+/// The DAGs are walked in depth-first order following the links of every block in order,
+/// so the same DAGs are always written the same way, every block is written once.
+pub fn write_car<BS, W>(bs: &BS, roots: &[Cid], w: W) -> Result<()>
+where
+    BS: Blockstore + ?Sized,
+    W: Write,
+{
+    let mut writer = CarWriter::new(w, roots.to_vec())?;
+    walk_dag(bs, roots, |block| writer.write_block(block).map(|_| ()))?;
+    writer.into_inner()?;
+    Ok(())
+}
+
+/// WriteCarV2 writes the DAGs of the roots from the blockstore as a CARv2,
+/// i.e. a CARv1 written like [`write_car`], wrapped with the CARv2 header and followed by
+/// the index of the blocks.
+pub fn write_car_v2<BS, W>(bs: &BS, roots: &[Cid], mut w: W) -> Result<()>
+where
+    BS: Blockstore + ?Sized,
+    W: Write + Seek,
+{
+    let start = w.seek(SeekFrom::Current(0))?;
+    w.write_all(&CAR_V2_PRAGMA)?;
+    // the header is rewritten once the sizes are known.
+    CarV2Header::default().write(&mut w)?;
+    let data_offset = CAR_V2_PRAGMA.len() as u64 + CarV2Header::SIZE;
+
+    let mut index = Index::new();
+    let mut writer = CarWriter::new(&mut w, roots.to_vec())?;
+    walk_dag(bs, roots, |block| {
+        let offset = writer.write_block(block)?;
+        index.insert(block.cid(), offset);
+        Ok(())
+    })?;
+    let data_size = writer.size();
+    index.write(&mut w)?;
+
+    let end = w.seek(SeekFrom::Current(0))?;
+    w.seek(SeekFrom::Start(start + CAR_V2_PRAGMA.len() as u64))?;
+    let header = CarV2Header {
+        data_offset,
+        data_size,
+        index_offset: data_offset + data_size,
+        ..Default::default()
+    };
+    header.write(&mut w)?;
+    w.seek(SeekFrom::Start(end))?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Walks the DAGs of the roots in depth-first order, calling `f` once on every block.
+fn walk_dag<BS, F>(bs: &BS, roots: &[Cid], mut f: F) -> Result<()>
+where
+    BS: Blockstore + ?Sized,
+    F: FnMut(&BasicBlock) -> Result<()>,
+{
+    let mut visited = HashSet::new();
+    let mut stack = roots.iter().rev().cloned().collect::<Vec<_>>();
+    while let Some(cid) = stack.pop() {
+        if !visited.insert(cid.clone()) {
+            continue;
+        }
+        let block = bs.get(&cid)?;
+        f(&block)?;
+        // raw blocks are always leaves.
+        if cid.codec() != Codec::Raw {
+            let node = ipld_format::coding::decode(&block)?;
+            stack.extend(node.links().into_iter().rev().map(|link| link.cid.clone()));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,208 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+use bytes::Bytes;
+use cid::{Cid, Codec, IntoExt};
+use matches::matches;
+use multihash::Code;
+
+use block_format::{BasicBlock, Block};
+use ipfs_blockstore::{Blockstore, BlockstoreError, MemBlockstore};
+use ipfs_car::{
+    load_car, write_car, write_car_v2, CarError, CarReader, CarV2Header, IndexedCarReader,
+    CAR_V2_PRAGMA,
+};
+use ipld_core::{IpldNode, IpldValue};
+
+fn put_node(bs: &mut MemBlockstore, name: &str, links: Vec<&Cid>) -> Cid {
+    let mut map = BTreeMap::new();
+    map.insert("name".into(), IpldValue::String(name.to_string()));
+    map.insert(
+        "links".into(),
+        IpldValue::List(links.into_iter().cloned().map(IpldValue::Link).collect()),
+    );
+    let node = IpldNode::wrap_object(&IpldValue::Map(map), Code::Sha2_256.into()).unwrap();
+    let cid = node.cid().clone();
+    bs.put(BasicBlock::new_with_cid(node.to_bytes(), cid.clone()).unwrap())
+        .unwrap();
+    cid
+}
+
+fn put_raw(bs: &mut MemBlockstore, data: &'static [u8]) -> Cid {
+    let cid = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(data).into_ext());
+    bs.put(BasicBlock::new_with_cid(Bytes::from_static(data), cid.clone()).unwrap())
+        .unwrap();
+    cid
+}
+
+/// root1 -> (a -> (raw1, raw2), raw2), root2 -> (a, b), lonely
+fn setup() -> (MemBlockstore, Vec<Cid>) {
+    let mut bs = MemBlockstore::default();
+    let raw1 = put_raw(&mut bs, b"raw1");
+    let raw2 = put_raw(&mut bs, b"raw2");
+    let a = put_node(&mut bs, "a", vec![&raw1, &raw2]);
+    let b = put_node(&mut bs, "b", vec![]);
+    let root1 = put_node(&mut bs, "root1", vec![&a, &raw2]);
+    let root2 = put_node(&mut bs, "root2", vec![&a, &b]);
+    put_node(&mut bs, "lonely", vec![]);
+    (bs, vec![root1, root2, a, b, raw1, raw2])
+}
+
+#[test]
+fn test_car_v1() {
+    let (bs, cids) = setup();
+    let roots = cids[..2].to_vec();
+    let mut car = vec![];
+    write_car(&bs, &roots, &mut car).unwrap();
+
+    // the same DAGs are always written the same way
+    let mut again = vec![];
+    write_car(&bs, &roots, &mut again).unwrap();
+    assert_eq!(car, again);
+
+    let reader = CarReader::new(car.as_slice()).unwrap();
+    assert_eq!(reader.roots(), roots.as_slice());
+    assert!(reader.v2_header().is_none());
+    let order = reader
+        .map(|block| block.unwrap().cid().clone())
+        .collect::<Vec<_>>();
+    let expect = [0, 2, 4, 5, 1, 3]
+        .iter()
+        .map(|i| cids[*i].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(order, expect);
+
+    let mut loaded = MemBlockstore::default();
+    let header = load_car(&mut loaded, car.as_slice()).unwrap();
+    assert_eq!(header.roots, roots);
+    assert_eq!(header.version, 1);
+    assert_eq!(loaded.len(), 6);
+    for cid in &cids {
+        assert_eq!(
+            loaded.get(cid).unwrap().raw_data(),
+            bs.get(cid).unwrap().raw_data()
+        );
+    }
+}
+
+#[test]
+fn test_car_v1_errors() {
+    let (bs, cids) = setup();
+    let mut car = vec![];
+    write_car(&bs, &cids[..1], &mut car).unwrap();
+
+    // the data of the last block doesn't match its CID
+    let mut corrupted = car.clone();
+    *corrupted.last_mut().unwrap() ^= 0xff;
+    let mut loaded = MemBlockstore::default();
+    let r = load_car(&mut loaded, corrupted.as_slice());
+    assert!(matches!(r, Err(CarError::BlockFormat(_))));
+
+    // truncated section
+    let r = load_car(&mut loaded, &car[..car.len() - 1]);
+    assert!(matches!(r, Err(CarError::Io(_))));
+
+    // missing block
+    let mut bs = bs;
+    bs.delete_block(&cids[4]).unwrap();
+    let r = write_car(&bs, &cids[..1], vec![]);
+    assert!(matches!(
+        r,
+        Err(CarError::Blockstore(BlockstoreError::NotFound(_)))
+    ));
+}
+
+#[test]
+fn test_car_v2() {
+    let (bs, cids) = setup();
+    let roots = cids[..2].to_vec();
+    let mut car = Cursor::new(vec![]);
+    write_car_v2(&bs, &roots, &mut car).unwrap();
+    let car = car.into_inner();
+    assert_eq!(&car[..CAR_V2_PRAGMA.len()], &CAR_V2_PRAGMA[..]);
+
+    // the inner CARv1 is the same as the CARv1
+    let mut v1 = vec![];
+    write_car(&bs, &roots, &mut v1).unwrap();
+    let data_offset = CAR_V2_PRAGMA.len() + CarV2Header::SIZE as usize;
+    assert_eq!(&car[data_offset..data_offset + v1.len()], v1.as_slice());
+
+    let mut reader = IndexedCarReader::new(Cursor::new(car.clone())).unwrap();
+    assert_eq!(reader.roots(), roots.as_slice());
+    assert_eq!(reader.v2_header().data_size, v1.len() as u64);
+    assert_eq!(reader.index().len(), 6);
+    for cid in cids.iter().rev() {
+        let block = reader.get(cid).unwrap().unwrap();
+        assert_eq!(block.raw_data(), bs.get(cid).unwrap().raw_data());
+    }
+    let missing = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(b"missing").into_ext());
+    assert!(reader.get(&missing).unwrap().is_none());
+    // same digest, different codec
+    let other_codec = Cid::new_v1(Codec::DagCBOR, cids[4].hash().to_owned());
+    assert!(reader.get(&other_codec).unwrap().is_none());
+
+    // CARv2 can be read sequentially too
+    let mut loaded = MemBlockstore::default();
+    let header = load_car(&mut loaded, car.as_slice()).unwrap();
+    assert_eq!(header.roots, roots);
+    assert_eq!(loaded.len(), 6);
+}
+
+#[test]
+fn test_car_v2_without_index() {
+    let (bs, cids) = setup();
+    let mut car = Cursor::new(vec![]);
+    write_car_v2(&bs, &cids[..1], &mut car).unwrap();
+    let mut car = car.into_inner();
+
+    // drop the index and clear its offset
+    let reader = IndexedCarReader::new(Cursor::new(car.clone())).unwrap();
+    let index = reader.index().clone();
+    let index_offset = reader.v2_header().index_offset as usize;
+    car.truncate(index_offset);
+    let pos = CAR_V2_PRAGMA.len() + 32;
+    car[pos..pos + 8].copy_from_slice(&0u64.to_le_bytes());
+
+    let mut reader = IndexedCarReader::new(Cursor::new(car)).unwrap();
+    assert_eq!(reader.index(), &index);
+    let block = reader.get(&cids[5]).unwrap().unwrap();
+    assert_eq!(block.raw_data(), &b"raw2"[..]);
+}
+
+#[test]
+fn test_car_v2_invalid_offsets() {
+    let (bs, cids) = setup();
+    let mut car = Cursor::new(vec![]);
+    write_car_v2(&bs, &cids[..1], &mut car).unwrap();
+    let car = car.into_inner();
+    let reader = IndexedCarReader::new(Cursor::new(car.clone())).unwrap();
+    let index_offset = reader.v2_header().index_offset as usize;
+    let header = CAR_V2_PRAGMA.len();
+
+    // the offset overflows when added to the start of the CAR
+    let mut overflow = vec![0];
+    overflow.extend_from_slice(&car);
+    let pos = 1 + header + 32;
+    overflow[pos..pos + 8].copy_from_slice(&u64::max_value().to_le_bytes());
+    let mut cursor = Cursor::new(overflow);
+    cursor.set_position(1);
+    let r = IndexedCarReader::new(cursor);
+    assert!(matches!(r, Err(CarError::InvalidSection(_))));
+
+    // the data size is smaller than the header of the inner CARv1
+    let mut small = car[..index_offset].to_vec();
+    small[header + 24..header + 32].copy_from_slice(&1u64.to_le_bytes());
+    small[header + 32..header + 40].copy_from_slice(&0u64.to_le_bytes());
+    let r = IndexedCarReader::new(Cursor::new(small));
+    assert!(matches!(r, Err(CarError::InvalidSection(_))));
+
+    // the width of the index entries is larger than the largest digest
+    let mut wide = car;
+    // codec varint and number of buckets
+    let pos = index_offset + 2 + 4;
+    wide[pos..pos + 4].copy_from_slice(&u32::max_value().to_le_bytes());
+    let r = IndexedCarReader::new(Cursor::new(wide));
+    assert!(matches!(r, Err(CarError::InvalidSection(_))));
+}
@@ -0,0 +1,35 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use cid::Cid;
+
+/// Type alias to use this library's [`GcError`] type in a `Result`.
+pub type Result<T> = std::result::Result<T, GcError>;
+
+/// Errors generated from this library.
+#[derive(Debug, thiserror::Error)]
+pub enum GcError {
+    /// Blockstore error.
+    #[error("blockstore error: {0}")]
+    Blockstore(#[from] ipfs_blockstore::BlockstoreError),
+    /// Datastore error.
+    #[error("datastore error: {0}")]
+    Datastore(#[from] datastore::DSError),
+    /// The links of a block could not be fetched while computing the live set.
+    #[error("could not retrieve links for {0}: {1}")]
+    CannotFetchLinks(Cid, Box<dyn std::error::Error + Send + Sync>),
+    /// The links of some live blocks could not be fetched, nothing was removed.
+    #[error("garbage collection aborted: could not retrieve some links")]
+    CannotFetchAllLinks,
+    /// A block could not be removed from the blockstore.
+    #[error("error removing key from blockstore: {0}, reason: {1}")]
+    CannotDeleteBlock(Cid, ipfs_blockstore::BlockstoreError),
+    /// Some blocks could not be removed from the blockstore.
+    #[error("garbage collection incomplete: could not delete some keys")]
+    CannotDeleteSomeBlocks,
+    /// The computation of the live set was stopped, see `colored_set`.
+    #[error("garbage collection stopped")]
+    Stopped,
+    /// Other error.
+    #[error("other err: {0}")]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
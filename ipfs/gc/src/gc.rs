@@ -0,0 +1,277 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+
+use cid::{Cid, Codec};
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use futures::executor::block_on;
+use futures::SinkExt;
+
+use datastore::{key::Key, Datastore};
+use ipfs_blockstore::{Blockstore, BlockstoreError};
+
+use crate::error::{GcError, Result};
+use crate::pin::Pinner;
+
+const RESULT_BUF_SIZE: usize = 128;
+const DEFAULT_BATCH_SIZE: usize = 128;
+
+/// The events reported by a garbage collection run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GcEvent {
+    /// The mark phase finished, with the number of live blocks.
+    Marked(usize),
+    /// A block was removed from the blockstore (or would be, in dry-run mode).
+    Removed(Cid),
+    /// The sweep phase has checked `checked` out of `total` blocks.
+    Progress {
+        /// The number of blocks checked so far.
+        checked: usize,
+        /// The number of blocks in the blockstore.
+        total: usize,
+    },
+}
+
+/// The item of the stream returned by [`gc`].
+pub type GcResult = Result<GcEvent>;
+
+/// The options of a garbage collection run.
+#[derive(Clone, Debug)]
+pub struct GcOptions {
+    /// Only report the blocks that would be removed, without removing them.
+    pub dry_run: bool,
+    /// The number of blocks removed from the blockstore at once.
+    pub batch_size: usize,
+}
+
+impl Default for GcOptions {
+    fn default() -> Self {
+        GcOptions {
+            dry_run: false,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+/// GC performs a mark and sweep garbage collection of the blocks in the blockstore.
+///
+/// First all blocks reachable from the pinned roots and the `best_effort_roots` are marked
+/// as live, then every other block is removed from the blockstore, and the datastore is synced.
+/// The blocks of `best_effort_roots` that are missing from the blockstore are silently ignored.
+///
+/// The collection runs in a background thread, the returned stream reports the progress,
+/// the removed CIDs and the errors, it ends when the collection is finished.
+/// Dropping the stream stops the collection at the next reported event.
+pub fn gc<BS, DS, P>(
+    bs: Arc<BS>,
+    ds: DS,
+    pinner: Arc<P>,
+    best_effort_roots: Vec<Cid>,
+    options: GcOptions,
+) -> Receiver<GcResult>
+where
+    BS: Blockstore + Send + Sync + 'static,
+    DS: Datastore,
+    P: Pinner + Send + Sync + 'static,
+{
+    let (tx, rx) = channel(RESULT_BUF_SIZE);
+    thread::spawn(move || {
+        let mut output = Output(tx);
+        if let Err(e) = run(
+            &*bs,
+            &ds,
+            &*pinner,
+            &best_effort_roots,
+            &options,
+            &mut output,
+        ) {
+            output.send(Err(e));
+        }
+    });
+    rx
+}
+
+struct Output(Sender<GcResult>);
+
+impl Output {
+    /// Returns false when the stream has been dropped.
+    fn send(&mut self, result: GcResult) -> bool {
+        block_on(self.0.send(result)).is_ok()
+    }
+}
+
+fn run<BS, DS, P>(
+    bs: &BS,
+    ds: &DS,
+    pinner: &P,
+    best_effort_roots: &[Cid],
+    options: &GcOptions,
+    output: &mut Output,
+) -> Result<()>
+where
+    BS: Blockstore,
+    DS: Datastore,
+    P: Pinner,
+{
+    let live = colored_set(bs, pinner, best_effort_roots, |e| output.send(Err(e)))?;
+    if !output.send(Ok(GcEvent::Marked(live.len()))) {
+        return Ok(());
+    }
+
+    let keys = bs.all_keys()?;
+    let total = keys.len();
+    let batch_size = options.batch_size.max(1);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut has_errors = false;
+    for (index, cid) in keys.into_iter().enumerate() {
+        if !live.contains(&cid) {
+            batch.push(cid);
+        }
+        let checked = index + 1;
+        if batch.len() < batch_size && checked < total {
+            continue;
+        }
+        let removed = sweep(bs, &batch, options.dry_run, output);
+        batch.clear();
+        match removed {
+            Some(ok) => has_errors = has_errors || !ok,
+            None => return Ok(()),
+        }
+        if !output.send(Ok(GcEvent::Progress { checked, total })) {
+            return Ok(());
+        }
+    }
+
+    if has_errors {
+        output.send(Err(GcError::CannotDeleteSomeBlocks));
+    }
+    if !options.dry_run {
+        // Nothing has been written in dry-run mode.
+        ds.sync(&Key::new(""))?;
+    }
+    Ok(())
+}
+
+/// Removes a batch of blocks, falling back to removing them one by one to report
+/// the blocks that can't be removed.
+/// Returns whether all blocks have been removed, or `None` if the stream has been dropped.
+fn sweep<BS: Blockstore>(
+    bs: &BS,
+    batch: &[Cid],
+    dry_run: bool,
+    output: &mut Output,
+) -> Option<bool> {
+    if batch.is_empty() {
+        return Some(true);
+    }
+    if dry_run || bs.delete_many(batch).is_ok() {
+        for cid in batch {
+            if !output.send(Ok(GcEvent::Removed(cid.clone()))) {
+                return None;
+            }
+        }
+        return Some(true);
+    }
+
+    let mut ok = true;
+    for cid in batch {
+        let result = match bs.delete_block(cid) {
+            Ok(()) | Err(BlockstoreError::NotFound(_)) => Ok(GcEvent::Removed(cid.clone())),
+            Err(e) => {
+                ok = false;
+                Err(GcError::CannotDeleteBlock(cid.clone(), e))
+            }
+        };
+        if !output.send(result) {
+            return None;
+        }
+    }
+    Some(ok)
+}
+
+/// ColoredSet computes the set of live blocks, i.e. the blocks reachable from the
+/// recursive and internal pins and from the `best_effort_roots`, plus the direct pins.
+///
+/// Every block whose links can't be fetched is reported to `on_error`, and if any of
+/// them is reachable from a pin, `GcError::CannotFetchAllLinks` is returned.
+/// `on_error` returns whether to go on, the traversal stops as soon as it returns false
+/// and `GcError::Stopped` is returned.
+pub fn colored_set<BS, P, F>(
+    bs: &BS,
+    pinner: &P,
+    best_effort_roots: &[Cid],
+    mut on_error: F,
+) -> Result<HashSet<Cid>>
+where
+    BS: Blockstore + ?Sized,
+    P: Pinner + ?Sized,
+    F: FnMut(GcError) -> bool,
+{
+    let mut live = HashSet::new();
+    let mut has_errors = false;
+
+    let mut get_links = |cid: &Cid, best_effort: bool| match links(bs, cid) {
+        Ok(links) => Ok(links),
+        Err(GcError::Blockstore(BlockstoreError::NotFound(_))) if best_effort => Ok(vec![]),
+        Err(e) => {
+            has_errors = has_errors || !best_effort;
+            if on_error(GcError::CannotFetchLinks(cid.clone(), Box::new(e))) {
+                Ok(vec![])
+            } else {
+                Err(GcError::Stopped)
+            }
+        }
+    };
+
+    for root in pinner.recursive_keys()? {
+        walk(&root, &mut live, |cid| get_links(cid, false))?;
+    }
+    for root in best_effort_roots {
+        walk(root, &mut live, |cid| get_links(cid, true))?;
+    }
+    for root in pinner.internal_pins()? {
+        walk(&root, &mut live, |cid| get_links(cid, false))?;
+    }
+    live.extend(pinner.direct_keys()?);
+
+    if has_errors {
+        return Err(GcError::CannotFetchAllLinks);
+    }
+    Ok(live)
+}
+
+/// Walks the DAG from `root` in depth-first order, adding every visited block to `visited`.
+/// The blocks which have been visited already are not walked again,
+/// the walk stops at the first error of `get_links`.
+fn walk<F>(root: &Cid, visited: &mut HashSet<Cid>, mut get_links: F) -> Result<()>
+where
+    F: FnMut(&Cid) -> Result<Vec<Cid>>,
+{
+    let mut stack = vec![root.clone()];
+    while let Some(cid) = stack.pop() {
+        if visited.contains(&cid) {
+            continue;
+        }
+        stack.extend(get_links(&cid)?.into_iter().rev());
+        visited.insert(cid);
+    }
+    Ok(())
+}
+
+/// Returns the CIDs linked by the block, decoded with the registered decoder of its codec.
+fn links<BS: Blockstore + ?Sized>(bs: &BS, cid: &Cid) -> Result<Vec<Cid>> {
+    // raw blocks are always leaves, no need to fetch them.
+    if cid.codec() == Codec::Raw {
+        return Ok(vec![]);
+    }
+    let data = bs.get_bytes(cid)?;
+    let node =
+        ipld_format::coding::decode_bytes(cid, data).map_err(|e| GcError::Other(Box::new(e)))?;
+    Ok(node
+        .links()
+        .into_iter()
+        .map(|link| link.cid.clone())
+        .collect())
+}
@@ -0,0 +1,14 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+//! A mark-and-sweep garbage collector for the blockstore in Rust.
+//! Port from the [Go implementation](https://github.com/ipfs/go-ipfs/tree/master/pin/gc).
+
+#![deny(missing_docs)]
+
+mod error;
+mod gc;
+mod pin;
+
+pub use self::error::{GcError, Result};
+pub use self::gc::{colored_set, gc, GcEvent, GcOptions, GcResult};
+pub use self::pin::Pinner;
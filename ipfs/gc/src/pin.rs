@@ -0,0 +1,18 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use cid::Cid;
+
+use crate::error::Result;
+
+/// Pinner is the pin set the garbage collector computes the live set from.
+pub trait Pinner {
+    /// Returns the keys pinned directly, only the block itself is kept.
+    fn direct_keys(&self) -> Result<Vec<Cid>>;
+
+    /// Returns the keys pinned recursively, the whole DAG below them is kept.
+    fn recursive_keys(&self) -> Result<Vec<Cid>>;
+
+    /// Returns the keys used by the pinner itself to store the pin set,
+    /// the DAGs below them are kept as well.
+    fn internal_pins(&self) -> Result<Vec<Cid>>;
+}
@@ -0,0 +1,212 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use cid::{Cid, Codec, IntoExt};
+use futures::executor::block_on_stream;
+use matches::matches;
+use multihash::Code;
+use tempfile::TempDir;
+
+use block_format::{BasicBlock, Block};
+use datastore::basic_ds::new_map_datastore;
+use ds_rocksdb::RocksDB;
+use ipfs_blockstore::{Blockstore, DatastoreBlockstore, MemBlockstore};
+use ipfs_gc::{colored_set, gc, GcError, GcEvent, GcOptions, Pinner, Result};
+use ipld_core::{IpldNode, IpldValue};
+
+#[derive(Default)]
+struct MockPinner {
+    direct: Vec<Cid>,
+    recursive: Vec<Cid>,
+}
+
+impl Pinner for MockPinner {
+    fn direct_keys(&self) -> Result<Vec<Cid>> {
+        Ok(self.direct.clone())
+    }
+
+    fn recursive_keys(&self) -> Result<Vec<Cid>> {
+        Ok(self.recursive.clone())
+    }
+
+    fn internal_pins(&self) -> Result<Vec<Cid>> {
+        Ok(vec![])
+    }
+}
+
+fn put_node<B: Blockstore>(bs: &mut B, name: &str, links: Vec<&Cid>) -> Cid {
+    let mut map = BTreeMap::new();
+    map.insert("name".into(), IpldValue::String(name.to_string()));
+    map.insert(
+        "links".into(),
+        IpldValue::List(links.into_iter().cloned().map(IpldValue::Link).collect()),
+    );
+    let node = IpldNode::wrap_object(&IpldValue::Map(map), Code::Sha2_256.into()).unwrap();
+    let cid = node.cid().clone();
+    let block = BasicBlock::new_with_cid(node.to_bytes(), cid.clone()).unwrap();
+    bs.put(block).unwrap();
+    cid
+}
+
+/// root -> a -> b, orphan -> b, direct, lonely
+fn put_nodes<B: Blockstore>(bs: &mut B) -> Vec<Cid> {
+    let b = put_node(bs, "b", vec![]);
+    let a = put_node(bs, "a", vec![&b]);
+    let root = put_node(bs, "root", vec![&a]);
+    let orphan = put_node(bs, "orphan", vec![&b]);
+    let direct = put_node(bs, "direct", vec![&orphan]);
+    let lonely = put_node(bs, "lonely", vec![]);
+    vec![root, a, b, orphan, direct, lonely]
+}
+
+fn setup() -> (MemBlockstore, Vec<Cid>) {
+    let mut bs = MemBlockstore::default();
+    let cids = put_nodes(&mut bs);
+    (bs, cids)
+}
+
+fn setup_rocksdb() -> (DatastoreBlockstore<RocksDB>, Vec<Cid>, TempDir) {
+    let tempdir = tempfile::Builder::new().prefix("gc").tempdir().unwrap();
+    let db = RocksDB::new_with_default(tempdir.path().to_str().unwrap()).unwrap();
+    let mut bs = DatastoreBlockstore::new(db);
+    let cids = put_nodes(&mut bs);
+    (bs, cids, tempdir)
+}
+
+fn removed(events: &[ipfs_gc::GcResult]) -> Vec<Cid> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Ok(GcEvent::Removed(cid)) => Some(cid.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn check_gc<B: Blockstore + Send + Sync + 'static>(bs: B, cids: Vec<Cid>) {
+    let bs = Arc::new(bs);
+    let pinner = Arc::new(MockPinner {
+        direct: vec![cids[4].clone()],
+        recursive: vec![cids[0].clone()],
+    });
+
+    let options = GcOptions {
+        batch_size: 2,
+        ..Default::default()
+    };
+    let stream = gc(bs.clone(), new_map_datastore(), pinner, vec![], options);
+    let events = block_on_stream(stream).collect::<Vec<_>>();
+    assert!(events.iter().all(|event| event.is_ok()));
+    assert_eq!(events[0].as_ref().unwrap(), &GcEvent::Marked(4));
+
+    let mut removed = removed(&events);
+    removed.sort_by_key(|cid| cid.to_string());
+    let mut expect = vec![cids[3].clone(), cids[5].clone()];
+    expect.sort_by_key(|cid| cid.to_string());
+    assert_eq!(removed, expect);
+
+    if let Ok(GcEvent::Progress { checked, total }) = events.last().unwrap() {
+        assert_eq!(*checked, 6);
+        assert_eq!(*total, 6);
+    } else {
+        unreachable!()
+    }
+
+    let mut keys = bs.all_keys().unwrap();
+    keys.sort_by_key(|cid| cid.to_string());
+    let mut expect = cids[..3]
+        .iter()
+        .chain(&cids[4..5])
+        .cloned()
+        .collect::<Vec<_>>();
+    expect.sort_by_key(|cid| cid.to_string());
+    assert_eq!(keys, expect);
+}
+
+#[test]
+fn test_gc() {
+    let (bs, cids) = setup();
+    check_gc(bs, cids);
+}
+
+#[test]
+fn test_gc_rocksdb() {
+    let (bs, cids, _dir) = setup_rocksdb();
+    check_gc(bs, cids);
+}
+
+#[test]
+fn test_gc_dry_run_and_best_effort_roots() {
+    let (bs, cids) = setup();
+    let bs = Arc::new(bs);
+    let pinner = Arc::new(MockPinner::default());
+
+    let options = GcOptions {
+        dry_run: true,
+        ..Default::default()
+    };
+    let missing = Cid::new_v1(Codec::DagCBOR, Code::Sha2_256.digest(b"missing").into_ext());
+    let roots = vec![cids[3].clone(), missing];
+    let stream = gc(bs.clone(), new_map_datastore(), pinner, roots, options);
+    let events = block_on_stream(stream).collect::<Vec<_>>();
+    assert!(events.iter().all(|event| event.is_ok()));
+    // the orphan and its child `b` are kept alive.
+    assert_eq!(removed(&events).len(), 4);
+    // nothing is removed in dry-run mode
+    assert_eq!(bs.all_keys().unwrap().len(), 6);
+}
+
+#[test]
+fn test_gc_missing_pinned_block() {
+    let (bs, cids) = setup();
+    bs.delete_block(&cids[1]).unwrap();
+    let bs = Arc::new(bs);
+    let pinner = Arc::new(MockPinner {
+        direct: vec![],
+        recursive: vec![cids[0].clone()],
+    });
+
+    let stream = gc(
+        bs.clone(),
+        new_map_datastore(),
+        pinner,
+        vec![],
+        GcOptions::default(),
+    );
+    let events = block_on_stream(stream).collect::<Vec<_>>();
+    assert!(matches!(events[0], Err(GcError::CannotFetchLinks(_, _))));
+    assert!(matches!(events[1], Err(GcError::CannotFetchAllLinks)));
+    assert_eq!(events.len(), 2);
+    // nothing is removed when the live set is incomplete
+    assert_eq!(bs.all_keys().unwrap().len(), 5);
+}
+
+#[test]
+fn test_colored_set_stop() {
+    let (bs, cids) = setup();
+    bs.delete_block(&cids[1]).unwrap();
+    bs.delete_block(&cids[3]).unwrap();
+    let pinner = MockPinner {
+        direct: vec![],
+        recursive: vec![cids[0].clone(), cids[4].clone()],
+    };
+
+    let mut errors = 0;
+    let result = colored_set(&bs, &pinner, &[], |_| {
+        errors += 1;
+        true
+    });
+    assert!(matches!(result, Err(GcError::CannotFetchAllLinks)));
+    assert_eq!(errors, 2);
+
+    // the traversal stops at the first error
+    let mut errors = 0;
+    let result = colored_set(&bs, &pinner, &[], |_| {
+        errors += 1;
+        false
+    });
+    assert!(matches!(result, Err(GcError::Stopped)));
+    assert_eq!(errors, 1);
+}
@@ -1,23 +1,40 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::{Arc, PoisonError, RwLock};
 
-use cid::{Cid, Codec, Prefix, Version};
+use cid::{Cid, Codec};
 use serde::{de::DeserializeOwned, Serialize};
 
-use block_format::{BasicBlock, Block as BlockT};
+use block_format::{Block as BlockT, BlockBuilder};
 use ipfs_blockstore::Blockstore;
+use ipld_core::{dag_cbor, from_ipld, to_ipld};
 
 use crate::error::*;
 
+/// Returns the builder of AMT blocks, which are CIDv1 `dag-cbor` blocks hashed with Blake2b-256.
+pub fn block_builder() -> BlockBuilder {
+    BlockBuilder::new_v1(Codec::DagCBOR, multihash::Code::Blake2b256.into())
+}
+
 pub trait Blocks {
     fn get<Output: DeserializeOwned>(&self, cid: &Cid) -> Result<Output>;
     fn put<Input: Serialize>(&mut self, v: Input) -> Result<Cid>;
 }
 
+/// `Blocks` of a `Blockstore`, the objects are encoded in DAG-CBOR.
+///
+/// The clones share the blockstore, which is behind a lock so that an `Amt` over them
+/// could be sent to another thread.
 pub struct BStoreWrapper<BS: Blockstore> {
-    bs: Rc<RefCell<BS>>,
+    bs: Arc<RwLock<BS>>,
+}
+
+impl<BS: Blockstore> BStoreWrapper<BS> {
+    pub fn new(bs: BS) -> Self {
+        BStoreWrapper {
+            bs: Arc::new(RwLock::new(bs)),
+        }
+    }
 }
 
 impl<BS: Blockstore> Clone for BStoreWrapper<BS> {
@@ -30,23 +47,24 @@ impl<BS: Blockstore> Clone for BStoreWrapper<BS> {
 
 impl<BS: Blockstore> Blocks for BStoreWrapper<BS> {
     fn get<Output: DeserializeOwned>(&self, cid: &Cid) -> Result<Output> {
-        let r = self.bs.borrow().get(cid)?;
-        let o: Output = serde_cbor::from_slice(r.raw_data().as_ref())?;
+        // a panic of another thread doesn't leave the blockstore in an invalid state
+        let r = self
+            .bs
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(cid)?;
+        let o: Output = from_ipld(dag_cbor::decode(r.raw_data().as_ref())?)?;
         Ok(o)
     }
 
     fn put<Input: Serialize>(&mut self, v: Input) -> Result<Cid> {
-        let v = serde_cbor::to_vec(&v)?;
-        let prefix = Prefix {
-            version: Version::V1,
-            codec: Codec::DagCBOR,
-            mh_type: multihash::Code::Blake2b256,
-            mh_len: 32,
-        };
-        let cid = Cid::new_from_prefix(&prefix, v.as_ref());
-
-        let blk = BasicBlock::new_with_cid(v.into(), cid.clone())?;
-        self.bs.borrow_mut().put(blk)?;
+        let v = dag_cbor::encode(&to_ipld(&v)?)?;
+        let blk = block_builder().build(v.into())?;
+        let cid = blk.cid().clone();
+        self.bs
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put(blk)?;
         Ok(cid)
     }
 }
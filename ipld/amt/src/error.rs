@@ -5,10 +5,7 @@ pub type Result<T> = std::result::Result<T, AmtIpldError>;
 #[derive(Debug, thiserror::Error)]
 pub enum AmtIpldError {
     #[error("blockstore error, err:{0}")]
-    Blockstore(#[from] blockstore::BlockstoreError),
-
-    #[error("core de/serialize error: {0}")]
-    Cbor(#[from] serde_cbor::Error),
+    Blockstore(#[from] ipfs_blockstore::BlockstoreError),
 
     #[error("cid error: {0}")]
     Cid(#[from] cid::Error),
@@ -25,6 +22,18 @@ pub enum AmtIpldError {
     #[error("no node found at (sub)index: {0}")]
     NoNodeForIndex(usize),
 
+    #[error("invalid bit width: {0}")]
+    InvalidBitWidth(u32),
+
+    #[error("invalid height {height} for the bit width {bit_width}")]
+    InvalidHeight { height: u64, bit_width: u32 },
+
+    #[error("invalid bitmap of {len} bytes for the bit width {bit_width}")]
+    InvalidBitmap { len: usize, bit_width: u32 },
+
+    #[error("invalid node at height {0}, its bitmap doesn't match its branches or values")]
+    InvalidNode(u64),
+
     #[error("other err: {0}")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
@@ -7,6 +7,8 @@ mod node;
 #[cfg(test)]
 mod tests;
 
-pub use crate::blocks::Blocks;
+pub use crate::blocks::{block_builder, BStoreWrapper, Blocks};
 pub use crate::error::*;
-pub use crate::node::Amt;
+pub use crate::node::{
+    diff, diff_with_bitwidth, Amt, Change, Iter, DEFAULT_BIT_WIDTH, MAX_BIT_WIDTH,
+};
@@ -0,0 +1,249 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cid::Cid;
+
+use ipld_core::IpldValue;
+
+use super::{
+    check_bit_width, check_height, keys_per_branch, Item, Node, PartAmt, DEFAULT_BIT_WIDTH,
+};
+use crate::blocks::Blocks;
+use crate::error::*;
+
+/// A change of a key between two roots of an `Amt`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// the key is only in the new root.
+    Add { key: u64, value: IpldValue },
+    /// the key is only in the old root.
+    Remove { key: u64, value: IpldValue },
+    /// the key is in both roots, with different values.
+    Modify {
+        key: u64,
+        before: IpldValue,
+        after: IpldValue,
+    },
+}
+
+impl Change {
+    /// the changed key.
+    pub fn key(&self) -> u64 {
+        match self {
+            Change::Add { key, .. } | Change::Remove { key, .. } | Change::Modify { key, .. } => {
+                *key
+            }
+        }
+    }
+}
+
+/// Returns the changes from the `Amt` of `old_root` to the one of `new_root`, in key order.
+pub fn diff<B: Blocks>(bs: &B, old_root: &Cid, new_root: &Cid) -> Result<Vec<Change>> {
+    diff_with_bitwidth(bs, old_root, new_root, DEFAULT_BIT_WIDTH)
+}
+
+/// Returns the changes from the `Amt` of `old_root` to the one of `new_root`, in key order,
+/// both are built with the bit_width.
+///
+/// The two trees are walked side by side, the branches with the same cid are equal and
+/// skipped without being loaded. When the heights are different, the lower tree is the
+/// first branch of the higher one, and the other branches are loaded.
+pub fn diff_with_bitwidth<B: Blocks>(
+    bs: &B,
+    old_root: &Cid,
+    new_root: &Cid,
+    bit_width: u32,
+) -> Result<Vec<Change>> {
+    check_bit_width(bit_width)?;
+    let mut changes = vec![];
+    if old_root == new_root {
+        return Ok(changes);
+    }
+
+    let old: PartAmt = bs.get(old_root)?;
+    check_height(bit_width, old.0)?;
+    old.2.check(bit_width, old.0)?;
+    let new: PartAmt = bs.get(new_root)?;
+    check_height(bit_width, new.0)?;
+    new.2.check(bit_width, new.0)?;
+    let (mut old_height, mut old_node) = (old.0, Some(old.2));
+    let (mut new_height, mut new_node) = (new.0, Some(new.2));
+
+    let mut before = BTreeMap::new();
+    while old_height > new_height {
+        old_node = split_first_branch(bs, bit_width, old_node, old_height, &mut before)?;
+        old_height -= 1;
+    }
+    let mut after = BTreeMap::new();
+    while new_height > old_height {
+        new_node = split_first_branch(bs, bit_width, new_node, new_height, &mut after)?;
+        new_height -= 1;
+    }
+    diff_entries(before, after, &mut changes);
+
+    diff_node(
+        bs,
+        bit_width,
+        old_node,
+        new_node,
+        old_height,
+        0,
+        &mut changes,
+    )?;
+    changes.sort_by_key(Change::key);
+    Ok(changes)
+}
+
+/// the branches of the node indexed by their bit position.
+fn indexed_branches(node: Node) -> BTreeMap<usize, Item> {
+    node.positions()
+        .into_iter()
+        .zip(node.branches.into_inner())
+        .collect()
+}
+
+/// the values of the leaf node indexed by their key.
+fn indexed_leafs(node: Node, offset: u64) -> BTreeMap<u64, IpldValue> {
+    node.positions()
+        .into_iter()
+        .map(|pos| offset + pos as u64)
+        .zip(node.leafs)
+        .collect()
+}
+
+/// returns the first branch of the node at the root of the tree, and collects the
+/// key/values of the other branches.
+fn split_first_branch<B: Blocks>(
+    bs: &B,
+    bit_width: u32,
+    node: Option<Node>,
+    height: u64,
+    entries: &mut BTreeMap<u64, IpldValue>,
+) -> Result<Option<Node>> {
+    let node = match node {
+        Some(node) => node,
+        None => return Ok(None),
+    };
+    let keys_per_branch = keys_per_branch(bit_width, height);
+    let mut first = None;
+    for (pos, branch) in indexed_branches(node) {
+        let node = branch.into_node(bs, bit_width, height - 1)?;
+        if pos == 0 {
+            first = Some(node);
+        } else {
+            let offset = pos as u64 * keys_per_branch;
+            collect_entries(bs, bit_width, node, height - 1, offset, entries)?;
+        }
+    }
+    Ok(first)
+}
+
+fn diff_node<B: Blocks>(
+    bs: &B,
+    bit_width: u32,
+    old: Option<Node>,
+    new: Option<Node>,
+    height: u64,
+    offset: u64,
+    changes: &mut Vec<Change>,
+) -> Result<()> {
+    let (old, new) = match (old, new) {
+        (Some(old), Some(new)) => (old, new),
+        (old, new) => {
+            let mut before = BTreeMap::new();
+            if let Some(node) = old {
+                collect_entries(bs, bit_width, node, height, offset, &mut before)?;
+            }
+            let mut after = BTreeMap::new();
+            if let Some(node) = new {
+                collect_entries(bs, bit_width, node, height, offset, &mut after)?;
+            }
+            diff_entries(before, after, changes);
+            return Ok(());
+        }
+    };
+
+    if height == 0 {
+        diff_entries(
+            indexed_leafs(old, offset),
+            indexed_leafs(new, offset),
+            changes,
+        );
+        return Ok(());
+    }
+
+    let keys_per_branch = keys_per_branch(bit_width, height);
+    let mut old = indexed_branches(old);
+    let mut new = indexed_branches(new);
+    let positions = old
+        .keys()
+        .chain(new.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    for pos in positions {
+        let offset = offset + pos as u64 * keys_per_branch;
+        match (old.remove(&pos), new.remove(&pos)) {
+            // the subtrees are equal if the cids are the same
+            (Some(Item::Link(old)), Some(Item::Link(new))) if old == new => {}
+            (old, new) => {
+                let old = old
+                    .map(|b| b.into_node(bs, bit_width, height - 1))
+                    .transpose()?;
+                let new = new
+                    .map(|b| b.into_node(bs, bit_width, height - 1))
+                    .transpose()?;
+                diff_node(bs, bit_width, old, new, height - 1, offset, changes)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// collect all key/values of the node, loading the links.
+fn collect_entries<B: Blocks>(
+    bs: &B,
+    bit_width: u32,
+    node: Node,
+    height: u64,
+    offset: u64,
+    entries: &mut BTreeMap<u64, IpldValue>,
+) -> Result<()> {
+    if height == 0 {
+        entries.extend(indexed_leafs(node, offset));
+        return Ok(());
+    }
+    let keys_per_branch = keys_per_branch(bit_width, height);
+    for (pos, branch) in indexed_branches(node) {
+        let node = branch.into_node(bs, bit_width, height - 1)?;
+        let offset = offset + pos as u64 * keys_per_branch;
+        collect_entries(bs, bit_width, node, height - 1, offset, entries)?;
+    }
+    Ok(())
+}
+
+fn diff_entries(
+    mut before: BTreeMap<u64, IpldValue>,
+    after: BTreeMap<u64, IpldValue>,
+    changes: &mut Vec<Change>,
+) {
+    for (key, value) in after {
+        match before.remove(&key) {
+            None => changes.push(Change::Add { key, value }),
+            Some(old) => {
+                if old != value {
+                    changes.push(Change::Modify {
+                        key,
+                        before: old,
+                        after: value,
+                    })
+                }
+            }
+        }
+    }
+    changes.extend(
+        before
+            .into_iter()
+            .map(|(key, value)| Change::Remove { key, value }),
+    );
+}
@@ -1,8 +1,12 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
-use serde_cbor::Value;
+use std::iter::Zip;
+use std::slice;
+use std::vec;
 
-use super::{Amt, Item, Node, BITS_PER_SUBKEY, WIDTH};
+use ipld_core::IpldValue;
+
+use super::{keys_per_branch, Amt, Item, Node};
 use crate::blocks::Blocks;
 use crate::error::*;
 
@@ -13,9 +17,17 @@ where
     /// this function would use in anywhere to traverse the trie, do not need flush first.
     pub fn for_each<F>(&self, f: &mut F) -> Result<()>
     where
-        F: FnMut(u64, &Value) -> Result<()>,
+        F: FnMut(u64, &IpldValue) -> Result<()>,
+    {
+        self.for_each_while(&mut |key, value| f(key, value).map(|_| true))
+    }
+
+    /// like `for_each`, but stop the traversal when `f` returns `false`.
+    pub fn for_each_while<F>(&self, f: &mut F) -> Result<()>
+    where
+        F: FnMut(u64, &IpldValue) -> Result<bool>,
     {
-        traversing(&self.bs, &self.root, self.height, 0, f)
+        traversing(&self.bs, self.bit_width, &self.root, self.height, 0, f).map(|_| ())
     }
 
     /// Subtract removes all elements of 'or' from 'self'
@@ -28,154 +40,150 @@ where
             }
         })
     }
+
+    /// iterate the key/values in key order, the `Link` branches are loaded when they're
+    /// reached, so that it could be used before `flush()`.
+    pub fn iter(&mut self) -> Iter<'_, B> {
+        self.iter_from(0)
+    }
+
+    /// iterate the key/values from the key `start` in key order, the branches before it
+    /// are skipped without being loaded.
+    pub fn iter_from(&mut self, start: u64) -> Iter<'_, B> {
+        let mut iter = Iter {
+            bs: &self.bs,
+            bit_width: self.bit_width,
+            start,
+            stack: vec![],
+            leafs: None,
+        };
+        iter.push(&mut self.root, self.height, 0);
+        iter
+    }
 }
 
-fn traversing<B, F>(bs: &B, node: &Node, height: u64, prefix_key: u64, f: &mut F) -> Result<()>
+/// returns whether the traversal goes on.
+fn traversing<B, F>(
+    bs: &B,
+    bit_width: u32,
+    node: &Node,
+    height: u64,
+    offset: u64,
+    f: &mut F,
+) -> Result<bool>
 where
     B: Blocks,
-    F: FnMut(u64, &Value) -> Result<()>,
+    F: FnMut(u64, &IpldValue) -> Result<bool>,
 {
-    let prefix = prefix_key << BITS_PER_SUBKEY;
+    let keys_per_branch = keys_per_branch(bit_width, height);
     if height == 0 {
-        for i in 0..WIDTH {
-            if node.get_bit(i) {
-                let current_key = prefix + i as u64;
-                let index = node.bit_to_index(i);
-                f(current_key, &node.leafs[index])?;
+        for (pos, value) in node.positions().into_iter().zip(node.leafs.iter()) {
+            if !f(offset + pos as u64, value)? {
+                return Ok(false);
             }
         }
-        return Ok(());
+        return Ok(true);
     }
 
     let mut branches = node.branches.borrow_mut();
 
-    for i in 0..WIDTH {
-        if node.get_bit(i) {
-            let current_key = prefix + i as u64;
-            let index = node.bit_to_index(i);
-            branches[index].load_item(bs)?;
+    for (pos, branch) in node.positions().into_iter().zip(branches.iter_mut()) {
+        let current_key = offset + pos as u64 * keys_per_branch;
+        branch.load_item(bs, bit_width, height - 1)?;
 
-            if let Item::Ptr(node) = &branches[index] {
-                traversing(bs, node, height - 1, current_key, f)?;
-            } else {
-                unreachable!("after `load_item`, Item must be `Ptr`")
+        if let Item::Ptr(node) = branch {
+            if !traversing(bs, bit_width, node, height - 1, current_key, f)? {
+                return Ok(false);
             }
-        }
-    }
-    Ok(())
-}
-
-impl<B> Amt<B>
-where
-    B: Blocks,
-{
-    /// `iter()` is equal to `for_each` now(could use before `flush()`).
-    /// but `iter()` would cast more resource
-    pub fn iter(&self) -> Iter<B> {
-        let node_ref = &self.root;
-
-        let prefix_key_list = (0..WIDTH)
-            .map(|prefix_key| (prefix_key, node_ref.get_bit(prefix_key)))
-            .filter(|(_, need)| *need)
-            .map(|(pref, _)| pref as u64)
-            .collect::<Vec<_>>();
-
-        let init_node = &self.root as *const Node;
-
-        let init = if self.height == 0 {
-            Traversing::Leaf(0, (prefix_key_list, init_node))
         } else {
-            Traversing::Branch(prefix_key_list, init_node)
-        };
-        Iter {
-            size: self.count,
-            count: 0,
-            stack: vec![init],
-            bs: &self.bs,
+            unreachable!("after `load_item`, Item must be `Ptr`")
         }
     }
+    Ok(true)
 }
 
-/// this `Iter` only could be used for FlushedRoot, due to current module use child_cache to store child,
-/// and the child ref is under `RefCell`. So that we could only iterate the tree after flushing.
-/// if someone do not what iterating the free after flushing, could use `for_each`.
+type Branches<'a> = Zip<vec::IntoIter<u64>, slice::IterMut<'a, Item>>;
+type Leafs<'a> = Zip<vec::IntoIter<u64>, slice::Iter<'a, IpldValue>>;
+
+/// Iterator over the key/values of an `Amt` in key order.
+///
+/// `Link` branches are loaded from the store when they're reached, and replaced by their
+/// node like in `Amt::get`, so that a following traversal doesn't load them again.
 pub struct Iter<'a, B>
 where
     B: Blocks,
 {
-    size: u64,
-    count: u64,
-    stack: Vec<Traversing>,
     // blocks ref, use for load node from cid
     bs: &'a B,
+    bit_width: u32,
+    /// the keys before it are skipped.
+    start: u64,
+    /// the branches left of every node from the root to the current one, with the height
+    /// of the node and the first key of every branch.
+    stack: Vec<(u64, Branches<'a>)>,
+    /// the values left of the current leaf node, with their keys.
+    leafs: Option<Leafs<'a>>,
 }
 
-enum Traversing {
-    Leaf(usize, (Vec<u64>, *const Node)),
-    Branch(Vec<u64>, *const Node),
+impl<'a, B> Iter<'a, B>
+where
+    B: Blocks,
+{
+    /// push the branches or the values of the node whose first key is `offset`.
+    fn push(&mut self, node: &'a mut Node, height: u64, offset: u64) {
+        let keys_per_branch = keys_per_branch(self.bit_width, height);
+        let mut keys = node
+            .positions()
+            .into_iter()
+            .map(|pos| offset + pos as u64 * keys_per_branch)
+            .collect::<Vec<_>>();
+        // the branches whose last key is before `start`
+        let skip = keys
+            .iter()
+            .take_while(|key| key.saturating_add(keys_per_branch - 1) < self.start)
+            .count();
+        let keys = keys.split_off(skip).into_iter();
+        if height == 0 {
+            self.leafs = Some(keys.zip(node.leafs[skip..].iter()));
+        } else {
+            let branches = node.branches.get_mut()[skip..].iter_mut();
+            self.stack.push((height, keys.zip(branches)));
+        }
+    }
 }
 
 impl<'a, B> Iterator for Iter<'a, B>
 where
     B: Blocks,
 {
-    type Item = (u64, &'a Value);
+    type Item = Result<(u64, &'a IpldValue)>;
 
-    /// it's safe to use unsafe here, for except root node, every node is in heap,
-    /// and be refered from root node. thus we use unsafe to avoid lifetime check
-    /// and mutable check.
-    /// notice iterator would load node for cid, thus after iter, all tree is in
-    /// `Ptr` mode, in other word, is being expanded
     fn next(&mut self) -> Option<Self::Item> {
-        let last = match self.stack.pop() {
-            Some(last) => last,
-            None => {
-                return None;
+        loop {
+            if let Some((key, value)) = self.leafs.as_mut().and_then(|leafs| leafs.next()) {
+                return Some(Ok((key, value)));
             }
-        };
-        match last {
-            Traversing::Leaf(pos, (keys, leaf_node)) => {
-                let r = unsafe { (*leaf_node).leafs.get(pos).map(|v| (keys[pos], v)) };
-                match r {
-                    Some(v) => {
-                        let pos = pos + 1;
-                        self.stack.push(Traversing::Leaf(pos, (keys, leaf_node)));
-                        self.count += 1;
-                        Some(v)
-                    }
-                    None => self.next(),
+            self.leafs = None;
+
+            let (height, branches) = self.stack.last_mut()?;
+            let height = *height;
+            let (offset, branch) = match branches.next() {
+                Some(branch) => branch,
+                None => {
+                    // all branches of the current node are visited, back to the parent
+                    self.stack.pop();
+                    continue;
                 }
+            };
+            if let Err(e) = branch.load_item(self.bs, self.bit_width, height - 1) {
+                // the traversal can't go on, stop after returning the error
+                self.stack.clear();
+                return Some(Err(e));
             }
-            Traversing::Branch(keys, node_ref) => {
-                unsafe {
-                    let node = &(*node_ref);
-                    let mut children = vec![];
-                    for (b, key) in node.branches.borrow_mut().iter_mut().zip(keys.into_iter()) {
-                        b.load_item(self.bs).ok()?;
-                        if let Item::Ptr(child_node) = b {
-                            let prefix_key_list = (0..WIDTH)
-                                .map(|prefix_key| (prefix_key, child_node.get_bit(prefix_key)))
-                                .filter(|(_, need)| *need)
-                                .map(|(pref, _)| (key << BITS_PER_SUBKEY) + pref as u64)
-                                .collect::<Vec<_>>();
-
-                            let node_ptr = child_node.as_ref() as *const Node;
-                            if !child_node.leafs.is_empty() {
-                                children.push(Traversing::Leaf(0, (prefix_key_list, node_ptr)));
-                            } else {
-                                children.push(Traversing::Branch(prefix_key_list, node_ptr));
-                            }
-                        }
-                    }
-                    self.stack.extend(children.into_iter().rev());
-                }
-                self.next()
+            match branch {
+                Item::Ptr(node) => self.push(node, height - 1, offset),
+                Item::Link(_) => unreachable!("after `load_item`, Item must be `Ptr`"),
             }
         }
     }
-
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let hint = (self.size - self.count) as usize;
-        (hint, Some(hint))
-    }
 }
@@ -1,28 +1,71 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
+mod diff;
 mod iter;
 mod trait_impl;
 
 use std::cell::RefCell;
-use std::ops::DerefMut;
 
 use cid::Cid;
 use serde::{de::DeserializeOwned, Serialize};
-use serde_cbor::Value;
 
+use ipld_core::{from_ipld, to_ipld, IpldValue};
+
+pub use self::diff::{diff, diff_with_bitwidth, Change};
+pub use self::iter::Iter;
 pub use self::trait_impl::PartAmt;
 use crate::blocks::Blocks;
 use crate::error::*;
 
-const WIDTH: usize = 8; // 2^3
-const BITS_PER_SUBKEY: u64 = 3; // 3 is for bit_width 8 (2^3=8)
-const SUBKEY_MASK: u64 = 8 - 1; // 2^3 - 1
+/// The default bit width, 8 (2^3) branches or values per node like the AMT v0 of Filecoin.
+pub const DEFAULT_BIT_WIDTH: u32 = 3;
+/// The max bit width, the bitmap of a node is 8KiB.
+pub const MAX_BIT_WIDTH: u32 = 16;
+
+fn check_bit_width(bit_width: u32) -> Result<()> {
+    if bit_width == 0 || bit_width > MAX_BIT_WIDTH {
+        return Err(AmtIpldError::InvalidBitWidth(bit_width));
+    }
+    Ok(())
+}
+
+/// the height of a loaded root, the keys of a tree higher than `64 / bit_width` would
+/// be out of the range of u64.
+fn check_height(bit_width: u32, height: u64) -> Result<()> {
+    if height > u64::from(64 / bit_width) {
+        return Err(AmtIpldError::InvalidHeight { height, bit_width });
+    }
+    Ok(())
+}
+
+/// the bytes of the bitmap of a node with `2^bit_width` bits.
+fn bitmap_len(bit_width: u32) -> usize {
+    ((1 << bit_width) + 7) / 8
+}
+
+/// the position of the key in the node at the height.
+fn bit_pos(bit_width: u32, height: u64, key: u64) -> usize {
+    let mask = (1 << bit_width) - 1;
+    (key.checked_shr(bit_width * height as u32).unwrap_or(0) & mask) as usize
+}
+
+/// the count of the keys under a branch of the node at the height, i.e. `width^height`.
+fn keys_per_branch(bit_width: u32, height: u64) -> u64 {
+    1u64.checked_shl(bit_width * height as u32)
+        .unwrap_or(u64::max_value())
+}
 
-fn bit_pos(h: u64, s: u64) -> usize {
-    ((h >> s) & SUBKEY_MASK) as usize
+/// whether the key is out of the capacity of a tree of the height.
+fn out_of_range(bit_width: u32, height: u64, key: u64) -> bool {
+    key.checked_shr(bit_width * (height as u32 + 1))
+        .unwrap_or(0)
+        != 0
 }
 
 /// Root of Amt nodes. store current tree height and count as well
+///
+/// the `Amt` could be sent to other threads if the blocks could, but is not thread safe,
+/// if want to use `Amt` in multi thread, must use lock to wrap `Amt`
 pub struct Amt<B>
 where
     B: Blocks,
@@ -32,23 +75,26 @@ where
     root: Node,
 
     bs: B,
+    /// every node has `2^bit_width` branches or values, it's not stored in the root
+    bit_width: u32,
 }
 
 /// branch for Node.
 /// when `load_item`, must be `Ptr`
 /// when `flush`, must be `Link`
 /// when `serialize/deserialize`, must be `Link`
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub enum Item {
     Link(Cid),
     Ptr(Box<Node>),
 }
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, PartialEq)]
 pub struct Node {
-    bitmap: usize,
+    /// the bit `i` is the bit `i % 8` of the byte `i / 8`
+    bitmap: Vec<u8>,
     branches: RefCell<Vec<Item>>,
-    leafs: Vec<Value>,
+    leafs: Vec<IpldValue>,
 }
 
 impl<B> Amt<B>
@@ -57,26 +103,47 @@ where
 {
     /// create a new empy Amt tree
     pub fn new(bs: B) -> Self {
+        Self::new_empty(bs, DEFAULT_BIT_WIDTH)
+    }
+
+    /// create a new empy Amt tree with `2^bit_width` branches or values per node,
+    /// the bit_width must be in `1..=MAX_BIT_WIDTH`.
+    pub fn new_with_bitwidth(bs: B, bit_width: u32) -> Result<Self> {
+        check_bit_width(bit_width)?;
+        Ok(Self::new_empty(bs, bit_width))
+    }
+
+    fn new_empty(bs: B, bit_width: u32) -> Self {
         Amt::<B> {
             height: 0,
             count: 0,
-            root: Node::new(),
+            root: Node::new(bit_width),
             bs,
+            bit_width,
         }
     }
 
     /// load an Amt tree from cid
     pub fn load(cid: &Cid, bs: B) -> Result<Self> {
+        Self::load_with_bitwidth(cid, bs, DEFAULT_BIT_WIDTH)
+    }
+
+    /// load an Amt tree from cid with the bit_width it was built with
+    pub fn load_with_bitwidth(cid: &Cid, bs: B, bit_width: u32) -> Result<Self> {
+        check_bit_width(bit_width)?;
         let part_root: PartAmt = bs.get(cid)?;
-        Ok(Self::from_part(part_root, bs))
+        check_height(bit_width, part_root.0)?;
+        part_root.2.check(bit_width, part_root.0)?;
+        Ok(Self::from_part(part_root, bs, bit_width))
     }
 
-    pub(crate) fn from_part(part_root: PartAmt, bs: B) -> Self {
+    pub(crate) fn from_part(part_root: PartAmt, bs: B, bit_width: u32) -> Self {
         Amt::<B> {
             height: part_root.0,
             count: part_root.1,
             root: part_root.2,
             bs,
+            bit_width,
         }
     }
 
@@ -92,25 +159,26 @@ where
         self.count
     }
 
+    pub fn bit_width(&self) -> u32 {
+        self.bit_width
+    }
+
     /// set a k/v for Amt tree
     pub fn set<Input: Serialize>(&mut self, k: u64, input: Input) -> Result<()> {
-        let v: Value = ipld_core::struct_to_cbor_value(&input)?;
+        let v = to_ipld(&input)?;
 
         // extend amt tree first
         // if current key large then tree capacity, create a new root, and move current root be
         // sub node of new root, so that the tree is been extended.
-        let mut tmp = k >> (BITS_PER_SUBKEY * (self.height + 1));
-        while tmp != 0 {
+        while out_of_range(self.bit_width, self.height, k) {
             if !self.root.is_empty() {
-                self.root.flush(&mut self.bs, self.height)?;
+                self.root.flush(&mut self.bs, self.bit_width, self.height)?;
                 let cid = self.bs.put(&self.root)?;
-                self.root = Node::new_with_cid(cid);
+                self.root = Node::new_with_cid(self.bit_width, cid);
             }
-            tmp >>= BITS_PER_SUBKEY;
             self.height += 1;
         }
-        let current_shift = BITS_PER_SUBKEY * self.height;
-        let add = self.root.set(&self.bs, self.height, k, v, current_shift)?;
+        let add = self.root.set(&self.bs, self.bit_width, self.height, k, v)?;
         if add {
             self.count += 1;
         }
@@ -127,46 +195,37 @@ where
 
     /// get a value for k, if k is not exist, would return `Error::NotFound`
     pub fn get<Output: DeserializeOwned>(&self, k: u64) -> Result<Output> {
-        let test = k >> (BITS_PER_SUBKEY * (self.height + 1));
-        if test != 0 {
+        if out_of_range(self.bit_width, self.height, k) {
             // not found
             return Err(AmtIpldError::NotFound(k));
         }
-        let current_shift = BITS_PER_SUBKEY * self.height;
         let output = self
             .root
-            .get(&self.bs, self.height, k, current_shift, |v| {
-                ipld_core::cbor_value_to_struct(v.clone()).map_err(AmtIpldError::IpldCbor)
+            .get(&self.bs, self.bit_width, self.height, k, |v| {
+                from_ipld(v.clone()).map_err(AmtIpldError::IpldCbor)
             })?;
         Ok(output)
     }
 
     /// delete for k, if k is not exist, would return `Error::NotFound`
     pub fn delete(&mut self, k: u64) -> Result<()> {
-        let current_shift = BITS_PER_SUBKEY * self.height;
-        self.root.delete(&self.bs, self.height, k, current_shift)?;
+        if out_of_range(self.bit_width, self.height, k) {
+            return Err(AmtIpldError::NotFound(k));
+        }
+        self.root.delete(&self.bs, self.bit_width, self.height, k)?;
         self.count -= 1;
 
-        while self.root.bitmap == 1 && self.height > 0 {
-            let sub = {
-                let mut branches = self.root.branches.borrow_mut();
-                branches[0].load_item(&self.bs)?;
-
-                if let Item::Ptr(node) = &mut branches[0] {
-                    // the `self.node.branches[;]` would be released with `self.node` releasing,
-                    // and self.node would be replaced by `sub` later.
-                    // thus, we use an `empty` node replace the `self.node.branches[i]`, passing to outside,
-                    // and use the `empty` node replace with self.node.
-                    // notice only allow in single thread
-                    let mut empty = Node::new();
-                    std::mem::swap(node.deref_mut(), &mut empty);
-                    empty
-                } else {
-                    unreachable!("after `load_item`, Item must be `Ptr`")
-                }
-            };
-
-            self.root = sub;
+        // only the first branch is left, it's the new root
+        while self.root.bitmap[0] == 1
+            && self.root.bitmap[1..].iter().all(|b| *b == 0)
+            && self.height > 0
+        {
+            let branches = self.root.branches.get_mut();
+            branches[0].load_item(&self.bs, self.bit_width, self.height - 1)?;
+            match branches.remove(0) {
+                Item::Ptr(node) => self.root = *node,
+                Item::Link(_) => unreachable!("after `load_item`, Item must be `Ptr`"),
+            }
             self.height -= 1;
         }
 
@@ -183,7 +242,7 @@ where
 
     /// commit all changes into db and generate new cid for current Amt
     pub fn flush(&mut self) -> Result<Cid> {
-        self.root.flush(&mut self.bs, self.height)?;
+        self.root.flush(&mut self.bs, self.bit_width, self.height)?;
         // (&self.height, &self.count, &self.root) equal to Serialize for `Amt<B>`
         let cid = self.bs.put((&self.height, &self.count, &self.root))?;
         Ok(cid)
@@ -191,52 +250,60 @@ where
 }
 
 impl Item {
-    fn new_ptr() -> Self {
-        Item::Ptr(Box::new(Node::default()))
-    }
-
-    fn load_item<B>(&mut self, bs: &B) -> Result<()>
+    /// load the node of the link, which is at the height.
+    fn load_item<B>(&mut self, bs: &B, bit_width: u32, height: u64) -> Result<()>
     where
         B: Blocks,
     {
         if let Item::Link(cid) = self {
-            let n: Node = bs.get(cid)?;
+            let n = Node::load(bs, cid, bit_width, height)?;
             *self = Item::Ptr(Box::new(n));
         }
         Ok(())
     }
+
+    fn into_node<B>(self, bs: &B, bit_width: u32, height: u64) -> Result<Node>
+    where
+        B: Blocks,
+    {
+        match self {
+            Item::Link(cid) => Node::load(bs, &cid, bit_width, height),
+            Item::Ptr(node) => Ok(*node),
+        }
+    }
 }
 
 // only could put outside of `Node` to avoid mutable check
 /// set 1 for bit position index in bitmap
 #[inline]
-fn set_bit(bitmap: &mut usize, index: usize) {
-    let b = 1 << index;
-    *bitmap |= b;
+fn set_bit(bitmap: &mut [u8], index: usize) {
+    bitmap[index / 8] |= 1 << (index % 8);
 }
 
 // only could put outside of `Node` to avoid mutable check
 /// set 0 for bit position index in bitmap
 #[inline]
-fn unset_bit(bitmap: &mut usize, index: usize) {
-    let b = 1 << index;
-    *bitmap &= !b
+fn unset_bit(bitmap: &mut [u8], index: usize) {
+    bitmap[index / 8] &= !(1 << (index % 8));
 }
 
 impl Node {
-    fn new() -> Self {
-        Node::default()
-    }
-
-    fn new_with_cid(cid: Cid) -> Self {
+    fn new(bit_width: u32) -> Self {
         Node {
-            bitmap: 1,
-            branches: RefCell::new(vec![Item::Link(cid)]),
+            bitmap: vec![0; bitmap_len(bit_width)],
+            branches: RefCell::new(vec![]),
             leafs: vec![],
         }
     }
 
-    pub fn new_from_raw(bitmap: usize, branches: Vec<Item>, leafs: Vec<Value>) -> Self {
+    fn new_with_cid(bit_width: u32, cid: Cid) -> Self {
+        let mut node = Node::new(bit_width);
+        set_bit(&mut node.bitmap, 0);
+        node.branches.get_mut().push(Item::Link(cid));
+        node
+    }
+
+    pub fn new_from_raw(bitmap: Vec<u8>, branches: Vec<Item>, leafs: Vec<IpldValue>) -> Self {
         Node {
             bitmap,
             branches: RefCell::new(branches),
@@ -244,25 +311,78 @@ impl Node {
         }
     }
 
+    fn load<B>(bs: &B, cid: &Cid, bit_width: u32, height: u64) -> Result<Self>
+    where
+        B: Blocks,
+    {
+        let node: Node = bs.get(cid)?;
+        node.check(bit_width, height)?;
+        Ok(node)
+    }
+
+    /// the bitmap must have `2^bit_width` bits, the bits out of the width are 0.
+    /// a leaf node (height 0) only has values, an inner node only has branches,
+    /// one for each bit set in the bitmap.
+    fn check(&self, bit_width: u32, height: u64) -> Result<()> {
+        let width = 1 << bit_width;
+        if self.bitmap.len() != bitmap_len(bit_width)
+            || (width..self.bitmap.len() * 8).any(|i| self.get_bit(i))
+        {
+            return Err(AmtIpldError::InvalidBitmap {
+                len: self.bitmap.len(),
+                bit_width,
+            });
+        }
+        let count = self.bitmap.iter().map(|b| b.count_ones()).sum::<u32>() as usize;
+        let branches = self.branches.borrow().len();
+        let valid = if height == 0 {
+            branches == 0 && self.leafs.len() == count
+        } else {
+            self.leafs.is_empty() && branches == count
+        };
+        if !valid {
+            return Err(AmtIpldError::InvalidNode(height));
+        }
+        Ok(())
+    }
+
     #[inline]
     fn get_bit(&self, index: usize) -> bool {
-        (1 << index) & self.bitmap != 0
+        self.bitmap
+            .get(index / 8)
+            .map_or(false, |b| b & (1 << (index % 8)) != 0)
     }
 
     fn bit_to_index(&self, bit_pos: usize) -> usize {
-        let mask = (1 << bit_pos) - 1;
-        (mask & self.bitmap).count_ones() as usize
+        let (bytes, bits) = (bit_pos / 8, bit_pos % 8);
+        let before: u32 = self.bitmap[..bytes].iter().map(|b| b.count_ones()).sum();
+        let mask = (1 << bits) - 1;
+        (before + (mask & self.bitmap[bytes]).count_ones()) as usize
+    }
+
+    /// the positions of the branches or values, in order.
+    fn positions(&self) -> Vec<usize> {
+        (0..self.bitmap.len() * 8)
+            .filter(|i| self.get_bit(*i))
+            .collect()
     }
 
     fn is_empty(&self) -> bool {
-        self.bitmap == 0
+        self.bitmap.iter().all(|b| *b == 0)
     }
 
-    fn set<B>(&mut self, bs: &B, height: u64, key: u64, v: Value, shift: u64) -> Result<bool>
+    fn set<B>(
+        &mut self,
+        bs: &B,
+        bit_width: u32,
+        height: u64,
+        key: u64,
+        v: IpldValue,
+    ) -> Result<bool>
     where
         B: Blocks,
     {
-        let pos = bit_pos(key, shift);
+        let pos = bit_pos(bit_width, height, key);
         let exist = self.get_bit(pos);
         let index = self.bit_to_index(pos);
 
@@ -280,26 +400,33 @@ impl Node {
         let mut branches = self.branches.borrow_mut();
 
         if exist {
-            branches[index].load_item(bs)?;
+            branches[index].load_item(bs, bit_width, height - 1)?;
         } else {
             // create new branch
-            branches.insert(index, Item::new_ptr());
+            branches.insert(index, Item::Ptr(Box::new(Node::new(bit_width))));
             set_bit(&mut self.bitmap, pos);
         }
 
         if let Item::Ptr(node) = &mut branches[index] {
-            node.set(bs, height - 1, key, v, shift - BITS_PER_SUBKEY)
+            node.set(bs, bit_width, height - 1, key, v)
         } else {
             unreachable!("after `load_item`, Item must be `Ptr`")
         }
     }
 
-    fn get<B, F, Output>(&self, bs: &B, height: u64, key: u64, shift: u64, f: F) -> Result<Output>
+    fn get<B, F, Output>(
+        &self,
+        bs: &B,
+        bit_width: u32,
+        height: u64,
+        key: u64,
+        f: F,
+    ) -> Result<Output>
     where
         B: Blocks,
-        F: Fn(&Value) -> Result<Output>,
+        F: Fn(&IpldValue) -> Result<Output>,
     {
-        let pos = bit_pos(key, shift);
+        let pos = bit_pos(bit_width, height, key);
         if !self.get_bit(pos) {
             return Err(AmtIpldError::NotFound(key));
         }
@@ -312,22 +439,22 @@ impl Node {
         }
 
         // load item
-        self.branches.borrow_mut()[index].load_item(bs)?;
+        self.branches.borrow_mut()[index].load_item(bs, bit_width, height - 1)?;
 
         let borrow = self.branches.borrow();
         let b = &borrow[index];
         if let Item::Ptr(node) = b {
-            node.get(bs, height - 1, key, shift - BITS_PER_SUBKEY, f)
+            node.get(bs, bit_width, height - 1, key, f)
         } else {
             unreachable!("after `load_item`, Item must be `Ptr`")
         }
     }
 
-    fn delete<B>(&mut self, bs: &B, height: u64, key: u64, shift: u64) -> Result<()>
+    fn delete<B>(&mut self, bs: &B, bit_width: u32, height: u64, key: u64) -> Result<()>
     where
         B: Blocks,
     {
-        let pos = bit_pos(key, shift);
+        let pos = bit_pos(bit_width, height, key);
         if !self.get_bit(pos) {
             return Err(AmtIpldError::NotFound(key));
         }
@@ -340,10 +467,10 @@ impl Node {
         }
 
         let mut branches = self.branches.borrow_mut();
-        branches[index].load_item(bs)?;
+        branches[index].load_item(bs, bit_width, height - 1)?;
 
         if let Item::Ptr(node) = &mut branches[index] {
-            node.delete(bs, height - 1, key, shift - BITS_PER_SUBKEY)?;
+            node.delete(bs, bit_width, height - 1, key)?;
 
             if node.is_empty() {
                 unset_bit(&mut self.bitmap, pos);
@@ -355,7 +482,7 @@ impl Node {
         }
     }
 
-    fn flush<B>(&mut self, bs: &mut B, depth: u64) -> Result<()>
+    fn flush<B>(&mut self, bs: &mut B, bit_width: u32, depth: u64) -> Result<()>
     where
         B: Blocks,
     {
@@ -366,9 +493,9 @@ impl Node {
 
         let mut branches = self.branches.borrow_mut();
         for b in branches.iter_mut() {
-            b.load_item(bs)?;
+            b.load_item(bs, bit_width, depth - 1)?;
             if let Item::Ptr(node) = b {
-                node.flush(bs, depth - 1)?;
+                node.flush(bs, bit_width, depth - 1)?;
                 let cid = bs.put(node)?;
                 // refresh current branch link cid
                 *b = Item::Link(cid)
@@ -385,5 +512,6 @@ pub fn create_root<B: Blocks>(height: u64, count: u64, node: Node, bs: B) -> Amt
         count,
         root: node,
         bs,
+        bit_width: DEFAULT_BIT_WIDTH,
     }
 }
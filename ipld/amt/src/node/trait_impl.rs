@@ -5,7 +5,8 @@ use std::ops::Deref;
 use std::result;
 
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
-use serde_cbor::Value;
+
+use ipld_core::{IpldLink, IpldValue};
 
 use super::{Amt, Item, Node};
 use crate::blocks::Blocks;
@@ -22,14 +23,15 @@ where
     }
 }
 
-impl<B> Eq for Amt<B> where B: Blocks {}
-
 impl<B> PartialEq for Amt<B>
 where
     B: Blocks,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.height.eq(&other.height) && self.count.eq(&other.count) && self.root.eq(&other.root)
+        self.height.eq(&other.height)
+            && self.count.eq(&other.count)
+            && self.root.eq(&other.root)
+            && self.bit_width.eq(&other.bit_width)
     }
 }
 
@@ -40,8 +42,8 @@ where
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Root{{ height:{:}, count:{:}, node:{:?} }}",
-            self.height, self.count, self.root
+            "Root{{ height:{:}, count:{:}, bit_width:{:}, node:{:?} }}",
+            self.height, self.count, self.bit_width, self.root
         )
     }
 }
@@ -55,7 +57,7 @@ impl Serialize for Item {
         S: Serializer,
     {
         match self {
-            Item::Link(cid) => cid::ipld_dag_cbor::serialize(cid, serializer),
+            Item::Link(cid) => IpldLink(cid.clone()).serialize(serializer),
             Item::Ptr(_) => unreachable!("could not serialize `Ptr`, just allow `Link`"),
         }
     }
@@ -66,7 +68,7 @@ impl<'de> Deserialize<'de> for Item {
     where
         D: Deserializer<'de>,
     {
-        cid::ipld_dag_cbor::deserialize(deserializer).map(Item::Link)
+        IpldLink::deserialize(deserializer).map(|link| Item::Link(link.into_inner()))
     }
 }
 
@@ -75,10 +77,8 @@ impl Serialize for Node {
     where
         S: Serializer,
     {
-        let r = self.bitmap.to_be_bytes();
-        let bytes: [u8; 1] = [r[r.len() - 1]; 1];
         (
-            serde_bytes::Bytes::new(bytes.as_ref()),
+            serde_bytes::Bytes::new(&self.bitmap),
             self.branches.borrow().deref(),
             &self.leafs,
         )
@@ -87,21 +87,19 @@ impl Serialize for Node {
 }
 
 #[derive(Deserialize)]
-struct NodeVisitor(serde_bytes::ByteBuf, Vec<Item>, Vec<Value>);
+struct NodeVisitor(serde_bytes::ByteBuf, Vec<Item>, Vec<IpldValue>);
 impl<'de> Deserialize<'de> for Node {
     fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         let visitor = NodeVisitor::deserialize(deserializer)?;
-        if visitor.0.len() != 1 {
-            return Err(D::Error::custom(format!(
-                "node bitmap must be 1 byte, current is:{:?}",
-                visitor.0
-            )));
+        // the length is checked with the bit width when the node is loaded
+        if visitor.0.is_empty() {
+            return Err(D::Error::custom("node bitmap must not be empty"));
         }
         Ok(Node::new_from_raw(
-            visitor.0[0] as usize,
+            visitor.0.into_vec(),
             visitor.1,
             visitor.2,
         ))
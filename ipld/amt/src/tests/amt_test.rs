@@ -44,10 +44,10 @@ fn one_root_node_reorder_insert() {
     let m = vec![0, 2, 5, 7];
     for (src, (key, value)) in m.iter().zip(collection.into_iter()) {
         assert_eq!(*src, key);
-        assert_eq!(Value::Text(src.to_string()), value);
+        assert_eq!(IpldValue::String(src.to_string()), value);
     }
 
-    for (src, (key, _)) in m.iter().zip(root.iter()) {
+    for (src, (key, _)) in m.iter().zip(root.iter().map(Result::unwrap)) {
         assert_eq!(*src, key)
     }
 }
@@ -105,7 +105,7 @@ fn tow_level_node_reorder_insert() {
     let m = vec![1, 7, 8];
     for (src, (key, value)) in m.iter().zip(collection.into_iter()) {
         assert_eq!(*src, key);
-        assert_eq!(Value::Text(src.to_string()), value);
+        assert_eq!(IpldValue::String(src.to_string()), value);
     }
 
     let bs = db();
@@ -130,10 +130,10 @@ fn tow_level_node_reorder_insert() {
     let m = vec![1, 7, 8];
     for (src, (key, value)) in m.iter().zip(collection.into_iter()) {
         assert_eq!(*src, key);
-        assert_eq!(Value::Text(src.to_string()), value);
+        assert_eq!(IpldValue::String(src.to_string()), value);
     }
 
-    for (src, (key, _)) in m.iter().zip(root.iter()) {
+    for (src, (key, _)) in m.iter().zip(root.iter().map(Result::unwrap)) {
         assert_eq!(*src, key)
     }
 }
@@ -168,10 +168,10 @@ fn there_level() {
     m.sort();
     for (src, (key, value)) in m.iter().zip(collection.into_iter()) {
         assert_eq!(*src, key);
-        assert_eq!(Value::Integer(*src as i128), value);
+        assert_eq!(IpldValue::Integer(*src as i128), value);
     }
 
-    for (src, (key, _)) in m.iter().zip(root.iter()) {
+    for (src, (key, _)) in m.iter().zip(root.iter().map(Result::unwrap)) {
         assert_eq!(*src, key)
     }
 }
@@ -358,7 +358,7 @@ fn test_for_each() {
     );
     assert_eq!(c1.to_string(), c2.to_string());
 
-    let root2 = Amt::load(&c2, bs).unwrap();
+    let mut root2 = Amt::load(&c2, bs).unwrap();
     assert_eq!(root2.count(), INDEXS.len() as u64);
 
     let mut x = 0;
@@ -372,12 +372,265 @@ fn test_for_each() {
 
     x = 0;
     for (_, item) in root2.iter().enumerate() {
-        assert_eq!(item.0, INDEXS[x]);
+        assert_eq!(item.unwrap().0, INDEXS[x]);
         x += 1;
     }
     assert_eq!(x, INDEXS.len());
 }
 
+#[test]
+fn test_iter_from() {
+    let bs = db();
+    let mut root = Amt::new(bs.clone());
+    for i in INDEXS.iter() {
+        root.set(*i, *i).unwrap();
+    }
+    let c = root.flush().unwrap();
+
+    for start in [0, 1, 2, 8, 63, 64, 1000, 4095, 9999].iter() {
+        let keys = root
+            .iter_from(*start)
+            .map(|item| item.unwrap().0)
+            .collect::<Vec<_>>();
+        let expected = INDEXS
+            .iter()
+            .cloned()
+            .filter(|k| k >= start)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, expected);
+    }
+    assert_eq!(root.iter_from(10000).count(), 0);
+    assert_eq!(root.iter_from(u64::max_value()).count(), 0);
+
+    // the branches before the start are not loaded
+    let mut root = Amt::load(&c, bs.clone()).unwrap();
+    let gets = bs.gets();
+    let (key, value) = root.iter_from(9999).next().unwrap().unwrap();
+    assert_eq!((key, value), (9999, &IpldValue::Integer(9999)));
+    // the height of the tree is 4, a node is loaded per level under the root
+    assert_eq!(bs.gets() - gets, 4);
+}
+
+#[test]
+fn test_iter_missing_link() {
+    let bs = db();
+    let mut root = Amt::new(bs.clone());
+    for i in 0..100 {
+        root.set(i, i).unwrap();
+    }
+    let c = root.flush().unwrap();
+
+    // the nodes under the root are not in the new db
+    let part: PartAmt = bs.get(&c).unwrap();
+    let mut bs = db();
+    let c = bs.put((part.0, part.1, &part.2)).unwrap();
+    let mut root = Amt::load(&c, bs).unwrap();
+    let items = root.iter().collect::<Vec<_>>();
+    assert_eq!(items.len(), 1);
+    assert!(matches!(
+        items[0],
+        Err(AmtIpldError::Blockstore(BlockstoreError::NotFound(_)))
+    ));
+}
+
+#[test]
+fn test_for_each_while() {
+    let bs = db();
+    let mut root = Amt::new(bs);
+    for i in INDEXS.iter() {
+        root.set(*i, "value").unwrap();
+    }
+    root.flush().unwrap();
+
+    let mut keys = vec![];
+    root.for_each_while(&mut |key, _| {
+        keys.push(key);
+        Ok(keys.len() < 100)
+    })
+    .unwrap();
+    assert_eq!(keys, &INDEXS[..100]);
+
+    // the error stops the traversal as well
+    let mut count = 0;
+    let res = root.for_each_while(&mut |key, _| {
+        count += 1;
+        if key == INDEXS[10] {
+            Err(AmtIpldError::NotFound(key))
+        } else {
+            Ok(true)
+        }
+    });
+    assert!(matches!(res, Err(AmtIpldError::NotFound(_))));
+    assert_eq!(count, 11);
+}
+
+#[test]
+fn test_diff() {
+    let bs = db();
+    let mut root = Amt::new(bs.clone());
+    for i in 0..5000 {
+        root.set(i, i).unwrap();
+    }
+    let c1 = root.flush().unwrap();
+    assert!(diff(&bs, &c1, &c1).unwrap().is_empty());
+
+    root.set(1, "one").unwrap();
+    root.delete(2).unwrap();
+    root.set(5000, 5000).unwrap();
+    let c2 = root.flush().unwrap();
+
+    let changes = diff(&bs, &c1, &c2).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            Change::Modify {
+                key: 1,
+                before: IpldValue::Integer(1),
+                after: IpldValue::String("one".to_string()),
+            },
+            Change::Remove {
+                key: 2,
+                value: IpldValue::Integer(2),
+            },
+            Change::Add {
+                key: 5000,
+                value: IpldValue::Integer(5000),
+            },
+        ]
+    );
+
+    // the equal branches are skipped, only the roots and the nodes of the two paths to
+    // the changed leafs are loaded
+    let gets = bs.gets();
+    let changes = diff(&bs, &c2, &c1).unwrap();
+    assert!(bs.gets() - gets <= 2 + 2 * 2 * 4);
+    assert_eq!(
+        changes.iter().map(Change::key).collect::<Vec<_>>(),
+        [1, 2, 5000]
+    );
+    assert!(matches!(changes[2], Change::Remove { key: 5000, .. }));
+
+    // the higher tree has more levels, the lower tree is its first branch
+    root.set(100_000, 0).unwrap();
+    let c3 = root.flush().unwrap();
+    let changes = diff(&bs, &c3, &c2).unwrap();
+    assert_eq!(
+        changes,
+        vec![Change::Remove {
+            key: 100_000,
+            value: IpldValue::Integer(0),
+        }]
+    );
+    let changes = diff(&bs, &c1, &c3).unwrap();
+    assert_eq!(
+        changes.iter().map(Change::key).collect::<Vec<_>>(),
+        [1, 2, 5000, 100_000]
+    );
+}
+
+#[test]
+fn test_bit_width() {
+    // the default bit width is the one of AMT v0
+    let mut root = Amt::new_with_bitwidth(db(), DEFAULT_BIT_WIDTH).unwrap();
+    root.set(0, "0").unwrap();
+    root.set(1, "1").unwrap();
+    root.set(7, "7").unwrap();
+    assert_eq!(
+        root.flush().unwrap().to_string(),
+        "bafy2bzacedj2lbq4w2xr74jm4ndynfn66z2v2ybcl5lfmoeilezaqcq2pplui"
+    );
+
+    for bit_width in [1, 2, 5, 8].iter() {
+        let bs = db();
+        let mut root = Amt::new_with_bitwidth(bs.clone(), *bit_width).unwrap();
+        for i in INDEXS.iter() {
+            root.set(*i, *i).unwrap();
+        }
+        assert_delete(&mut root, INDEXS[1]);
+        let c = root.flush().unwrap();
+
+        let mut root = Amt::load_with_bitwidth(&c, bs.clone(), *bit_width).unwrap();
+        assert_eq!(root.bit_width(), *bit_width);
+        assert_eq!(root.count(), INDEXS.len() as u64 - 1);
+        assert_eq!(root.get::<u64>(INDEXS[100]).unwrap(), INDEXS[100]);
+        let keys = root
+            .iter_from(INDEXS[1])
+            .map(|item| item.unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, &INDEXS[2..]);
+
+        root.set(INDEXS[1], 0).unwrap();
+        let c2 = root.flush().unwrap();
+        let changes = diff_with_bitwidth(&bs, &c, &c2, *bit_width).unwrap();
+        assert_eq!(
+            changes,
+            vec![Change::Add {
+                key: INDEXS[1],
+                value: IpldValue::Integer(0),
+            }]
+        );
+    }
+}
+
+#[test]
+fn test_invalid_bit_width_and_height() {
+    let mut bs = db();
+    let mut root = Amt::new(bs.clone());
+    root.set(1, "foo").unwrap();
+    let c = root.flush().unwrap();
+
+    for bit_width in [0, MAX_BIT_WIDTH + 1].iter() {
+        assert!(matches!(
+            Amt::new_with_bitwidth(bs.clone(), *bit_width),
+            Err(AmtIpldError::InvalidBitWidth(_))
+        ));
+        assert!(matches!(
+            Amt::load_with_bitwidth(&c, bs.clone(), *bit_width),
+            Err(AmtIpldError::InvalidBitWidth(_))
+        ));
+        assert!(matches!(
+            diff_with_bitwidth(&bs, &c, &c, *bit_width),
+            Err(AmtIpldError::InvalidBitWidth(_))
+        ));
+    }
+
+    // a root whose height is out of the range of the keys
+    let part: PartAmt = bs.get(&c).unwrap();
+    let height = u64::from(64 / DEFAULT_BIT_WIDTH) + 1;
+    let bad = bs.put((&height, &part.1, &part.2)).unwrap();
+    assert!(matches!(
+        Amt::load(&bad, bs.clone()),
+        Err(AmtIpldError::InvalidHeight { .. })
+    ));
+    assert!(matches!(
+        diff(&bs, &c, &bad),
+        Err(AmtIpldError::InvalidHeight { .. })
+    ));
+}
+
+#[test]
+fn test_send() {
+    fn assert_send<T: Send>() {}
+    // only checked at compile time
+    #[allow(dead_code)]
+    fn assert_wrapper_send<BS: ipfs_blockstore::Blockstore + Send + Sync>() {
+        assert_send::<Amt<BStoreWrapper<BS>>>();
+    }
+
+    let bs = db();
+    let mut root = Amt::new(bs.clone());
+    root.set(1, "foo").unwrap();
+    let c = std::thread::spawn(move || {
+        root.set(100, "bar").unwrap();
+        root.flush().unwrap()
+    })
+    .join()
+    .unwrap();
+    let root = Amt::load(&c, bs).unwrap();
+    assert_get(&root, 1, "foo");
+    assert_get(&root, 100, "bar");
+}
+
 const INDEXS: [u64; 4933] = [
     1, 3, 8, 11, 14, 15, 17, 20, 23, 25, 29, 31, 32, 33, 34, 36, 37, 38, 40, 41, 42, 43, 47, 48,
     49, 51, 53, 55, 56, 60, 62, 64, 69, 72, 73, 74, 77, 79, 80, 84, 85, 88, 90, 91, 92, 94, 95, 96,
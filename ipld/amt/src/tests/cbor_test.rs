@@ -1,12 +1,14 @@
-use super::*;
 use cid::IntoExt;
+use matches::matches;
+
+use super::*;
 
 #[test]
 fn node_test() {
     let cid = Cid::new_v0(multihash::Sha2_256::digest(b"something").into_ext()).unwrap();
     let b = Item::Link(cid);
-    let node = Node::new_from_raw(1, vec![b], vec![]);
-    let v = serde_cbor::to_vec(&node).unwrap();
+    let node = Node::new_from_raw(vec![1], vec![b], vec![]);
+    let v = to_vec(&node).unwrap();
     println!("{:?}", v);
     assert_eq!(
         v,
@@ -16,14 +18,18 @@ fn node_test() {
             167, 72, 19, 203, 128
         ]
     );
-    let n: Node = serde_cbor::from_slice(&v).unwrap();
+    let n: Node = from_slice(&v).unwrap();
     assert_eq!(node, n);
 
-    let node = Node::new_from_raw(255, vec![], vec![Value::Text("123".to_string())]);
-    let v = serde_cbor::to_vec(&node).unwrap();
+    let node = Node::new_from_raw(
+        vec![255],
+        vec![],
+        vec![IpldValue::String("123".to_string())],
+    );
+    let v = to_vec(&node).unwrap();
     println!("{:?}", v);
     assert_eq!(v, vec![131, 65, 255, 128, 129, 99, 49, 50, 51]);
-    let n: Node = serde_cbor::from_slice(&v).unwrap();
+    let n: Node = from_slice(&v).unwrap();
     assert_eq!(node, n);
 }
 
@@ -31,10 +37,10 @@ fn node_test() {
 fn root_test() {
     let cid = Cid::new_v0(multihash::Sha2_256::digest(b"something").into_ext()).unwrap();
     let b = Item::Link(cid);
-    let node = Node::new_from_raw(1, vec![b], vec![]);
+    let node = Node::new_from_raw(vec![1], vec![b], vec![]);
     let db = db();
     let root = create_root(4, 100, node, db.clone());
-    let v = serde_cbor::to_vec(&root).unwrap();
+    let v = to_vec(&root).unwrap();
     println!("{:?}", v);
 
     assert_eq!(
@@ -46,7 +52,85 @@ fn root_test() {
         ]
     );
 
-    let pr: PartAmt = serde_cbor::from_slice(&v).unwrap();
-    let r = Amt::from_part(pr, db);
+    let pr: PartAmt = from_slice(&v).unwrap();
+    let r = Amt::from_part(pr, db, DEFAULT_BIT_WIDTH);
     assert_eq!(root, r);
 }
+
+#[test]
+fn bitmap_test() {
+    // the bitmap has `2^bit_width` bits, and at least 1 byte like the bitmap of AMT v0
+    let db = db();
+    let mut root = Amt::new_with_bitwidth(db.clone(), 1).unwrap();
+    root.set(1, 1).unwrap();
+    let c = root.flush().unwrap();
+    let pr: PartAmt = db.get(&c).unwrap();
+    assert_eq!(to_vec(&pr.2).unwrap(), vec![131, 65, 2, 128, 129, 1]);
+
+    let mut root = Amt::new_with_bitwidth(db.clone(), 5).unwrap();
+    root.set(0, 0).unwrap();
+    root.set(9, 9).unwrap();
+    root.set(31, 31).unwrap();
+    let c = root.flush().unwrap();
+    let pr: PartAmt = db.get(&c).unwrap();
+    assert_eq!(
+        to_vec(&pr.2).unwrap(),
+        vec![131, 68, 1, 2, 0, 128, 128, 131, 0, 9, 24, 31]
+    );
+
+    // the bitmap of a node must match the bit width
+    assert!(matches!(
+        Amt::load_with_bitwidth(&c, db.clone(), 3),
+        Err(AmtIpldError::InvalidBitmap {
+            len: 4,
+            bit_width: 3
+        })
+    ));
+    let root = Amt::load_with_bitwidth(&c, db, 5).unwrap();
+    assert_eq!(root.get::<u64>(31).unwrap(), 31);
+}
+
+#[test]
+fn invalid_node_test() {
+    let mut db = db();
+    // a leaf with 2 bits set in its bitmap, but only 1 value
+    let leaf = Node::new_from_raw(vec![0b11], vec![], vec![IpldValue::Integer(0)]);
+    let leaf = db.put(&leaf).unwrap();
+    let root = Node::new_from_raw(vec![1], vec![Item::Link(leaf)], vec![]);
+    let c = db.put((&1u64, &2u64, &root)).unwrap();
+
+    let mut root = Amt::load(&c, db.clone()).unwrap();
+    assert!(matches!(
+        root.get::<u64>(1),
+        Err(AmtIpldError::InvalidNode(0))
+    ));
+    assert!(matches!(root.set(1, 1), Err(AmtIpldError::InvalidNode(0))));
+    assert!(matches!(
+        root.iter().next(),
+        Some(Err(AmtIpldError::InvalidNode(0)))
+    ));
+
+    // an inner node with values
+    let root = Node::new_from_raw(vec![1], vec![], vec![IpldValue::Integer(0)]);
+    let c = db.put((&1u64, &1u64, &root)).unwrap();
+    assert!(matches!(
+        Amt::load(&c, db.clone()),
+        Err(AmtIpldError::InvalidNode(1))
+    ));
+
+    // a leaf with links
+    let link = db
+        .put(&Node::new_from_raw(vec![0], vec![], vec![]))
+        .unwrap();
+    let root = Node::new_from_raw(vec![1], vec![Item::Link(link)], vec![]);
+    let c = db.put((&0u64, &1u64, &root)).unwrap();
+    assert!(matches!(
+        Amt::load(&c, db.clone()),
+        Err(AmtIpldError::InvalidNode(0))
+    ));
+    let empty = Amt::new(db.clone()).flush().unwrap();
+    assert!(matches!(
+        diff(&db, &c, &empty),
+        Err(AmtIpldError::InvalidNode(0))
+    ));
+}
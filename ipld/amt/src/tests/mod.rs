@@ -1,48 +1,53 @@
 mod amt_test;
 mod cbor_test;
 
-use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
 use std::result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
 
 use serde::{de::DeserializeOwned, Serialize};
-use serde_cbor::Value;
 
-use blockstore::BlockstoreError;
-use cid::{Cid, Codec, Prefix, Version};
+use cid::Cid;
+use ipfs_blockstore::BlockstoreError;
+use ipld_core::{dag_cbor, from_ipld, to_ipld, IpldValue};
 
+use crate::blocks::block_builder;
 use crate::node::{create_root, Item, Node, PartAmt};
 
 use super::*;
 
 #[derive(Default, Clone)]
 pub struct DB {
-    db: Rc<RefCell<HashMap<Vec<u8>, Vec<u8>>>>,
+    db: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>>,
+    gets: Arc<AtomicUsize>,
+}
+
+impl DB {
+    /// the count of the `get` calls.
+    pub fn gets(&self) -> usize {
+        self.gets.load(Ordering::SeqCst)
+    }
 }
 
 impl Blocks for DB {
     fn get<Output: DeserializeOwned>(&self, cid: &Cid) -> result::Result<Output, AmtIpldError> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
         let o = self
             .db
-            .borrow()
+            .read()
+            .unwrap()
             .get(&cid.to_bytes())
             .ok_or(BlockstoreError::NotFound(cid.clone()).into())
-            .and_then(|v| serde_cbor::from_slice(v).map_err(AmtIpldError::Cbor))?;
+            .and_then(|v| from_slice(v))?;
 
         Ok(o)
     }
 
     fn put<Input: Serialize>(&mut self, v: Input) -> result::Result<Cid, AmtIpldError> {
-        let v = serde_cbor::to_vec(&v)?;
-        let prefix = Prefix {
-            version: Version::V1,
-            codec: Codec::DagCBOR,
-            mh_type: multihash::Code::Blake2b256,
-            mh_len: 32,
-        };
-        let cid = Cid::new_from_prefix(&prefix, v.as_ref());
-        self.db.borrow_mut().insert(cid.to_bytes(), v);
+        let v = to_vec(&v)?;
+        let cid = block_builder().cid(&v)?;
+        self.db.write().unwrap().insert(cid.to_bytes(), v);
         Ok(cid)
     }
 }
@@ -50,3 +55,11 @@ impl Blocks for DB {
 pub fn db() -> DB {
     Default::default()
 }
+
+pub fn to_vec<T: Serialize>(v: &T) -> Result<Vec<u8>> {
+    Ok(dag_cbor::encode(&to_ipld(v)?)?)
+}
+
+pub fn from_slice<T: DeserializeOwned>(b: &[u8]) -> Result<T> {
+    Ok(from_ipld(dag_cbor::decode(b)?)?)
+}
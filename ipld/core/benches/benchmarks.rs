@@ -5,7 +5,8 @@ use std::collections::BTreeMap;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use serde::{Deserialize, Serialize};
 
-use ipld_core::{IpldNode, IpldValue};
+use block_format::Block;
+use ipld_core::{dag_cbor, IpldNode, IpldValue, IpldValueRef, Node};
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 struct MyStruct {
@@ -82,5 +83,61 @@ fn bench_to_cbor(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_wrap_object, bench_from_block, bench_to_cbor);
+/// A large block like the leaves of an AMT, a list of byte strings, strings and links.
+fn large_node() -> IpldNode {
+    let link = IpldNode::wrap_object(&test_struct_obj(), multihash::Code::Sha2_256.into())
+        .unwrap()
+        .cid()
+        .clone();
+    let values = (0..1024)
+        .map(|i| {
+            IpldValue::List(vec![
+                IpldValue::Bytes(vec![i as u8; 256]),
+                IpldValue::String(format!("value {}", i)),
+                IpldValue::Link(link.clone()),
+            ])
+        })
+        .collect();
+    IpldNode::wrap_object(&IpldValue::List(values), multihash::Code::Sha2_256.into()).unwrap()
+}
+
+fn bench_decode_large(c: &mut Criterion) {
+    let node = large_node();
+
+    c.bench_function("decode_large_owned", |b| {
+        b.iter(|| {
+            let _ = black_box(dag_cbor::decode(node.raw_data()).unwrap());
+        })
+    });
+    c.bench_function("decode_large_borrowed", |b| {
+        b.iter(|| {
+            let _ = black_box(IpldValueRef::decode(node.raw_data()).unwrap());
+        })
+    });
+}
+
+fn bench_from_block_large(c: &mut Criterion) {
+    let node = large_node();
+
+    c.bench_function("from_block_large", |b| {
+        b.iter(|| {
+            let _ = black_box(IpldNode::from_block(&node).unwrap());
+        })
+    });
+    c.bench_function("from_block_large_links", |b| {
+        b.iter(|| {
+            let n = IpldNode::from_block(&node).unwrap();
+            let _ = black_box(n.links().len());
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_wrap_object,
+    bench_from_block,
+    bench_to_cbor,
+    bench_decode_large,
+    bench_from_block_large
+);
 criterion_main!(benches);
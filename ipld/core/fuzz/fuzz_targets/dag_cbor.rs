@@ -0,0 +1,22 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use ipld_core::dag_cbor;
+
+fuzz_target!(|data: &[u8]| {
+    // the strict decoding accepts the canonical encodings only,
+    // so a decoded value must be encoded back into the same bytes.
+    if let Ok(value) = dag_cbor::decode(data) {
+        let encoded = dag_cbor::encode(&value).expect("a decoded value can be encoded");
+        assert_eq!(encoded, data);
+    }
+    // the lenient decoding must not panic either, and accepts the canonical encodings.
+    let lenient = dag_cbor::decode_lenient(data);
+    if let Ok(value) = dag_cbor::decode(data) {
+        assert_eq!(lenient.ok(), Some(value));
+    }
+    let _ = minicbor::decode::<ipld_core::IpldValue>(data);
+});
@@ -0,0 +1,43 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use cid::Codec;
+
+use ipld_format::coding::{CodecPlugin, CodecRegistry};
+use ipld_format::FormatError;
+
+use crate::error::IpldCoreError;
+use crate::json_node::DagJsonNode;
+use crate::node::IpldNode;
+use crate::value::IpldValue;
+use crate::{dag_cbor, dag_json};
+
+/// Registers the codecs of this library in the registry: the `dag-cbor` and `dag-json`
+/// decoders and encoders, and the `raw` encoder of `IpldValue::Bytes`.
+///
+/// They are registered in the default registries without calling it.
+pub fn register_codecs(registry: &mut CodecRegistry) {
+    registry.register_decoder(Codec::DagCBOR, |block| {
+        let node = IpldNode::from_block(block).map_err(|e| FormatError::Other(Box::new(e)))?;
+        Ok(Box::new(node))
+    });
+    registry.register_encoder(Codec::DagCBOR, |value: &IpldValue| {
+        dag_cbor::encode(value).map_err(|e| FormatError::Other(Box::new(e)))
+    });
+    registry.register_decoder(Codec::DagJSON, |block| {
+        let node = DagJsonNode::from_block(block).map_err(|e| FormatError::Other(Box::new(e)))?;
+        Ok(Box::new(node))
+    });
+    registry.register_encoder(Codec::DagJSON, |value: &IpldValue| {
+        dag_json::encode(value).map_err(|e| FormatError::Other(Box::new(e)))
+    });
+    registry.register_encoder(Codec::Raw, |value: &IpldValue| match value {
+        IpldValue::Bytes(bytes) => Ok(bytes.clone()),
+        _ => Err(FormatError::Other(Box::new(IpldCoreError::ObjErr(
+            "raw codec can only encode bytes".into(),
+        )))),
+    });
+}
+
+inventory::submit! {
+    CodecPlugin::new(register_codecs)
+}
@@ -1,152 +1,735 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
-use std::collections::BTreeMap;
+//! The conversions between the serde data model and `IpldValue`.
+//!
+//! The structs are maps, the sequences and the tuples are lists, the enums are externally
+//! tagged like `serde_json`, i.e. a unit variant is its name and the other variants are
+//! a map with a single key, and the CIDs wrapped in `IpldLink` are links.
+
+use std::collections::{btree_map, BTreeMap};
 use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::vec;
 
 use cid::Cid;
+use serde::{de, ser, Deserialize, Serialize};
+
+use crate::error::IpldCoreError;
+use crate::value::{IpldValue, SortedStr};
+
+/// The name of the newtype struct of the links, recognized by the serializer and
+/// the deserializer of `IpldValue`.
+const LINK_NAME: &str = "$__ipld_link";
+
+/// Converts the serializable value into an `IpldValue`.
+pub fn to_ipld<T: Serialize + ?Sized>(value: &T) -> Result<IpldValue, IpldCoreError> {
+    value.serialize(Serializer)
+}
+
+/// Converts the `IpldValue` into a deserializable value.
+pub fn from_ipld<T: de::DeserializeOwned>(value: IpldValue) -> Result<T, IpldCoreError> {
+    T::deserialize(Deserializer(value))
+}
+
+impl ser::Error for IpldCoreError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        IpldCoreError::ObjErr(msg.to_string())
+    }
+}
+
+impl de::Error for IpldCoreError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        IpldCoreError::ObjErr(msg.to_string())
+    }
+}
+
+/// A CID which is converted into an `IpldValue::Link`, unlike a bare `Cid`.
+///
+/// The other serializers see a newtype struct of the bytes of the CID.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IpldLink(pub Cid);
+
+impl IpldLink {
+    /// Convert to inner CID.
+    pub fn into_inner(self) -> Cid {
+        self.0
+    }
+}
+
+impl From<Cid> for IpldLink {
+    fn from(cid: Cid) -> Self {
+        IpldLink(cid)
+    }
+}
+
+impl From<IpldLink> for Cid {
+    fn from(link: IpldLink) -> Self {
+        link.0
+    }
+}
+
+impl AsRef<Cid> for IpldLink {
+    fn as_ref(&self) -> &Cid {
+        &self.0
+    }
+}
+
+impl Deref for IpldLink {
+    type Target = Cid;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for IpldLink {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
 
-use crate::error::{IpldCborError, Result};
-use crate::value::IpldValue;
+struct CidBytes<'a>(&'a [u8]);
 
-/// Convert structure to CBOR Value.
-pub fn struct_to_cbor_value<S: serde::Serialize>(v: &S) -> Result<serde_cbor::Value> {
-    let s = serde_cbor::to_vec(&v)?;
-    let value: serde_cbor::Value = serde_cbor::from_slice(&s)?;
-    Ok(value)
+impl<'a> Serialize for CidBytes<'a> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
 }
 
-/// Convert CBOR Value to structure.
-pub fn cbor_value_to_struct<O: serde::de::DeserializeOwned>(v: serde_cbor::Value) -> Result<O> {
-    Ok(serde_cbor::value::from_value(v)?)
+impl Serialize for IpldLink {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(LINK_NAME, &CidBytes(&self.0.to_bytes()))
+    }
 }
 
-/// Convert Obj Integer to Obj Float for matching golang version.
-pub fn hack_convert_int_to_float(value: IpldValue) -> Result<IpldValue> {
-    let mut value = value;
-    let mut func = |obj: &mut IpldValue| match obj {
-        IpldValue::Integer(ref mut i) => {
-            // all integer would convert into f64
-            *obj = IpldValue::Float(*i as f64);
-            Ok(())
+impl<'de> Deserialize<'de> for IpldLink {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(LINK_NAME, LinkVisitor)
+    }
+}
+
+struct LinkVisitor;
+
+impl<'de> de::Visitor<'de> for LinkVisitor {
+    type Value = IpldLink;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("a link")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Cid::try_from(v).map(IpldLink).map_err(de::Error::custom)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
         }
-        _ => Ok(()),
-    };
-    traverse_obj_tree(&mut value, &mut func)?;
-    Ok(value)
-}
-
-/// Convert Obj Float to Obj Integer for matching golang version.
-pub fn hack_convert_float_to_int(value: IpldValue) -> Result<IpldValue> {
-    let mut value = value;
-    let mut func = |obj: &mut IpldValue| match obj {
-        IpldValue::Float(ref mut f) => {
-            if f.fract() == 0.0 {
-                *obj = IpldValue::Integer(*f as i128);
-            }
-            Ok(())
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_bytes(self)
+    }
+}
+
+/// The serializer of `IpldValue`.
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = IpldValue;
+    type Error = IpldCoreError;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeVariant<SerializeMap>;
+
+    fn serialize_bool(self, v: bool) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_i128(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<IpldValue, IpldCoreError> {
+        i128::try_from(v)
+            .map(IpldValue::Integer)
+            .map_err(|_| ser::Error::custom(format!("integer out of range: {}", v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<IpldValue, IpldCoreError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<IpldValue, IpldCoreError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<IpldValue, IpldCoreError> {
+        if name != LINK_NAME {
+            return value.serialize(self);
         }
-        _ => Ok(()),
-    };
-    traverse_obj_tree(&mut value, &mut func)?;
-    Ok(value)
+        match value.serialize(self)? {
+            IpldValue::Bytes(bytes) => Ok(IpldValue::Link(Cid::try_from(bytes.as_slice())?)),
+            _ => Err(IpldCoreError::InvalidLink),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<IpldValue, IpldCoreError> {
+        let mut map = BTreeMap::new();
+        map.insert(SortedStr::from(variant), value.serialize(self)?);
+        Ok(IpldValue::Map(map))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, IpldCoreError> {
+        Ok(SerializeList(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, IpldCoreError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, IpldCoreError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeList>, IpldCoreError> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, IpldCoreError> {
+        Ok(SerializeMap {
+            map: BTreeMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap, IpldCoreError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeMap>, IpldCoreError> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
 }
 
-///
-pub fn convert_to_cborish_obj(mut value: IpldValue) -> Result<IpldValue> {
-    let mut func = |obj: &mut IpldValue| match obj {
-        IpldValue::Map(ref mut map) => {
-            if map.len() == 1 {
-                if let Some(link) = map.get("/") {
-                    match link {
-                        IpldValue::Text(s) => {
-                            let cid = Cid::try_from(s.as_str())?;
-                            *obj = IpldValue::Link(cid);
-                        }
-                        IpldValue::Link(cid) => {
-                            *obj = IpldValue::Link(cid.clone());
-                        }
-                        _ => return Err(IpldCborError::NonStringLink), // should not happen
-                    }
-                }
-            }
-            Ok(())
+struct SerializeList(Vec<IpldValue>);
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = IpldValue;
+    type Error = IpldCoreError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), IpldCoreError> {
+        self.0.push(to_ipld(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::List(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = IpldValue;
+    type Error = IpldCoreError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), IpldCoreError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<IpldValue, IpldCoreError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = IpldValue;
+    type Error = IpldCoreError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), IpldCoreError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<IpldValue, IpldCoreError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeMap {
+    map: BTreeMap<SortedStr, IpldValue>,
+    key: Option<SortedStr>,
+}
+
+impl SerializeMap {
+    fn insert(&mut self, key: SortedStr, value: IpldValue) -> Result<(), IpldCoreError> {
+        if self.map.contains_key(&key) {
+            return Err(ser::Error::custom(format!("duplicate map key: {}", key)));
         }
-        _ => Ok(()),
-    };
-    traverse_obj_tree(&mut value, &mut func)?;
-    Ok(value)
+        self.map.insert(key, value);
+        Ok(())
+    }
 }
 
-///
-pub fn convert_to_jsonish_obj(mut value: IpldValue) -> Result<IpldValue> {
-    let mut func = |obj: &mut IpldValue| {
-        match obj {
-            // change cid to map { "/", "string" }
-            IpldValue::Link(cid) => {
-                let link = IpldValue::Text(cid.to_string());
-                let mut map = BTreeMap::new();
-                map.insert("/".to_string(), link);
-                *obj = IpldValue::Map(map);
+impl ser::SerializeMap for SerializeMap {
+    type Ok = IpldValue;
+    type Error = IpldCoreError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), IpldCoreError> {
+        match to_ipld(key)? {
+            IpldValue::String(key) => {
+                self.key = Some(SortedStr::from(key));
                 Ok(())
             }
-            IpldValue::Map(ref mut map) => {
-                // if current map is like: { "/", cid }, change it to { "/": "string" }
-                if map.len() == 1 {
-                    if let Some(ref mut obj) = map.get("/") {
-                        match obj {
-                            IpldValue::Link(cid) => *obj = &IpldValue::Text(cid.to_string()),
-                            IpldValue::Text(_) => {} // do nothing,
-                            _ => return Err(IpldCborError::NonStringLink), // should not happen
-                        }
-                    }
+            key => Err(ser::Error::custom(format!(
+                "map key must be a string, found: {:?}",
+                key
+            ))),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), IpldCoreError> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value is called after serialize_key");
+        let value = to_ipld(value)?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<IpldValue, IpldCoreError> {
+        Ok(IpldValue::Map(self.map))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = IpldValue;
+    type Error = IpldCoreError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), IpldCoreError> {
+        let value = to_ipld(value)?;
+        self.insert(SortedStr::from(key), value)
+    }
+
+    fn end(self) -> Result<IpldValue, IpldCoreError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Serializes the variants of the enums as a map with a single key, the name of the variant.
+struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> SerializeVariant<S> {
+    fn wrap(variant: &'static str, value: IpldValue) -> Result<IpldValue, IpldCoreError> {
+        let mut map = BTreeMap::new();
+        map.insert(SortedStr::from(variant), value);
+        Ok(IpldValue::Map(map))
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = IpldValue;
+    type Error = IpldCoreError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), IpldCoreError> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<IpldValue, IpldCoreError> {
+        Self::wrap(self.variant, ser::SerializeSeq::end(self.inner)?)
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeMap> {
+    type Ok = IpldValue;
+    type Error = IpldCoreError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), IpldCoreError> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<IpldValue, IpldCoreError> {
+        Self::wrap(self.variant, ser::SerializeMap::end(self.inner)?)
+    }
+}
+
+/// The deserializer of `IpldValue`.
+struct Deserializer(IpldValue);
+
+impl Deserializer {
+    fn invalid_type<E: de::Error>(&self, exp: &dyn de::Expected) -> E {
+        let unexp = match &self.0 {
+            IpldValue::Null => de::Unexpected::Unit,
+            IpldValue::Bool(b) => de::Unexpected::Bool(*b),
+            IpldValue::Integer(_) => de::Unexpected::Other("integer"),
+            IpldValue::Float(f) => de::Unexpected::Float(*f),
+            IpldValue::String(s) => de::Unexpected::Str(s),
+            IpldValue::Bytes(b) => de::Unexpected::Bytes(b),
+            IpldValue::List(_) => de::Unexpected::Seq,
+            IpldValue::Map(_) => de::Unexpected::Map,
+            IpldValue::Link(_) => de::Unexpected::Other("link"),
+        };
+        de::Error::invalid_type(unexp, exp)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = IpldCoreError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, IpldCoreError> {
+        match self.0 {
+            IpldValue::Null => visitor.visit_unit(),
+            IpldValue::Bool(b) => visitor.visit_bool(b),
+            IpldValue::Integer(i) => {
+                if let Ok(u) = u64::try_from(i) {
+                    visitor.visit_u64(u)
+                } else if let Ok(i) = i64::try_from(i) {
+                    visitor.visit_i64(i)
+                } else {
+                    visitor.visit_i128(i)
                 }
-                Ok(())
             }
-            _ => Ok(()),
+            IpldValue::Float(f) => visitor.visit_f64(f),
+            IpldValue::String(s) => visitor.visit_string(s),
+            IpldValue::Bytes(b) => visitor.visit_byte_buf(b),
+            IpldValue::List(list) => visitor.visit_seq(SeqDeserializer(list.into_iter())),
+            IpldValue::Map(map) => visitor.visit_map(MapDeserializer {
+                iter: map.into_iter(),
+                value: None,
+            }),
+            // the visitors which don't know the links see a newtype struct.
+            IpldValue::Link(cid) => visitor.visit_newtype_struct(LinkDeserializer(cid)),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, IpldCoreError> {
+        match self.0 {
+            IpldValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, IpldCoreError> {
+        if name != LINK_NAME {
+            return visitor.visit_newtype_struct(self);
         }
-    };
-    traverse_obj_tree(&mut value, &mut func)?;
-    Ok(value)
-}
-
-fn traverse_obj_tree<F>(obj: &mut IpldValue, f: &mut F) -> Result<()>
-where
-    F: FnMut(&mut IpldValue) -> Result<()>,
-{
-    f(obj)?;
-    match obj {
-        IpldValue::Array(ref mut arr) => {
-            for v in arr.iter_mut() {
-                traverse_obj_tree(v, f)?;
+        match self.0 {
+            IpldValue::Link(cid) => visitor.visit_bytes(&cid.to_bytes()),
+            _ => Err(self.invalid_type(&"a link")),
+        }
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, IpldCoreError> {
+        match self.0 {
+            IpldValue::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            IpldValue::Map(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().expect("the map has one entry");
+                visitor.visit_enum(EnumDeserializer {
+                    variant: variant.into_inner(),
+                    value: Some(value),
+                })
             }
-            Ok(())
+            _ => Err(self.invalid_type(&"a string or a map with a single key")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct SeqDeserializer(vec::IntoIter<IpldValue>);
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = IpldCoreError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, IpldCoreError> {
+        match self.0.next() {
+            Some(value) => seed.deserialize(Deserializer(value)).map(Some),
+            None => Ok(None),
         }
-        IpldValue::Map(ref mut m) => {
-            for v in m.values_mut() {
-                traverse_obj_tree(v, f)?;
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MapDeserializer {
+    iter: btree_map::IntoIter<SortedStr, IpldValue>,
+    value: Option<IpldValue>,
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = IpldCoreError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, IpldCoreError> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key = IpldValue::String(key.into_inner());
+                seed.deserialize(Deserializer(key)).map(Some)
             }
-            Ok(())
+            None => Ok(None),
         }
-        _ => Ok(()),
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, IpldCoreError> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed is called after next_key_seed");
+        seed.deserialize(Deserializer(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
     }
 }
 
-/// Convert obj into json string.
-/// Just for testing. Please use the `to_json` method of `IpldNode`.
-#[inline]
-pub fn obj_to_json(obj: IpldValue) -> Result<String> {
-    let json_obj = convert_to_jsonish_obj(obj)?;
-    // hack handle for rust, to match go
-    let json_obj = hack_convert_float_to_int(json_obj)?;
-    Ok(serde_json::to_string(&json_obj)?)
+/// Deserializes the bytes of a link for the visitors which don't know the links.
+struct LinkDeserializer(Cid);
+
+impl<'de> de::Deserializer<'de> for LinkDeserializer {
+    type Error = IpldCoreError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, IpldCoreError> {
+        visitor.visit_byte_buf(self.0.to_bytes())
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, IpldCoreError> {
+        if name == LINK_NAME {
+            visitor.visit_bytes(&self.0.to_bytes())
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple tuple_struct map struct
+        enum identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Option<IpldValue>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = IpldCoreError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer), IpldCoreError> {
+        let variant = seed.deserialize(Deserializer(IpldValue::String(self.variant)))?;
+        Ok((variant, VariantDeserializer(self.value)))
+    }
 }
 
-/// Convert json string into Obj.
-/// Just for testing. Please use the `from_json` method of `IpldNode`.
-#[inline]
-pub fn json_to_obj(json: &str) -> Result<IpldValue> {
-    let obj = serde_json::from_str::<IpldValue>(json)?;
-    // hack handle for rust, to match go
-    let obj = hack_convert_int_to_float(obj)?;
-    convert_to_cborish_obj(obj)
+struct VariantDeserializer(Option<IpldValue>);
+
+impl VariantDeserializer {
+    fn value(self, exp: &dyn de::Expected) -> Result<Deserializer, IpldCoreError> {
+        match self.0 {
+            Some(value) => Ok(Deserializer(value)),
+            None => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, exp)),
+        }
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = IpldCoreError;
+
+    fn unit_variant(self) -> Result<(), IpldCoreError> {
+        match self.0 {
+            None | Some(IpldValue::Null) => Ok(()),
+            Some(value) => Err(Deserializer(value).invalid_type(&"a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, IpldCoreError> {
+        seed.deserialize(self.value(&"a newtype variant")?)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, IpldCoreError> {
+        de::Deserializer::deserialize_seq(self.value(&"a tuple variant")?, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, IpldCoreError> {
+        de::Deserializer::deserialize_map(self.value(&"a struct variant")?, visitor)
+    }
 }
@@ -0,0 +1,383 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+//! The strict [DAG-CBOR](https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-cbor.md)
+//! codec of `IpldValue`.
+//!
+//! Unlike the `minicbor` implementation of `IpldValue`, which accepts any CBOR that can
+//! be represented, the encoding and the decoding here follow the canonical form:
+//!
+//! - the integers are in the range `-2^64..2^64` and their headers have the minimal length;
+//! - the floats are finite and always encoded in 64 bits;
+//! - the lengths are definite and the keys of the maps are strings, sorted by length first
+//!   and then by their bytes, without duplicate;
+//! - the only tag is 42, the links, and the only simple values are `false`, `true` and `null`.
+//!
+//! So a decoded value is encoded back into the same bytes.
+//!
+//! The lenient decoding, used by default by `IpldNode`, accepts the non-canonical encodings
+//! of the same data model: the non-minimal lengths, the unsorted or duplicate keys (the
+//! last one is kept), the 16 and 32 bits floats, the non-finite floats and the non-canonical
+//! links. It still rejects the indefinite lengths, the other tags and simple values, and
+//! the trailing data.
+
+use std::convert::TryFrom;
+
+use cid::Cid;
+
+use crate::error::IpldCoreError;
+use crate::value::IpldValue;
+use crate::value_ref::IpldValueRef;
+
+/// The tag of the links.
+const CID_TAG: u64 = 42;
+/// The maximum depth of the nested lists and maps that can be decoded.
+const MAX_DEPTH: usize = 512;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_STRING: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+const FALSE: u8 = 20;
+const TRUE: u8 = 21;
+const NULL: u8 = 22;
+const FLOAT16: u8 = 25;
+const FLOAT32: u8 = 26;
+const FLOAT64: u8 = 27;
+
+/// Encodes the value in DAG-CBOR.
+///
+/// The floats must be finite and the integers must be in the range `-2^64..2^64`.
+pub fn encode(value: &IpldValue) -> Result<Vec<u8>, IpldCoreError> {
+    let mut out = Vec::new();
+    write_value(value, &mut out)?;
+    Ok(out)
+}
+
+/// Decodes the DAG-CBOR bytes into a value, rejecting the non-canonical encodings.
+pub fn decode(bytes: &[u8]) -> Result<IpldValue, IpldCoreError> {
+    Ok(decode_ref(bytes)?.to_value())
+}
+
+/// Decodes the DAG-CBOR bytes like `decode`, borrowing the strings and the byte strings
+/// from them.
+pub fn decode_ref(bytes: &[u8]) -> Result<IpldValueRef<'_>, IpldCoreError> {
+    read(bytes, true)
+}
+
+/// Decodes the CBOR bytes into a value, accepting the non-canonical encodings.
+pub fn decode_lenient(bytes: &[u8]) -> Result<IpldValue, IpldCoreError> {
+    Ok(decode_ref_lenient(bytes)?.to_value())
+}
+
+/// Decodes the CBOR bytes like `decode_lenient`, borrowing the strings and the byte strings
+/// from them.
+pub fn decode_ref_lenient(bytes: &[u8]) -> Result<IpldValueRef<'_>, IpldCoreError> {
+    read(bytes, false)
+}
+
+fn read(bytes: &[u8], strict: bool) -> Result<IpldValueRef<'_>, IpldCoreError> {
+    let mut reader = Reader {
+        bytes,
+        pos: 0,
+        strict,
+    };
+    let value = reader.value(0)?;
+    if reader.pos != bytes.len() {
+        return Err(error(format!("trailing data at offset {}", reader.pos)));
+    }
+    Ok(value)
+}
+
+fn error(msg: String) -> IpldCoreError {
+    IpldCoreError::DagCborErr(msg)
+}
+
+fn write_header(major: u8, n: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u64::from(u8::max_value()) {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n <= u64::from(u16::max_value()) {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u64::from(u32::max_value()) {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_value(value: &IpldValue, out: &mut Vec<u8>) -> Result<(), IpldCoreError> {
+    match value {
+        IpldValue::Null => out.push(MAJOR_SIMPLE << 5 | NULL),
+        IpldValue::Bool(false) => out.push(MAJOR_SIMPLE << 5 | FALSE),
+        IpldValue::Bool(true) => out.push(MAJOR_SIMPLE << 5 | TRUE),
+        IpldValue::Integer(i) => {
+            let (major, n) = if *i >= 0 {
+                (MAJOR_UNSIGNED, u64::try_from(*i))
+            } else {
+                (MAJOR_NEGATIVE, u64::try_from(-1 - *i))
+            };
+            let n = n.map_err(|_| error(format!("integer out of range: {}", i)))?;
+            write_header(major, n, out);
+        }
+        IpldValue::Float(f) => {
+            if !f.is_finite() {
+                return Err(error(format!("float is not finite: {}", f)));
+            }
+            out.push(MAJOR_SIMPLE << 5 | FLOAT64);
+            out.extend_from_slice(&f.to_bits().to_be_bytes());
+        }
+        IpldValue::String(s) => {
+            write_header(MAJOR_STRING, s.len() as u64, out);
+            out.extend_from_slice(s.as_bytes());
+        }
+        IpldValue::Bytes(bytes) => {
+            write_header(MAJOR_BYTES, bytes.len() as u64, out);
+            out.extend_from_slice(bytes);
+        }
+        IpldValue::List(list) => {
+            write_header(MAJOR_ARRAY, list.len() as u64, out);
+            for value in list {
+                write_value(value, out)?;
+            }
+        }
+        IpldValue::Map(map) => {
+            // `SortedStr` is ordered by length first, which is the canonical order.
+            write_header(MAJOR_MAP, map.len() as u64, out);
+            for (key, value) in map {
+                write_header(MAJOR_STRING, key.len() as u64, out);
+                out.extend_from_slice(key.as_bytes());
+                write_value(value, out)?;
+            }
+        }
+        IpldValue::Link(cid) => {
+            let cid = cid.to_bytes();
+            write_header(MAJOR_TAG, CID_TAG, out);
+            // the bytes of the link are prefixed by the multibase identity prefix.
+            write_header(MAJOR_BYTES, cid.len() as u64 + 1, out);
+            out.push(0);
+            out.extend_from_slice(&cid);
+        }
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Rejects the non-canonical encodings.
+    strict: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], IpldCoreError> {
+        if self.bytes.len() - self.pos < len {
+            return Err(error(format!(
+                "unexpected end of data at offset {}",
+                self.pos
+            )));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn uint(&mut self, len: usize) -> Result<u64, IpldCoreError> {
+        Ok(self
+            .take(len)?
+            .iter()
+            .fold(0, |n, byte| n << 8 | u64::from(*byte)))
+    }
+
+    /// Reads the header of an item, i.e. its major type and its argument,
+    /// which must have the minimal length in the strict decoding.
+    fn header(&mut self) -> Result<(u8, u8, u64), IpldCoreError> {
+        let offset = self.pos;
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let (n, min) = match info {
+            0..=23 => (u64::from(info), 0),
+            24 => (self.uint(1)?, 24),
+            25 => (self.uint(2)?, 0x100),
+            26 => (self.uint(4)?, 0x1_0000),
+            27 => (self.uint(8)?, 0x1_0000_0000),
+            31 => return Err(error(format!("indefinite length at offset {}", offset))),
+            _ => {
+                return Err(error(format!(
+                    "reserved additional information at offset {}",
+                    offset
+                )))
+            }
+        };
+        // the floats are the only items whose argument isn't a number.
+        if self.strict && n < min && major != MAJOR_SIMPLE {
+            return Err(error(format!("non-minimal length at offset {}", offset)));
+        }
+        Ok((major, info, n))
+    }
+
+    fn length(&mut self, n: u64) -> Result<usize, IpldCoreError> {
+        match usize::try_from(n) {
+            Ok(len) if len <= self.bytes.len() - self.pos => Ok(len),
+            _ => Err(error(format!(
+                "length {} exceeds the data at offset {}",
+                n, self.pos
+            ))),
+        }
+    }
+
+    fn string(&mut self, n: u64) -> Result<&'a str, IpldCoreError> {
+        let offset = self.pos;
+        let len = self.length(n)?;
+        let bytes = self.take(len)?;
+        std::str::from_utf8(bytes)
+            .map_err(|e| error(format!("invalid string at offset {}: {}", offset, e)))
+    }
+
+    // The lists and the maps are decoded here and the other items by `scalar`,
+    // which keeps the frames of the recursion small.
+    fn value(&mut self, depth: usize) -> Result<IpldValueRef<'a>, IpldCoreError> {
+        let offset = self.pos;
+        let (major, info, n) = self.header()?;
+        match major {
+            MAJOR_ARRAY | MAJOR_MAP if depth >= MAX_DEPTH => Err(error(format!(
+                "nesting deeper than {} at offset {}",
+                MAX_DEPTH, offset
+            ))),
+            MAJOR_ARRAY => {
+                // every item is one byte at least, it's a bound of the capacity.
+                let len = self.length(n)?;
+                let mut list = Vec::with_capacity(len);
+                for _ in 0..len {
+                    list.push(self.value(depth + 1)?);
+                }
+                Ok(IpldValueRef::List(list))
+            }
+            MAJOR_MAP => {
+                let len = self.length(n)?;
+                let mut map: Vec<(&'a str, IpldValueRef<'a>)> = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.key(map.last().map(|(last, _)| *last))?;
+                    let value = self.value(depth + 1)?;
+                    map.push((key, value));
+                }
+                Ok(IpldValueRef::Map(map))
+            }
+            _ => self.scalar(major, info, n, offset),
+        }
+    }
+
+    /// Reads the key of a map entry, which must follow the last key in the strict decoding.
+    fn key(&mut self, last: Option<&str>) -> Result<&'a str, IpldCoreError> {
+        let offset = self.pos;
+        let key = match self.header()? {
+            (MAJOR_STRING, _, n) => self.string(n)?,
+            _ => return Err(error(format!("non-string key at offset {}", offset))),
+        };
+        // the keys are sorted by length first, like `SortedStr`.
+        match last {
+            Some(last) if self.strict && (key.len(), key) <= (last.len(), last) => Err(error(
+                format!("unsorted or duplicate key `{}` at offset {}", key, offset),
+            )),
+            _ => Ok(key),
+        }
+    }
+
+    fn scalar(
+        &mut self,
+        major: u8,
+        info: u8,
+        n: u64,
+        offset: usize,
+    ) -> Result<IpldValueRef<'a>, IpldCoreError> {
+        let value = match major {
+            MAJOR_UNSIGNED => IpldValueRef::Integer(i128::from(n)),
+            MAJOR_NEGATIVE => IpldValueRef::Integer(-1 - i128::from(n)),
+            MAJOR_BYTES => {
+                let len = self.length(n)?;
+                IpldValueRef::Bytes(self.take(len)?)
+            }
+            MAJOR_STRING => IpldValueRef::String(self.string(n)?),
+            MAJOR_TAG => {
+                if n != CID_TAG {
+                    return Err(error(format!("unknown tag {} at offset {}", n, offset)));
+                }
+                let offset = self.pos;
+                let bytes = match self.header()? {
+                    (MAJOR_BYTES, _, n) => {
+                        let len = self.length(n)?;
+                        self.take(len)?
+                    }
+                    _ => return Err(error(format!("link is not bytes at offset {}", offset))),
+                };
+                match bytes.split_first() {
+                    Some((0, cid)) => {
+                        let link = Cid::try_from(cid)?;
+                        if self.strict && link.to_bytes() != cid {
+                            return Err(error(format!("non-canonical link at offset {}", offset)));
+                        }
+                        IpldValueRef::Link(link)
+                    }
+                    _ => {
+                        return Err(error(format!(
+                            "link without the identity multibase prefix at offset {}",
+                            offset
+                        )))
+                    }
+                }
+            }
+            _ => match info {
+                FALSE => IpldValueRef::Bool(false),
+                TRUE => IpldValueRef::Bool(true),
+                NULL => IpldValueRef::Null,
+                FLOAT64 | FLOAT32 | FLOAT16 => {
+                    let f = match info {
+                        FLOAT16 => f16_to_f64(n as u16),
+                        FLOAT32 => f64::from(f32::from_bits(n as u32)),
+                        _ => f64::from_bits(n),
+                    };
+                    if self.strict && info != FLOAT64 {
+                        return Err(error(format!(
+                            "float is not encoded in 64 bits at offset {}",
+                            offset
+                        )));
+                    }
+                    if self.strict && !f.is_finite() {
+                        return Err(error(format!("float is not finite at offset {}", offset)));
+                    }
+                    IpldValueRef::Float(f)
+                }
+                _ => {
+                    return Err(error(format!(
+                        "unsupported simple value at offset {}",
+                        offset
+                    )))
+                }
+            },
+        };
+        Ok(value)
+    }
+}
+
+/// Converts a half-precision float, which is only accepted by the lenient decoding.
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits >> 15 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from(bits >> 10 & 0x1f);
+    let fraction = bits & 0x3ff;
+    sign * match exponent {
+        0 => f64::from(fraction) * 2f64.powi(-24),
+        0x1f if fraction == 0 => std::f64::INFINITY,
+        0x1f => std::f64::NAN,
+        _ => f64::from(fraction + 0x400) * 2f64.powi(exponent - 25),
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+//! The [DAG-JSON](https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-json.md)
+//! codec of `IpldValue`.
+//!
+//! - links are encoded as `{"/":"<cid>"}` and bytes as `{"/":{"bytes":"<base64>"}}`,
+//!   with the standard base64 alphabet without padding;
+//! - the keys of the maps are sorted by their UTF-8 bytes and there is no whitespace,
+//!   so the encoding is deterministic;
+//! - the decoding rejects duplicated keys and the maps with a `/` key that are neither
+//!   a link nor bytes, since they are ambiguous;
+//! - the integers are in the range `-2^64..2^64`, like in DAG-CBOR.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Write;
+
+use cid::Cid;
+use serde::de::{self, Deserialize, DeserializeSeed};
+
+use crate::error::IpldCoreError;
+use crate::value::{IpldValue, SortedStr};
+
+const RESERVED_KEY: &str = "/";
+const BYTES_KEY: &str = "bytes";
+/// With the `arbitrary_precision` feature, serde_json passes the numbers out of the range
+/// of `i64` and `u64` as a map, whose only key is this token and whose value is the literal.
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// Encodes the value in DAG-JSON.
+///
+/// The floats must be finite and the integers must be in the range `-2^64..2^64`.
+pub fn encode(value: &IpldValue) -> Result<Vec<u8>, IpldCoreError> {
+    let mut out = Vec::new();
+    write_value(value, &mut out)?;
+    Ok(out)
+}
+
+/// Decodes the DAG-JSON bytes into a value.
+///
+/// The integers must be in the range `-2^64..2^64`, like for the encoding.
+pub fn decode(bytes: &[u8]) -> Result<IpldValue, IpldCoreError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = DagJsonSeed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+fn write_value(value: &IpldValue, out: &mut Vec<u8>) -> Result<(), IpldCoreError> {
+    match value {
+        IpldValue::Null => out.extend_from_slice(b"null"),
+        IpldValue::Bool(true) => out.extend_from_slice(b"true"),
+        IpldValue::Bool(false) => out.extend_from_slice(b"false"),
+        IpldValue::Integer(i) => {
+            if !in_range(*i) {
+                return Err(IpldCoreError::DagJsonErr(format!(
+                    "integer out of range: {}",
+                    i
+                )));
+            }
+            write!(out, "{}", i).expect("write to vec never fails");
+        }
+        IpldValue::Float(f) => {
+            if !f.is_finite() {
+                return Err(IpldCoreError::DagJsonErr(format!(
+                    "float is not finite: {}",
+                    f
+                )));
+            }
+            serde_json::to_writer(&mut *out, f)?;
+        }
+        IpldValue::String(s) => serde_json::to_writer(&mut *out, s)?,
+        IpldValue::Bytes(bytes) => {
+            out.extend_from_slice(br#"{"/":{"bytes":""#);
+            out.extend_from_slice(base64::encode_config(bytes, base64::STANDARD_NO_PAD).as_bytes());
+            out.extend_from_slice(br#""}}"#);
+        }
+        IpldValue::List(list) => {
+            out.push(b'[');
+            for (i, value) in list.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(value, out)?;
+            }
+            out.push(b']');
+        }
+        IpldValue::Map(map) => {
+            // the map is sorted by length first, DAG-JSON sorts the keys by their bytes.
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            out.push(b'{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, key.as_str())?;
+                out.push(b':');
+                write_value(value, out)?;
+            }
+            out.push(b'}');
+        }
+        IpldValue::Link(cid) => {
+            out.extend_from_slice(br#"{"/":""#);
+            out.extend_from_slice(cid.to_string().as_bytes());
+            out.extend_from_slice(br#""}"#);
+        }
+    }
+    Ok(())
+}
+
+/// Deserializes a DAG-JSON value, interpreting the maps with a `/` key.
+struct DagJsonSeed;
+
+impl<'de> DeserializeSeed<'de> for DagJsonSeed {
+    type Value = IpldValue;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(DagJsonVisitor)
+    }
+}
+
+/// The value of a map entry or a list element.
+struct DagJsonValue(IpldValue);
+
+impl<'de> Deserialize<'de> for DagJsonValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DagJsonSeed.deserialize(deserializer).map(DagJsonValue)
+    }
+}
+
+struct DagJsonVisitor;
+
+impl<'de> de::Visitor<'de> for DagJsonVisitor {
+    type Value = IpldValue;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("any valid DAG-JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(IpldValue::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(IpldValue::Integer(v.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(IpldValue::Integer(v.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(IpldValue::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(IpldValue::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(IpldValue::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(IpldValue::Null)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut list = Vec::new();
+        while let Some(DagJsonValue(value)) = seq.next_element()? {
+            list.push(value);
+        }
+        Ok(IpldValue::List(list))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = BTreeMap::new();
+        while let Some((key, DagJsonValue(value))) = map.next_entry::<String, _>()? {
+            if key == NUMBER_TOKEN && entries.is_empty() {
+                if let IpldValue::String(literal) = &value {
+                    return number(literal).map_err(de::Error::custom);
+                }
+            }
+            if entries
+                .insert(SortedStr::from(key.clone()), value)
+                .is_some()
+            {
+                return Err(de::Error::custom(format!("duplicate map key: {}", key)));
+            }
+        }
+        match entries.remove(&SortedStr::from(RESERVED_KEY)) {
+            Some(value) if entries.is_empty() => reserved(value).map_err(de::Error::custom),
+            Some(_) => Err(de::Error::custom(
+                "a map with a `/` key can't have other keys",
+            )),
+            None => Ok(IpldValue::Map(entries)),
+        }
+    }
+}
+
+/// Whether the integer is in the range `-2^64..2^64` of DAG-CBOR.
+fn in_range(i: i128) -> bool {
+    i >= -(1 << 64) && i < 1 << 64
+}
+
+/// Parses the literal of a number out of the range of `i64` and `u64`,
+/// i.e. an integer or a float like `1e20`.
+fn number(literal: &str) -> Result<IpldValue, String> {
+    if literal == "-0" || literal.contains(|c| c == '.' || c == 'e' || c == 'E') {
+        return match literal.parse::<f64>() {
+            Ok(f) if f.is_finite() => Ok(IpldValue::Float(f)),
+            _ => Err(format!("float out of range: {}", literal)),
+        };
+    }
+    match literal.parse::<i128>() {
+        Ok(i) if in_range(i) => Ok(IpldValue::Integer(i)),
+        _ => Err(format!("integer out of range: {}", literal)),
+    }
+}
+
+/// Interprets the value of the `/` key, which is a link or bytes.
+fn reserved(value: IpldValue) -> Result<IpldValue, String> {
+    match value {
+        IpldValue::String(cid) => Cid::try_from(cid.as_str())
+            .map(IpldValue::Link)
+            .map_err(|e| format!("invalid link `{}`: {}", cid, e)),
+        IpldValue::Map(mut map) => match map.remove(&SortedStr::from(BYTES_KEY)) {
+            Some(IpldValue::String(bytes)) if map.is_empty() => {
+                if bytes.ends_with('=') {
+                    return Err("padded base64 bytes".to_string());
+                }
+                base64::decode_config(&bytes, base64::STANDARD_NO_PAD)
+                    .map(IpldValue::Bytes)
+                    .map_err(|e| format!("invalid base64 bytes: {}", e))
+            }
+            _ => Err("the value of a `/` map must be a `bytes` string".to_string()),
+        },
+        _ => Err("the value of a `/` key must be a link string or a `bytes` map".to_string()),
+    }
+}
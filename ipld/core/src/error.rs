@@ -15,6 +15,12 @@ pub enum IpldCoreError {
     /// CBOR deserialization error.
     #[error("core decode error: {0}")]
     CborDecodeErr(#[from] minicbor::decode::Error),
+    /// Strict DAG-CBOR serialization/deserialization error.
+    #[error("dag-cbor error: {0}")]
+    DagCborErr(String),
+    /// DAG-JSON serialization/deserialization error.
+    #[error("dag-json error: {0}")]
+    DagJsonErr(String),
     /// CID error.
     #[error("cid error: {0}")]
     CidErr(#[from] cid::Error),
@@ -45,6 +51,26 @@ pub enum IpldCoreError {
     /// Failure when converting to Obj.
     #[error("Failure when converting to Obj, reason: {0}")]
     ObjErr(String),
+    /// Invalid path of a value.
+    #[error("invalid path: {0}")]
+    InvalidPath(String),
+    /// The value isn't of the expected kind.
+    #[error("invalid type: expected {expected}, found {found}")]
+    InvalidType {
+        /// The expected type.
+        expected: &'static str,
+        /// The kind of the value.
+        found: &'static str,
+    },
+    /// The codec isn't supported.
+    #[error("unsupported codec: {0:?}")]
+    UnsupportedCodec(cid::Codec),
+    /// Invalid selector.
+    #[error("invalid selector: {0}")]
+    InvalidSelector(String),
+    /// IPLD format error, e.g. failing to get a node.
+    #[error("ipld format error: {0}")]
+    FormatErr(#[from] ipld_format::FormatError),
     /// Other error.
     #[error("other error: {0}")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
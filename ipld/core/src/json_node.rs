@@ -0,0 +1,131 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use bytes::Bytes;
+use cid::{Cid, Codec, ExtCode};
+use either::Either;
+
+use block_format::{BasicBlock, Block};
+use ipld_format::{FormatError, Link, Node, NodeStat, Resolver};
+
+use crate::dag_json;
+use crate::error::IpldCoreError;
+use crate::node::{compute, compute_stat, resolve, tree};
+use crate::value::IpldValue;
+
+/// `DagJsonNode` represents an IPLD node encoded in DAG-JSON.
+///
+/// It resolves paths like `IpldNode`, which is the DAG-CBOR node of the same data model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DagJsonNode {
+    obj: IpldValue,
+    tree: Vec<String>,
+    links: Vec<Link>,
+    raw: Bytes,
+    cid: Cid,
+    stat: NodeStat,
+}
+
+impl DagJsonNode {
+    fn new_with_obj<B: Block + ?Sized>(block: &B, obj: IpldValue) -> Result<Self, IpldCoreError> {
+        let (tree, links) = compute(&obj)?;
+        let stat = compute_stat(block, &links, encoded_link_size)?;
+        Ok(Self {
+            obj,
+            tree,
+            links,
+            raw: block.to_bytes(),
+            cid: block.cid().clone(),
+            stat,
+        })
+    }
+
+    /// Encodes the value in DAG-JSON into a node, whose CID is version 1 hashed with `hash_type`.
+    pub fn wrap_object(value: &IpldValue, hash_type: ExtCode) -> Result<Self, IpldCoreError> {
+        let data = dag_json::encode(value)?;
+        let hash = hash_type.digest(&data);
+        let cid = Cid::new_v1(Codec::DagJSON, hash);
+        let block = BasicBlock::new_with_cid(data.into(), cid)?;
+        Self::new_with_obj(&block, value.clone())
+    }
+
+    /// Decodes the DAG-JSON bytes into a node, whose CID is version 1 hashed with `hash_type`.
+    ///
+    /// The bytes are re-encoded, so the node is canonical.
+    pub fn from_json(json: &[u8], hash_type: ExtCode) -> Result<Self, IpldCoreError> {
+        let value = dag_json::decode(json)?;
+        Self::wrap_object(&value, hash_type)
+    }
+
+    /// Decodes a DAG-JSON block into a node, which keeps the bytes and the CID of the block.
+    pub fn from_block<B: Block + ?Sized>(block: &B) -> Result<Self, IpldCoreError> {
+        let codec = block.cid().codec();
+        if codec != Codec::DagJSON {
+            return Err(IpldCoreError::DagJsonErr(format!(
+                "expected a dag-json block, found codec: {:?}",
+                codec
+            )));
+        }
+        let value = dag_json::decode(block.raw_data())?;
+        Self::new_with_obj(block, value)
+    }
+
+    /// Returns obj of the node.
+    pub fn obj(&self) -> &IpldValue {
+        &self.obj
+    }
+}
+
+/// Returns the size of the link encoded in DAG-JSON, i.e. `{"/":"<cid>"}`.
+fn encoded_link_size(cid: &Cid) -> usize {
+    8 + cid.to_string().len()
+}
+
+impl Resolver for DagJsonNode {
+    type Output = Either<Link, IpldValue>;
+
+    /// Resolve resolves a given path, and returns the object found at the end, as well
+    /// as the possible tail of the path that was not resolved.
+    fn resolve(&self, path: &[&str]) -> ipld_format::Result<(Self::Output, Vec<String>)> {
+        resolve(&self.obj, path)
+    }
+
+    /// Tree returns a flatten array of paths at the given path for the given depth.
+    fn tree(&self, path: &str, depth: Option<usize>) -> Vec<String> {
+        tree(&self.tree, path, depth)
+    }
+}
+
+impl Node for DagJsonNode {
+    fn resolve_link(&self, path: &[&str]) -> ipld_format::Result<(Link, Vec<String>)> {
+        let (either, rest) = self.resolve(path)?;
+
+        match either {
+            Either::Left(link) => Ok((link, rest)),
+            Either::Right(_) => Err(FormatError::NonLink),
+        }
+    }
+
+    fn links(&self) -> Vec<&Link> {
+        self.links.iter().collect()
+    }
+
+    fn stat(&self) -> ipld_format::Result<&NodeStat> {
+        Ok(&self.stat)
+    }
+
+    fn size(&self) -> u64 {
+        self.raw.len() as u64
+    }
+}
+
+impl Block for DagJsonNode {
+    fn raw_data(&self) -> &Bytes {
+        &self.raw
+    }
+}
+
+impl AsRef<Cid> for DagJsonNode {
+    fn as_ref(&self) -> &Cid {
+        &self.cid
+    }
+}
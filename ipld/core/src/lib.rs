@@ -4,12 +4,26 @@
 
 #![deny(missing_docs)]
 
+mod codec;
+mod convert;
+pub mod dag_cbor;
+pub mod dag_json;
 mod error;
+mod json_node;
+mod macros;
 mod node;
+mod rehash;
+pub mod selector;
 mod value;
+mod value_ref;
 
 pub use ipld_format::{FormatError, Link, Node, NodeStat, Resolver};
 
+pub use self::codec::register_codecs;
+pub use self::convert::{from_ipld, to_ipld, IpldLink};
 pub use self::error::{IpldCoreError, Result};
+pub use self::json_node::DagJsonNode;
 pub use self::node::IpldNode;
-pub use self::value::IpldValue;
+pub use self::rehash::rehash;
+pub use self::value::{IpldValue, Links, SortedStr};
+pub use self::value_ref::IpldValueRef;
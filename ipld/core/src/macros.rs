@@ -0,0 +1,109 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+/// Constructs an `IpldValue` from a JSON-like literal.
+///
+/// The keys of the maps are expressions converted into strings, the values are `null`,
+/// nested lists and maps, or expressions converted by `IpldValue::from`, e.g. a `Cid`
+/// is a link and a `&[u8]` is a byte string.
+///
+/// ```
+/// # use ipld_core::{ipld, IpldValue};
+/// let value = ipld!({
+///     "name": "foo",
+///     "size": 1 + 2,
+///     "chunks": [null, -1, 1.5, [true], {}],
+///     "data": &b"bytes"[..],
+/// });
+/// assert_eq!(value.get_path(&["chunks", "1"]), Some(&IpldValue::Integer(-1)));
+/// ```
+#[macro_export]
+macro_rules! ipld {
+    ($($ipld:tt)+) => {
+        $crate::ipld_internal!($($ipld)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! ipld_internal {
+    // The elements of a list, munched one by one.
+    (@list [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+    (@list [$($elems:expr),*]) => {
+        vec![$($elems),*]
+    };
+    (@list [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::ipld_internal!(@list [$($elems,)* $crate::ipld_internal!(null)] $($rest)*)
+    };
+    (@list [$($elems:expr,)*] [$($list:tt)*] $($rest:tt)*) => {
+        $crate::ipld_internal!(@list [$($elems,)* $crate::ipld_internal!([$($list)*])] $($rest)*)
+    };
+    (@list [$($elems:expr,)*] {$($map:tt)*} $($rest:tt)*) => {
+        $crate::ipld_internal!(@list [$($elems,)* $crate::ipld_internal!({$($map)*})] $($rest)*)
+    };
+    (@list [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::ipld_internal!(@list [$($elems,)* $crate::ipld_internal!($next),] $($rest)*)
+    };
+    (@list [$($elems:expr,)*] $last:expr) => {
+        $crate::ipld_internal!(@list [$($elems,)* $crate::ipld_internal!($last)])
+    };
+    (@list [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::ipld_internal!(@list [$($elems,)*] $($rest)*)
+    };
+
+    // The entries of a map: `@map $map (key tokens) (rest) (copy of rest)`.
+    (@map $map:ident () () ()) => {};
+    (@map $map:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        let _ = $map.insert(($($key)+).into(), $value);
+        $crate::ipld_internal!(@map $map () ($($rest)*) ($($rest)*));
+    };
+    (@map $map:ident [$($key:tt)+] ($value:expr)) => {
+        let _ = $map.insert(($($key)+).into(), $value);
+    };
+    (@map $map:ident ($($key:tt)+) (: null $($rest:tt)*) $copy:tt) => {
+        $crate::ipld_internal!(@map $map [$($key)+] ($crate::ipld_internal!(null)) $($rest)*);
+    };
+    (@map $map:ident ($($key:tt)+) (: [$($list:tt)*] $($rest:tt)*) $copy:tt) => {
+        $crate::ipld_internal!(@map $map [$($key)+] ($crate::ipld_internal!([$($list)*])) $($rest)*);
+    };
+    (@map $map:ident ($($key:tt)+) (: {$($inner:tt)*} $($rest:tt)*) $copy:tt) => {
+        $crate::ipld_internal!(@map $map [$($key)+] ($crate::ipld_internal!({$($inner)*})) $($rest)*);
+    };
+    (@map $map:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*) $copy:tt) => {
+        $crate::ipld_internal!(@map $map [$($key)+] ($crate::ipld_internal!($value)) , $($rest)*);
+    };
+    (@map $map:ident ($($key:tt)+) (: $value:expr) $copy:tt) => {
+        $crate::ipld_internal!(@map $map [$($key)+] ($crate::ipld_internal!($value)));
+    };
+    // A parenthesized key is an expression, munched at once.
+    (@map $map:ident () (($key:expr) : $($rest:tt)*) $copy:tt) => {
+        $crate::ipld_internal!(@map $map ($key) (: $($rest)*) (: $($rest)*));
+    };
+    (@map $map:ident ($($key:tt)*) ($tt:tt $($rest:tt)*) $copy:tt) => {
+        $crate::ipld_internal!(@map $map ($($key)* $tt) ($($rest)*) ($($rest)*));
+    };
+
+    (null) => {
+        $crate::IpldValue::Null
+    };
+    ([]) => {
+        $crate::IpldValue::List(vec![])
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::IpldValue::List($crate::ipld_internal!(@list [] $($tt)+))
+    };
+    ({}) => {
+        $crate::IpldValue::Map(::std::collections::BTreeMap::new())
+    };
+    ({ $($tt:tt)+ }) => {
+        $crate::IpldValue::Map({
+            let mut map = ::std::collections::BTreeMap::<$crate::SortedStr, $crate::IpldValue>::new();
+            $crate::ipld_internal!(@map map () ($($tt)+) ($($tt)+));
+            map
+        })
+    };
+    ($other:expr) => {
+        $crate::IpldValue::from($other)
+    };
+}
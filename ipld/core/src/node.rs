@@ -6,59 +6,91 @@ use bytes::Bytes;
 use cid::{Cid, Codec, ExtCode};
 use either::Either;
 use minicbor::{encode, Encoder};
+use once_cell::sync::OnceCell;
 use serde::ser;
 
 use block_format::{BasicBlock, Block};
 use ipld_format::{FormatError, Link, Node, NodeStat, Resolver};
 
+use crate::convert::to_ipld;
+use crate::dag_cbor;
+use crate::dag_json;
 use crate::error::IpldCoreError;
-use crate::value::IpldValue;
+use crate::value::{IpldValue, SortedStr};
+use crate::value_ref::IpldValueRef;
 
 /// `IpldNode` represents an IPLD node.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// The object, the tree, the links and the stat of the node are computed on first use.
+#[derive(Debug, Clone)]
 pub struct IpldNode {
-    obj: IpldValue,
-    tree: Vec<String>,
-    links: Vec<Link>,
+    obj: OnceCell<IpldValue>,
+    tree: OnceCell<Vec<String>>,
+    links: OnceCell<Vec<Link>>,
     raw: Bytes,
     cid: Cid,
+    stat: OnceCell<NodeStat>,
 }
 
 impl IpldNode {
-    fn new_with_obj<B: Block>(block: &B, obj: IpldValue) -> Result<Self, IpldCoreError> {
-        let (tree, links) = compute(&obj)?;
-        Ok(Self {
-            obj,
-            tree,
-            links,
-            raw: block.raw_data().clone(),
+    fn new<B: Block + ?Sized>(block: &B) -> Self {
+        Self {
+            obj: OnceCell::new(),
+            tree: OnceCell::new(),
+            links: OnceCell::new(),
+            raw: block.to_bytes(),
             cid: block.cid().clone(),
-        })
+            stat: OnceCell::new(),
+        }
+    }
+
+    pub(crate) fn new_with_obj<B: Block + ?Sized>(
+        block: &B,
+        obj: IpldValue,
+    ) -> Result<Self, IpldCoreError> {
+        let node = Self::new(block);
+        let _ = node.obj.set(obj);
+        Ok(node)
     }
 
     /// Deserialize a CBOR object into an IPLD Node.
     ///
     /// Equivalent to the `Decode` in `go-ipld-cbor`
+    ///
+    /// The bytes are decoded by `dag_cbor::decode_lenient` and encoded again, so the data
+    /// of the node is canonicalized.
     pub fn from_cbor(cbor: &[u8], hash_type: ExtCode) -> Result<Self, IpldCoreError> {
-        let value = minicbor::decode::<IpldValue>(cbor)?;
-        println!("Value: {:?}", value);
+        let value = dag_cbor::decode_lenient(cbor)?;
         Self::wrap_object(&value, hash_type)
     }
 
-    /// Serialize the object of IPLD Node into its CBOR serialized byte representation.
+    /// Deserialize a canonical DAG-CBOR object into an IPLD Node, whose data is the bytes.
+    ///
+    /// The non-canonical bytes are rejected, see `dag_cbor::decode`.
+    pub fn from_cbor_strict(cbor: &[u8], hash_type: ExtCode) -> Result<Self, IpldCoreError> {
+        let value = dag_cbor::decode(cbor)?;
+        let cid = Cid::new_v1(Codec::DagCBOR, hash_type.digest(cbor));
+        let block = BasicBlock::new_with_cid(cbor.to_vec().into(), cid)?;
+        Self::new_with_obj(&block, value)
+    }
+
+    /// Serialize the object of IPLD Node into its CBOR serialized byte representation,
+    /// i.e. the data of the block.
     pub fn to_cbor(&self) -> Result<Vec<u8>, IpldCoreError> {
-        Ok(minicbor::to_vec(&self.obj)?)
+        Ok(self.raw.to_vec())
     }
 
-    /// Deserialize the JSON object into IPLD Node.
+    /// Deserialize the DAG-JSON object into IPLD Node, see `dag_json::decode`.
     pub fn from_json(json: &str, hash_type: ExtCode) -> Result<Self, IpldCoreError> {
-        let value = serde_json::from_str::<IpldValue>(json)?;
+        let value = dag_json::decode(json.as_bytes())?;
         Self::wrap_object(&value, hash_type)
     }
 
-    /// Serialize the object of IPLD Node into its json string representation.
+    /// Serialize the object of IPLD Node into its DAG-JSON string representation,
+    /// see `dag_json::encode`.
     pub fn to_json(&self) -> Result<String, IpldCoreError> {
-        Ok(serde_json::to_string(&self.obj)?)
+        let json = dag_json::encode(self.obj())?;
+        Ok(String::from_utf8(json).expect("DAG-JSON is valid UTF-8"))
     }
 
     /// Convert an CBOR object into IPLD Node.
@@ -67,8 +99,7 @@ impl IpldNode {
         hash_type: ExtCode,
     ) -> Result<Self, IpldCoreError> {
         let data = minicbor::to_vec(value)?;
-        // println!("{:?}", data);
-        let value = minicbor::decode::<IpldValue>(&data)?;
+        let value = dag_cbor::decode_lenient(&data)?;
         let hash = hash_type.digest(&data);
         // println!("Hash: {:?}", hash.as_bytes());
         let cid = Cid::new_v1(Codec::DagCBOR, hash);
@@ -76,29 +107,92 @@ impl IpldNode {
         Self::new_with_obj(&block, value)
     }
 
+    /// Converts a serializable object into an IPLD Node, encoded with the codec.
+    ///
+    /// The object is converted by `to_ipld`, so its CIDs must be wrapped in `IpldLink`
+    /// to be links. Only the `dag-cbor` codec is supported.
+    pub fn from_object_with_codec<T: ser::Serialize + ?Sized>(
+        obj: &T,
+        hash_type: ExtCode,
+        codec: Codec,
+    ) -> Result<Self, IpldCoreError> {
+        if codec != Codec::DagCBOR {
+            return Err(IpldCoreError::UnsupportedCodec(codec));
+        }
+        let value = to_ipld(obj)?;
+        let data = dag_cbor::encode(&value)?;
+        let cid = Cid::new_v1(codec, hash_type.digest(&data));
+        let block = BasicBlock::new_with_cid(data.into(), cid)?;
+        Self::new_with_obj(&block, value)
+    }
+
     /// Decode a CBOR encoded Block into an IPLD Node.
     ///
     /// In general, you should not be calling this method directly.
     /// Instead, you should be calling the `from_cbor` or `from_json`` method.
-    pub fn from_block<B: Block>(block: &B) -> Result<Self, IpldCoreError> {
-        let value = minicbor::decode::<IpldValue>(block.raw_data())?;
-        Self::new_with_obj(block, value)
+    ///
+    /// The block is decoded by `dag_cbor::decode_ref_lenient` and its data is kept as is,
+    /// so the CID of the node is the CID of the block. It's decoded without copying its
+    /// data, only the links are kept, the object and the tree are decoded from the block
+    /// on first use.
+    pub fn from_block<B: Block + ?Sized>(block: &B) -> Result<Self, IpldCoreError> {
+        let value = dag_cbor::decode_ref_lenient(block.raw_data())?;
+        Ok(Self::new_with_links(block, &value))
+    }
+
+    /// Decode a canonical DAG-CBOR encoded Block into an IPLD Node like `from_block`.
+    ///
+    /// The non-canonical blocks are rejected, see `dag_cbor::decode`.
+    pub fn from_block_strict<B: Block + ?Sized>(block: &B) -> Result<Self, IpldCoreError> {
+        let value = dag_cbor::decode_ref(block.raw_data())?;
+        Ok(Self::new_with_links(block, &value))
+    }
+
+    fn new_with_links<B: Block + ?Sized>(block: &B, value: &IpldValueRef) -> Self {
+        let node = Self::new(block);
+        let _ = node.links.set(compute_links_ref(value));
+        node
     }
 
     /// Returns obj of the IPLD Node.
     pub fn obj(&self) -> &IpldValue {
-        &self.obj
+        self.obj.get_or_init(|| self.decode_ref().to_value())
+    }
+
+    /// Decodes the block of the IPLD Node, borrowing the strings and the byte strings
+    /// from it instead of copying them like `obj`.
+    ///
+    /// The block is decoded leniently like in `from_block`.
+    pub fn value_ref(&self) -> Result<IpldValueRef<'_>, IpldCoreError> {
+        dag_cbor::decode_ref_lenient(&self.raw)
+    }
+
+    /// Decodes the block, which is valid CBOR once the node is created.
+    fn decode_ref(&self) -> IpldValueRef<'_> {
+        self.value_ref()
+            .expect("the block of IpldNode is valid CBOR")
+    }
+
+    fn links_ref(&self) -> &[Link] {
+        self.links.get_or_init(|| compute_links(self.obj()))
+    }
+}
+
+// The object is decoded from the block, so it's equal if the blocks are.
+impl PartialEq for IpldNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cid == other.cid && self.raw == other.raw
     }
 }
 
 // Implement JSON serialization for IpldNode.
-// Equivalent to the `to_json`  of `IpldNode`.
+// The keys are in the order of `IpldValue`, `to_json` gives the canonical DAG-JSON.
 impl ser::Serialize for IpldNode {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: ser::Serializer,
     {
-        self.obj.serialize(serializer)
+        self.obj().serialize(serializer)
     }
 }
 
@@ -106,7 +200,7 @@ impl ser::Serialize for IpldNode {
 // Equivalent to the `to_cbor`  of `IpldNode`.
 impl encode::Encode for IpldNode {
     fn encode<W: encode::Write>(&self, e: &mut Encoder<W>) -> Result<(), encode::Error<W::Error>> {
-        e.encode(&self.obj)?.ok()
+        e.encode(self.obj())?.ok()
     }
 }
 
@@ -128,86 +222,17 @@ impl Resolver for IpldNode {
     /// Resolve resolves a given path, and returns the object found at the end, as well
     /// as the possible tail of the path that was not resolved.
     fn resolve(&self, path: &[&str]) -> ipld_format::Result<(Self::Output, Vec<String>)> {
-        let mut cur = &self.obj;
-        for (index, val) in path.iter().enumerate() {
-            match cur {
-                IpldValue::Map(m) => {
-                    cur = m.get::<str>(val).ok_or_else(|| {
-                        FormatError::Other(Box::new(IpldCoreError::NoSuchLink((*val).to_string())))
-                    })?;
-                }
-                IpldValue::List(arr) => {
-                    let index =
-                        usize::from_str(val).map_err(|e| FormatError::Other(Box::new(e)))?;
-                    cur = arr.get(index).ok_or_else(|| {
-                        FormatError::Other(Box::new(IpldCoreError::NoSuchLink(format!(
-                            "array index out of range[{}]",
-                            index
-                        ))))
-                    })?;
-                }
-                IpldValue::Link(cid) => {
-                    let link = Link::new_with_cid(cid.clone());
-                    return Ok((
-                        Either::Left(link),
-                        path.iter().skip(index).map(|s| (*s).to_string()).collect(),
-                    ));
-                }
-                _ => return Err(FormatError::Other(Box::new(IpldCoreError::NoLinks))),
-            }
-        }
-        if let IpldValue::Link(cid) = cur {
-            let link = Link::new_with_cid(cid.clone());
-            return Ok((Either::Left(link), vec![]));
-        }
-        Ok((Either::Right(cur.clone()), vec![]))
+        resolve(self.obj(), path)
     }
 
     /// Tree returns a flatten array of paths at the given path for the given depth.
     fn tree(&self, path: &str, depth: Option<usize>) -> Vec<String> {
-        if path.is_empty() && depth.is_none() {
-            return self.tree.clone();
-        }
-        let mut out = vec![];
-        for t in &self.tree {
-            if !t.starts_with(path) {
-                continue;
-            }
-            // start from path length.
-            // e.g. tree item like "123456/123", path is "123", then s would be "/123"
-            // `skip_while` would ignore first chars until meet "/"
-            // `skip_while` plus `trim_start_matches` would equal to `strings.TrimLeft` in GO
-            // but `s` is allocated, use char.utf8_len to peek slice for `t` could avoid allocate.
-            let skip = t.chars().skip(path.len()); // equal to `[len(path):]`
-            let s: String = if !path.is_empty() {
-                // only filter when path is not "", notice GO not impl for this!
-                skip.skip_while(|c| *c != '/').collect()
-            } else {
-                skip.collect()
-            };
-            // "/123/123" would be "123/123", "//123/123" would be "123/123"
-            let sub = s.trim_start_matches('/');
-            if sub.is_empty() {
-                // means current tree have no child
-                continue;
-            }
-
-            match depth {
-                None => {
-                    // means not filter by depth
-                    out.push(sub.to_string());
-                    continue;
-                }
-                Some(dep) => {
-                    // for example sub like "123/123/123", and depth is 2, would not peek
-                    let parts = sub.split('/').collect::<Vec<_>>();
-                    if parts.len() <= dep {
-                        out.push(sub.to_string());
-                    }
-                }
-            }
-        }
-        out
+        tree(
+            self.tree
+                .get_or_init(|| compute_tree_ref(&self.decode_ref())),
+            path,
+            depth,
+        )
     }
 }
 
@@ -217,18 +242,22 @@ impl Node for IpldNode {
 
         match either {
             Either::Left(link) => Ok((link, rest)),
-            Either::Right(_) => Err(FormatError::Other(Box::new(IpldCoreError::NonLink))),
+            Either::Right(_) => Err(FormatError::NonLink),
         }
     }
 
     fn links(&self) -> Vec<&Link> {
-        self.links.iter().collect()
+        self.links_ref().iter().collect()
     }
 
     /// Stat returns stats about the Node.
+    ///
+    /// The cumulative size is the size of the block, see `ipld_format::daghelpers::stat`
+    /// for the cumulative size of the whole DAG.
     fn stat(&self) -> ipld_format::Result<&NodeStat> {
-        // TODO: implement?
-        unimplemented!()
+        self.stat
+            .get_or_try_init(|| compute_stat(self, self.links_ref(), encoded_link_size))
+            .map_err(|e| FormatError::Other(Box::new(e)))
     }
 
     // Size returns the size of the binary representation of the Node.
@@ -237,44 +266,219 @@ impl Node for IpldNode {
     }
 }
 
-fn compute(obj: &IpldValue) -> Result<(Vec<String>, Vec<Link>), IpldCoreError> {
-    let mut tree = vec![];
-    let mut links = vec![];
-    let mut func = |name: &str, obj: &IpldValue| {
-        if !name.is_empty() {
-            // [1:]
-            let name = name.chars().skip(1).collect::<String>();
-            tree.push(name);
+/// Resolves the path through the object, stopping at the first link.
+///
+/// The errors are `FormatError::SegmentFailed`, with the index of the failing segment.
+pub(crate) fn resolve(
+    obj: &IpldValue,
+    path: &[&str],
+) -> ipld_format::Result<(Either<Link, IpldValue>, Vec<String>)> {
+    let mut cur = obj;
+    for (index, val) in path.iter().enumerate() {
+        let failed = |e: Box<dyn std::error::Error + Send + Sync>| FormatError::SegmentFailed {
+            index,
+            source: Box::new(FormatError::Other(e)),
+        };
+        match cur {
+            IpldValue::Map(m) => {
+                // the keys are sorted by length first, they can't be looked up by `str`.
+                cur = m.get(&SortedStr::from(*val)).ok_or_else(|| {
+                    failed(Box::new(IpldCoreError::NoSuchLink((*val).to_string())))
+                })?;
+            }
+            IpldValue::List(arr) => {
+                let i = usize::from_str(val).map_err(|e| failed(Box::new(e)))?;
+                cur = arr.get(i).ok_or_else(|| {
+                    failed(Box::new(IpldCoreError::NoSuchLink(format!(
+                        "array index out of range[{}]",
+                        i
+                    ))))
+                })?;
+            }
+            IpldValue::Link(cid) => {
+                let link = Link::new_with_cid(cid.clone());
+                return Ok((
+                    Either::Left(link),
+                    path.iter().skip(index).map(|s| (*s).to_string()).collect(),
+                ));
+            }
+            _ => return Err(failed(Box::new(IpldCoreError::NoLinks))),
         }
-        if let IpldValue::Link(cid) = obj {
-            links.push(Link::new_with_cid(cid.clone()))
+    }
+    if let IpldValue::Link(cid) = cur {
+        let link = Link::new_with_cid(cid.clone());
+        return Ok((Either::Left(link), vec![]));
+    }
+    Ok((Either::Right(cur.clone()), vec![]))
+}
+
+/// Returns the paths of the tree under the path, up to the depth.
+pub(crate) fn tree(tree: &[String], path: &str, depth: Option<usize>) -> Vec<String> {
+    if path.is_empty() && depth.is_none() {
+        return tree.to_vec();
+    }
+    let mut out = vec![];
+    for t in tree {
+        if !t.starts_with(path) {
+            continue;
         }
-        Ok(())
-    };
-    traverse(obj, "", &mut func)?;
-    Ok((tree, links))
+        // start from path length.
+        // e.g. tree item like "123456/123", path is "123", then s would be "/123"
+        // `skip_while` would ignore first chars until meet "/"
+        // `skip_while` plus `trim_start_matches` would equal to `strings.TrimLeft` in GO
+        // but `s` is allocated, use char.utf8_len to peek slice for `t` could avoid allocate.
+        let skip = t.chars().skip(path.len()); // equal to `[len(path):]`
+        let s: String = if !path.is_empty() {
+            // only filter when path is not "", notice GO not impl for this!
+            skip.skip_while(|c| *c != '/').collect()
+        } else {
+            skip.collect()
+        };
+        // "/123/123" would be "123/123", "//123/123" would be "123/123"
+        let sub = s.trim_start_matches('/');
+        if sub.is_empty() {
+            // means current tree have no child
+            continue;
+        }
+
+        match depth {
+            None => {
+                // means not filter by depth
+                out.push(sub.to_string());
+                continue;
+            }
+            Some(dep) => {
+                // for example sub like "123/123/123", and depth is 2, would not peek
+                let parts = sub.split('/').collect::<Vec<_>>();
+                if parts.len() <= dep {
+                    out.push(sub.to_string());
+                }
+            }
+        }
+    }
+    out
 }
 
-fn traverse<F>(obj: &IpldValue, cur: &str, f: &mut F) -> Result<(), IpldCoreError>
+/// Computes the stat of the block, `link_size` returns the size of an encoded link.
+pub(crate) fn compute_stat<B, F>(
+    block: &B,
+    links: &[Link],
+    link_size: F,
+) -> Result<NodeStat, IpldCoreError>
 where
-    F: FnMut(&str, &IpldValue) -> Result<(), IpldCoreError>,
+    B: Block + ?Sized,
+    F: Fn(&Cid) -> usize,
 {
-    f(cur, obj)?;
-    match obj {
-        IpldValue::Map(m) => {
-            for (k, v) in m.iter() {
-                let this = format!("{}/{}", cur, k);
-                traverse(v, &this, f)?;
+    let hash = multihash::Multihash::from_bytes(block.cid().hash().as_bytes().to_vec())
+        .map_err(|e| IpldCoreError::Other(Box::new(e)))?;
+    let block_size = block.raw_data().len();
+    let links_size = links.iter().map(|link| link_size(&link.cid)).sum();
+    Ok(NodeStat {
+        hash,
+        num_links: links.len(),
+        block_size,
+        links_size,
+        data_size: block_size.saturating_sub(links_size),
+        cumulative_size: block_size,
+    })
+}
+
+/// Returns the size of the link encoded in DAG-CBOR, i.e. the tag 42 and the byte string
+/// of the binary CID with the multibase identity prefix.
+fn encoded_link_size(cid: &Cid) -> usize {
+    let len = cid.to_bytes().len() + 1;
+    let header = match len {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        _ => 5,
+    };
+    2 + header + len
+}
+
+pub(crate) fn compute(obj: &IpldValue) -> Result<(Vec<String>, Vec<Link>), IpldCoreError> {
+    Ok((compute_tree(obj), compute_links(obj)))
+}
+
+/// Returns the paths of all the values within the object, without the leading `/`.
+pub(crate) fn compute_tree(obj: &IpldValue) -> Vec<String> {
+    fn traverse(obj: &IpldValue, cur: &mut String, tree: &mut Vec<String>) {
+        let len = cur.len();
+        let mut visit = |segment: &str, value: &IpldValue, cur: &mut String| {
+            cur.push('/');
+            cur.push_str(segment);
+            // [1:]
+            tree.push(cur[1..].to_string());
+            traverse(value, cur, tree);
+            cur.truncate(len);
+        };
+        match obj {
+            IpldValue::Map(m) => {
+                for (k, v) in m.iter() {
+                    visit(k.as_str(), v, cur);
+                }
+            }
+            IpldValue::List(arr) => {
+                for (i, v) in arr.iter().enumerate() {
+                    visit(&i.to_string(), v, cur);
+                }
             }
-            Ok(())
+            _ => {}
         }
-        IpldValue::List(arr) => {
-            for (i, v) in arr.iter().enumerate() {
-                let this = format!("{}/{}", cur, i);
-                traverse(v, &this, f)?;
+    }
+    let mut tree = vec![];
+    traverse(obj, &mut String::new(), &mut tree);
+    tree
+}
+
+/// Returns the links within the object, in the order of the paths.
+pub(crate) fn compute_links(obj: &IpldValue) -> Vec<Link> {
+    obj.links()
+        .map(|cid| Link::new_with_cid(cid.clone()))
+        .collect()
+}
+
+/// Returns the paths of all the values within the borrowed object, like `compute_tree`.
+fn compute_tree_ref(obj: &IpldValueRef) -> Vec<String> {
+    fn traverse(obj: &IpldValueRef, cur: &mut String, tree: &mut Vec<String>) {
+        let len = cur.len();
+        let mut visit = |segment: &str, value: &IpldValueRef, cur: &mut String| {
+            cur.push('/');
+            cur.push_str(segment);
+            tree.push(cur[1..].to_string());
+            traverse(value, cur, tree);
+            cur.truncate(len);
+        };
+        match obj {
+            IpldValueRef::Map(m) => {
+                for (k, v) in m.iter() {
+                    visit(k, v, cur);
+                }
+            }
+            IpldValueRef::List(arr) => {
+                for (i, v) in arr.iter().enumerate() {
+                    visit(&i.to_string(), v, cur);
+                }
             }
-            Ok(())
+            _ => {}
         }
-        _ => Ok(()),
     }
+    let mut tree = vec![];
+    traverse(obj, &mut String::new(), &mut tree);
+    tree
+}
+
+/// Returns the links within the borrowed object, like `compute_links`.
+fn compute_links_ref(obj: &IpldValueRef) -> Vec<Link> {
+    fn traverse(obj: &IpldValueRef, links: &mut Vec<Link>) {
+        match obj {
+            IpldValueRef::Map(m) => m.iter().for_each(|(_, v)| traverse(v, links)),
+            IpldValueRef::List(arr) => arr.iter().for_each(|v| traverse(v, links)),
+            IpldValueRef::Link(cid) => links.push(Link::new_with_cid(cid.clone())),
+            _ => {}
+        }
+    }
+    let mut links = vec![];
+    traverse(obj, &mut links);
+    links
 }
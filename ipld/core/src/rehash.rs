@@ -0,0 +1,69 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::HashMap;
+
+use cid::{Cid, Codec, Prefix};
+
+use block_format::BasicBlock;
+use ipld_format::{NodeAdder, NodeGetter};
+
+use crate::dag_cbor;
+use crate::error::IpldCoreError;
+use crate::node::IpldNode;
+
+/// Rewrites the DAG of the root with the CID prefix, e.g. another version or multihash,
+/// and returns the CID of the new root.
+///
+/// The DAG is rewritten bottom-up: the links of every DAG-CBOR node are replaced by the
+/// new CIDs of their children before the node is hashed and added to the `NodeAdder`.
+/// The nodes shared by several parents are rewritten once, and the links to blocks of
+/// other codecs are kept as they are.
+pub fn rehash<NG, NA>(
+    root: &Cid,
+    prefix: &Prefix,
+    getter: &NG,
+    adder: &NA,
+) -> Result<Cid, IpldCoreError>
+where
+    NG: NodeGetter<IpldNode>,
+    NA: NodeAdder<IpldNode>,
+{
+    if prefix.codec != Codec::DagCBOR {
+        return Err(IpldCoreError::UnsupportedCodec(prefix.codec));
+    }
+
+    let mut rehashed = HashMap::new();
+    // a node is pushed again above its children, it's rewritten once they're popped.
+    let mut stack = vec![(root.clone(), None)];
+    while let Some((cid, node)) = stack.pop() {
+        if rehashed.contains_key(&cid) {
+            continue;
+        }
+        if cid.codec() != Codec::DagCBOR {
+            rehashed.insert(cid.clone(), cid);
+            continue;
+        }
+        match node {
+            None => {
+                let node = getter.get(&cid)?;
+                let children = node
+                    .obj()
+                    .links()
+                    .filter(|link| !rehashed.contains_key(*link))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                stack.push((cid, Some(node)));
+                stack.extend(children.into_iter().map(|child| (child, None)));
+            }
+            Some(node) => {
+                let obj = node.obj().map_links(|link| Ok(rehashed[link].clone()))?;
+                let data = dag_cbor::encode(&obj)?;
+                let new_cid = Cid::new_from_prefix(prefix, &data);
+                let block = BasicBlock::new_with_cid(data.into(), new_cid.clone())?;
+                adder.add(&IpldNode::new_with_obj(&block, obj)?)?;
+                rehashed.insert(cid, new_cid);
+            }
+        }
+    }
+    Ok(rehashed[root].clone())
+}
@@ -0,0 +1,523 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+//! The [IPLD Selectors](https://github.com/ipld/specs/blob/master/selectors/selectors.md),
+//! which describe the traversals of a DAG, e.g. a path, or the links of a field followed
+//! recursively up to a depth.
+//!
+//! A `Selector` is converted from and into an `IpldValue` with the keys of the spec,
+//! so it can be encoded in DAG-CBOR or DAG-JSON, and is executed by `walk`.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use cid::Cid;
+
+use ipld_format::NodeGetter;
+
+use crate::error::IpldCoreError;
+use crate::node::IpldNode;
+use crate::value::{IpldValue, SortedStr};
+
+const MATCHER: &str = ".";
+const EXPLORE_ALL: &str = "a";
+const EXPLORE_FIELDS: &str = "f";
+const EXPLORE_INDEX: &str = "i";
+const EXPLORE_RANGE: &str = "r";
+const EXPLORE_RECURSIVE: &str = "R";
+const EXPLORE_RECURSIVE_EDGE: &str = "@";
+const EXPLORE_UNION: &str = "|";
+
+const NEXT: &str = ">";
+const FIELDS: &str = "f>";
+const INDEX: &str = "i";
+const START: &str = "^";
+const END: &str = "$";
+const LIMIT: &str = "l";
+const SEQUENCE: &str = ":>";
+const LIMIT_NONE: &str = "none";
+const LIMIT_DEPTH: &str = "depth";
+
+/// A selector of the nodes of a DAG.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selector {
+    /// Selects the current node.
+    Matcher,
+    /// Applies the next selector to all the fields of a map or the items of a list.
+    ExploreAll {
+        /// The selector of the children.
+        next: Box<Selector>,
+    },
+    /// Applies the selectors to the fields of a map, or the items of a list by index.
+    ExploreFields {
+        /// The selectors of the fields.
+        fields: BTreeMap<String, Selector>,
+    },
+    /// Applies the next selector to the item of a list at the index.
+    ExploreIndex {
+        /// The index of the item.
+        index: u64,
+        /// The selector of the item.
+        next: Box<Selector>,
+    },
+    /// Applies the next selector to the items of a list in the range `start..end`.
+    ExploreRange {
+        /// The first index, inclusive.
+        start: u64,
+        /// The last index, exclusive.
+        end: u64,
+        /// The selector of the items.
+        next: Box<Selector>,
+    },
+    /// Applies the sequence, in which the `ExploreRecursiveEdge`s apply the sequence again,
+    /// until the limit.
+    ExploreRecursive {
+        /// The limit of the recursion.
+        limit: RecursionLimit,
+        /// The selector of the recursion.
+        sequence: Box<Selector>,
+    },
+    /// Applies the sequence of the enclosing `ExploreRecursive` again.
+    ExploreRecursiveEdge,
+    /// Applies all the selectors, it selects a node if any of them selects it.
+    ExploreUnion(Vec<Selector>),
+}
+
+/// The limit of an `ExploreRecursive` selector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecursionLimit {
+    /// The recursion goes as deep as the DAG.
+    None,
+    /// The number of times the sequence is applied.
+    Depth(u64),
+}
+
+impl Selector {
+    /// Checks the edges are within an `ExploreRecursive`, which has one edge at least.
+    fn validate(&self, in_recursion: bool) -> Result<bool, IpldCoreError> {
+        match self {
+            Selector::Matcher => Ok(false),
+            Selector::ExploreAll { next }
+            | Selector::ExploreIndex { next, .. }
+            | Selector::ExploreRange { next, .. } => next.validate(in_recursion),
+            Selector::ExploreFields { fields } => fields
+                .values()
+                .try_fold(false, |edge, next| Ok(next.validate(in_recursion)? || edge)),
+            Selector::ExploreUnion(members) => members
+                .iter()
+                .try_fold(false, |edge, next| Ok(next.validate(in_recursion)? || edge)),
+            Selector::ExploreRecursive { sequence, .. } => {
+                if !sequence.validate(true)? {
+                    return Err(invalid("ExploreRecursive without ExploreRecursiveEdge"));
+                }
+                Ok(false)
+            }
+            Selector::ExploreRecursiveEdge if in_recursion => Ok(true),
+            Selector::ExploreRecursiveEdge => {
+                Err(invalid("ExploreRecursiveEdge outside of ExploreRecursive"))
+            }
+        }
+    }
+
+    fn parse(value: &IpldValue) -> Result<Self, IpldCoreError> {
+        let (kind, body) = match value.as_map() {
+            Some(map) if map.len() == 1 => map.iter().next().expect("the map has one entry"),
+            _ => return Err(invalid("a selector is a map with a single key")),
+        };
+        let selector = match kind.as_str() {
+            MATCHER => Selector::Matcher,
+            EXPLORE_ALL => Selector::ExploreAll {
+                next: Box::new(Self::parse(field(body, NEXT)?)?),
+            },
+            EXPLORE_FIELDS => {
+                let fields = field(body, FIELDS)?
+                    .as_map()
+                    .ok_or_else(|| invalid("the fields of ExploreFields must be a map"))?;
+                Selector::ExploreFields {
+                    fields: fields
+                        .iter()
+                        .map(|(name, next)| Ok((name.to_string(), Self::parse(next)?)))
+                        .collect::<Result<_, IpldCoreError>>()?,
+                }
+            }
+            EXPLORE_INDEX => Selector::ExploreIndex {
+                index: index(body, INDEX)?,
+                next: Box::new(Self::parse(field(body, NEXT)?)?),
+            },
+            EXPLORE_RANGE => {
+                let (start, end) = (index(body, START)?, index(body, END)?);
+                if start > end {
+                    return Err(invalid("the start of ExploreRange is after its end"));
+                }
+                Selector::ExploreRange {
+                    start,
+                    end,
+                    next: Box::new(Self::parse(field(body, NEXT)?)?),
+                }
+            }
+            EXPLORE_RECURSIVE => Selector::ExploreRecursive {
+                limit: RecursionLimit::parse(field(body, LIMIT)?)?,
+                sequence: Box::new(Self::parse(field(body, SEQUENCE)?)?),
+            },
+            EXPLORE_RECURSIVE_EDGE => Selector::ExploreRecursiveEdge,
+            EXPLORE_UNION => Selector::ExploreUnion(
+                body.as_list()
+                    .ok_or_else(|| invalid("the members of ExploreUnion must be a list"))?
+                    .iter()
+                    .map(Self::parse)
+                    .collect::<Result<_, IpldCoreError>>()?,
+            ),
+            other => return Err(invalid(format!("unknown selector `{}`", other))),
+        };
+        Ok(selector)
+    }
+
+    fn to_value(&self) -> IpldValue {
+        let (kind, body) = match self {
+            Selector::Matcher => (MATCHER, IpldValue::Map(BTreeMap::new())),
+            Selector::ExploreAll { next } => (EXPLORE_ALL, map(vec![(NEXT, next.to_value())])),
+            Selector::ExploreFields { fields } => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, next)| (SortedStr::from(name.as_str()), next.to_value()))
+                    .collect();
+                (EXPLORE_FIELDS, map(vec![(FIELDS, IpldValue::Map(fields))]))
+            }
+            Selector::ExploreIndex { index, next } => (
+                EXPLORE_INDEX,
+                map(vec![(INDEX, (*index).into()), (NEXT, next.to_value())]),
+            ),
+            Selector::ExploreRange { start, end, next } => (
+                EXPLORE_RANGE,
+                map(vec![
+                    (START, (*start).into()),
+                    (END, (*end).into()),
+                    (NEXT, next.to_value()),
+                ]),
+            ),
+            Selector::ExploreRecursive { limit, sequence } => (
+                EXPLORE_RECURSIVE,
+                map(vec![
+                    (LIMIT, limit.to_value()),
+                    (SEQUENCE, sequence.to_value()),
+                ]),
+            ),
+            Selector::ExploreRecursiveEdge => {
+                (EXPLORE_RECURSIVE_EDGE, IpldValue::Map(BTreeMap::new()))
+            }
+            Selector::ExploreUnion(members) => (
+                EXPLORE_UNION,
+                IpldValue::List(members.iter().map(Self::to_value).collect()),
+            ),
+        };
+        map(vec![(kind, body)])
+    }
+}
+
+impl RecursionLimit {
+    fn parse(value: &IpldValue) -> Result<Self, IpldCoreError> {
+        match value.as_map() {
+            Some(map) if map.len() == 1 => match map.iter().next() {
+                Some((kind, _)) if kind.as_str() == LIMIT_NONE => Ok(RecursionLimit::None),
+                Some((kind, _)) if kind.as_str() == LIMIT_DEPTH => {
+                    Ok(RecursionLimit::Depth(index(value, LIMIT_DEPTH)?))
+                }
+                _ => Err(invalid("unknown recursion limit")),
+            },
+            _ => Err(invalid("a recursion limit is a map with a single key")),
+        }
+    }
+
+    fn to_value(self) -> IpldValue {
+        match self {
+            RecursionLimit::None => map(vec![(LIMIT_NONE, IpldValue::Map(BTreeMap::new()))]),
+            RecursionLimit::Depth(depth) => map(vec![(LIMIT_DEPTH, depth.into())]),
+        }
+    }
+}
+
+impl TryFrom<&IpldValue> for Selector {
+    type Error = IpldCoreError;
+
+    /// Parses the selector and checks its `ExploreRecursiveEdge`s.
+    fn try_from(value: &IpldValue) -> Result<Self, Self::Error> {
+        let selector = Self::parse(value)?;
+        selector.validate(false)?;
+        Ok(selector)
+    }
+}
+
+impl From<&Selector> for IpldValue {
+    fn from(selector: &Selector) -> Self {
+        selector.to_value()
+    }
+}
+
+fn invalid<T: Into<String>>(msg: T) -> IpldCoreError {
+    IpldCoreError::InvalidSelector(msg.into())
+}
+
+fn map(entries: Vec<(&str, IpldValue)>) -> IpldValue {
+    IpldValue::Map(
+        entries
+            .into_iter()
+            .map(|(key, value)| (SortedStr::from(key), value))
+            .collect(),
+    )
+}
+
+fn field<'a>(body: &'a IpldValue, key: &str) -> Result<&'a IpldValue, IpldCoreError> {
+    body.get_path(&[key])
+        .ok_or_else(|| invalid(format!("missing field `{}`", key)))
+}
+
+fn index(body: &IpldValue, key: &str) -> Result<u64, IpldCoreError> {
+    field(body, key)?
+        .as_u64()
+        .ok_or_else(|| invalid(format!("field `{}` must be a non-negative integer", key)))
+}
+
+/// Whether a node is visited because it's selected or to be explored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VisitReason {
+    /// The node is selected by a `Matcher`.
+    Match,
+    /// The node is explored by the selector, but not selected.
+    Candidate,
+}
+
+/// A node visited by `walk`.
+#[derive(Debug)]
+pub struct Visit<'a> {
+    /// The path from the root, through the links.
+    pub path: &'a [String],
+    /// The CID of the block which contains the node.
+    pub block: &'a Cid,
+    /// The node, the links are loaded, so it isn't a link unless it's at the end of the path.
+    pub value: &'a IpldValue,
+    /// Why the node is visited.
+    pub reason: VisitReason,
+}
+
+/// Walks the DAG from the root with the selector, depth first, calling the visitor for
+/// every node explored.
+///
+/// The links met by the selector are loaded from the `NodeGetter`, and the walk stops
+/// at the first error.
+pub fn walk<NG, F>(
+    getter: &NG,
+    root: &Cid,
+    selector: &Selector,
+    mut visitor: F,
+) -> Result<(), IpldCoreError>
+where
+    NG: NodeGetter<IpldNode>,
+    F: FnMut(Visit<'_>) -> Result<(), IpldCoreError>,
+{
+    let state = match State::new(selector, None) {
+        Some(state) => state,
+        None => return Ok(()),
+    };
+    let mut walker = Walker {
+        getter,
+        visitor: &mut visitor,
+        path: vec![],
+    };
+    walker.walk(&IpldValue::Link(root.clone()), root, &state)
+}
+
+/// The innermost `ExploreRecursive` of a state.
+#[derive(Clone, Copy, Debug)]
+struct Recursion<'a> {
+    sequence: &'a Selector,
+    limit: RecursionLimit,
+}
+
+/// The selector applied to a node, and the recursion it's within.
+#[derive(Clone, Debug)]
+enum State<'a> {
+    One {
+        current: &'a Selector,
+        recursion: Option<Recursion<'a>>,
+    },
+    Union(Vec<State<'a>>),
+}
+
+impl<'a> State<'a> {
+    /// Returns the state of the selector, entering the recursions and following the edges,
+    /// or `None` if the selector selects nothing, i.e. an edge at the limit of its recursion.
+    fn new(selector: &'a Selector, recursion: Option<Recursion<'a>>) -> Option<Self> {
+        match selector {
+            Selector::ExploreRecursive { limit, sequence } => {
+                if *limit == RecursionLimit::Depth(0) {
+                    return None;
+                }
+                let recursion = Recursion {
+                    sequence,
+                    limit: *limit,
+                };
+                Self::new(sequence, Some(recursion))
+            }
+            Selector::ExploreRecursiveEdge => {
+                let recursion = recursion.expect("the edges are validated");
+                let limit = match recursion.limit {
+                    RecursionLimit::Depth(depth) if depth < 2 => return None,
+                    RecursionLimit::Depth(depth) => RecursionLimit::Depth(depth - 1),
+                    RecursionLimit::None => RecursionLimit::None,
+                };
+                let recursion = Recursion { limit, ..recursion };
+                Self::new(recursion.sequence, Some(recursion))
+            }
+            Selector::ExploreUnion(members) => {
+                let states = members
+                    .iter()
+                    .filter_map(|member| Self::new(member, recursion))
+                    .collect::<Vec<_>>();
+                if states.is_empty() {
+                    None
+                } else {
+                    Some(State::Union(states))
+                }
+            }
+            current => Some(State::One { current, recursion }),
+        }
+    }
+
+    /// Returns true if the node is selected.
+    fn decide(&self) -> bool {
+        match self {
+            State::One { current, .. } => **current == Selector::Matcher,
+            State::Union(states) => states.iter().any(State::decide),
+        }
+    }
+
+    /// Returns the segments explored by the state, or `None` if it can't be known
+    /// without the node, e.g. for `ExploreAll`.
+    fn interests(&self) -> Option<Vec<String>> {
+        match self {
+            State::One { current, .. } => match current {
+                Selector::Matcher => Some(vec![]),
+                Selector::ExploreFields { fields } => Some(fields.keys().cloned().collect()),
+                Selector::ExploreIndex { index, .. } => Some(vec![index.to_string()]),
+                _ => None,
+            },
+            State::Union(states) => {
+                let mut interests = vec![];
+                for state in states {
+                    for segment in state.interests()? {
+                        if !interests.contains(&segment) {
+                            interests.push(segment);
+                        }
+                    }
+                }
+                Some(interests)
+            }
+        }
+    }
+
+    /// Returns the state of the child at the segment, if it's explored.
+    fn explore(&self, segment: &str) -> Option<Self> {
+        let (current, recursion) = match self {
+            State::One { current, recursion } => (*current, *recursion),
+            State::Union(states) => {
+                let states = states
+                    .iter()
+                    .filter_map(|state| state.explore(segment))
+                    .collect::<Vec<_>>();
+                return match states.len() {
+                    0 => None,
+                    1 => states.into_iter().next(),
+                    _ => Some(State::Union(states)),
+                };
+            }
+        };
+        let in_range = |start: u64, end: u64| {
+            segment
+                .parse::<u64>()
+                .map_or(false, |index| start <= index && index < end)
+        };
+        let next = match current {
+            Selector::ExploreAll { next } => next,
+            Selector::ExploreFields { fields } => fields.get(segment)?,
+            Selector::ExploreIndex { index, next } if in_range(*index, index.saturating_add(1)) => {
+                next
+            }
+            Selector::ExploreRange { start, end, next } if in_range(*start, *end) => next,
+            _ => return None,
+        };
+        Self::new(next, recursion)
+    }
+}
+
+struct Walker<'a, NG, F> {
+    getter: &'a NG,
+    visitor: &'a mut F,
+    path: Vec<String>,
+}
+
+impl<'a, NG, F> Walker<'a, NG, F>
+where
+    NG: NodeGetter<IpldNode>,
+    F: FnMut(Visit<'_>) -> Result<(), IpldCoreError>,
+{
+    fn walk(&mut self, value: &IpldValue, block: &Cid, state: &State) -> Result<(), IpldCoreError> {
+        if let IpldValue::Link(cid) = value {
+            let node = self.getter.get(cid)?;
+            return self.walk_node(node.obj(), cid, state);
+        }
+        self.walk_node(value, block, state)
+    }
+
+    fn walk_node(
+        &mut self,
+        value: &IpldValue,
+        block: &Cid,
+        state: &State,
+    ) -> Result<(), IpldCoreError> {
+        let reason = if state.decide() {
+            VisitReason::Match
+        } else {
+            VisitReason::Candidate
+        };
+        (self.visitor)(Visit {
+            path: &self.path,
+            block,
+            value,
+            reason,
+        })?;
+
+        let children: Vec<(String, &IpldValue)> = match (value, state.interests()) {
+            (IpldValue::Map(map), Some(segments)) => segments
+                .into_iter()
+                .filter_map(|segment| {
+                    let child = map.get(&SortedStr::from(segment.as_str()))?;
+                    Some((segment, child))
+                })
+                .collect(),
+            (IpldValue::Map(map), None) => map
+                .iter()
+                .map(|(key, child)| (key.to_string(), child))
+                .collect(),
+            (IpldValue::List(list), Some(segments)) => segments
+                .into_iter()
+                .filter_map(|segment| {
+                    let child = list.get(segment.parse::<usize>().ok()?)?;
+                    Some((segment, child))
+                })
+                .collect(),
+            (IpldValue::List(list), None) => list
+                .iter()
+                .enumerate()
+                .map(|(index, child)| (index.to_string(), child))
+                .collect(),
+            _ => vec![],
+        };
+        for (segment, child) in children {
+            if let Some(next) = state.explore(&segment) {
+                self.path.push(segment);
+                self.walk(child, block, &next)?;
+                self.path.pop();
+            }
+        }
+        Ok(())
+    }
+}
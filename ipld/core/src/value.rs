@@ -1,7 +1,7 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::fmt;
 
@@ -13,6 +13,9 @@ use serde::{
     Deserialize, Serialize,
 };
 
+use crate::convert::IpldLink;
+use crate::error::IpldCoreError;
+
 /// A String Wrapper that implements `Ord` and `PartialOrd`,
 /// according to the length of string, in bytes.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -135,13 +138,512 @@ pub enum IpldValue {
     Link(Cid),
 }
 
+impl IpldValue {
+    /// Returns the name of the kind of the value in the IPLD data model, e.g. `map`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IpldValue::Null => "null",
+            IpldValue::Bool(_) => "bool",
+            IpldValue::Integer(_) => "integer",
+            IpldValue::Float(_) => "float",
+            IpldValue::String(_) => "string",
+            IpldValue::Bytes(_) => "bytes",
+            IpldValue::List(_) => "list",
+            IpldValue::Map(_) => "map",
+            IpldValue::Link(_) => "link",
+        }
+    }
+
+    /// Returns true if the value is null.
+    pub fn is_null(&self) -> bool {
+        *self == IpldValue::Null
+    }
+
+    /// Returns the bool if the value is a bool.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            IpldValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if the value is an integer.
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            IpldValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if the value is an integer in the range of `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|i| i64::try_from(i).ok())
+    }
+
+    /// Returns the integer if the value is an integer in the range of `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i128().and_then(|i| u64::try_from(i).ok())
+    }
+
+    /// Returns the float if the value is a float, the integers aren't converted.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            IpldValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Returns the string if the value is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            IpldValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes if the value is a byte string.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            IpldValue::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the list if the value is a list.
+    pub fn as_list(&self) -> Option<&Vec<IpldValue>> {
+        match self {
+            IpldValue::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns the mutable list if the value is a list.
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<IpldValue>> {
+        match self {
+            IpldValue::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns the map if the value is a map.
+    pub fn as_map(&self) -> Option<&BTreeMap<SortedStr, IpldValue>> {
+        match self {
+            IpldValue::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Returns the mutable map if the value is a map.
+    pub fn as_map_mut(&mut self) -> Option<&mut BTreeMap<SortedStr, IpldValue>> {
+        match self {
+            IpldValue::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Returns the CID if the value is a link.
+    pub fn as_link(&self) -> Option<&Cid> {
+        match self {
+            IpldValue::Link(cid) => Some(cid),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the key if the value is a map, or of the index if it's a list.
+    pub fn get(&self, segment: &str) -> Option<&IpldValue> {
+        match self {
+            IpldValue::Map(map) => map.get(&SortedStr::from(segment)),
+            IpldValue::List(list) => segment.parse::<usize>().ok().and_then(|i| list.get(i)),
+            _ => None,
+        }
+    }
+
+    /// Returns the mutable value of the key if the value is a map,
+    /// or of the index if it's a list.
+    pub fn get_mut(&mut self, segment: &str) -> Option<&mut IpldValue> {
+        match self {
+            IpldValue::Map(map) => map.get_mut(&SortedStr::from(segment)),
+            IpldValue::List(list) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(move |i| list.get_mut(i)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value at the path, whose segments are map keys or list indexes.
+    ///
+    /// The links aren't followed, the empty path is the value itself.
+    pub fn get_path(&self, path: &[&str]) -> Option<&IpldValue> {
+        path.iter()
+            .try_fold(self, |value, segment| value.get(segment))
+    }
+
+    /// Returns the mutable value at the path, whose segments are map keys or list indexes.
+    pub fn get_path_mut(&mut self, path: &[&str]) -> Option<&mut IpldValue> {
+        path.iter()
+            .try_fold(self, |value, segment| value.get_mut(segment))
+    }
+
+    /// Sets the value at the path and returns the previous value, if any.
+    ///
+    /// The missing intermediate keys are inserted as empty maps, the null intermediate
+    /// values are replaced by empty maps. The list indexes must exist, except the last
+    /// one which can be the length of the list to push the value.
+    pub fn set_path<V: Into<IpldValue>>(
+        &mut self,
+        path: &[&str],
+        value: V,
+    ) -> Result<Option<IpldValue>, IpldCoreError> {
+        let (last, parents) = match path.split_last() {
+            Some(split) => split,
+            None => return Ok(Some(std::mem::replace(self, value.into()))),
+        };
+        let mut current = self;
+        for (i, segment) in parents.iter().enumerate() {
+            if current.is_null() {
+                *current = IpldValue::Map(BTreeMap::new());
+            }
+            current = match current {
+                IpldValue::Map(map) => map
+                    .entry(SortedStr::from(*segment))
+                    .or_insert_with(|| IpldValue::Map(BTreeMap::new())),
+                IpldValue::List(list) => {
+                    let len = list.len();
+                    segment
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(move |index| list.get_mut(index))
+                        .ok_or_else(|| invalid_path(&path[..=i], format!("list of {}", len)))?
+                }
+                other => return Err(invalid_path(&path[..=i], other.kind())),
+            };
+        }
+        if current.is_null() {
+            *current = IpldValue::Map(BTreeMap::new());
+        }
+        match current {
+            IpldValue::Map(map) => Ok(map.insert(SortedStr::from(*last), value.into())),
+            IpldValue::List(list) => match last.parse::<usize>() {
+                Ok(index) if index < list.len() => {
+                    Ok(Some(std::mem::replace(&mut list[index], value.into())))
+                }
+                Ok(index) if index == list.len() => {
+                    list.push(value.into());
+                    Ok(None)
+                }
+                _ => Err(invalid_path(path, format!("list of {}", list.len()))),
+            },
+            other => Err(invalid_path(path, other.kind())),
+        }
+    }
+
+    /// Removes the value at the path and returns it, the following items of a list are shifted.
+    ///
+    /// The empty path can't be removed.
+    pub fn remove_path(&mut self, path: &[&str]) -> Option<IpldValue> {
+        let (last, parents) = path.split_last()?;
+        match self.get_path_mut(parents)? {
+            IpldValue::Map(map) => map.remove(&SortedStr::from(*last)),
+            IpldValue::List(list) => match last.parse::<usize>() {
+                Ok(index) if index < list.len() => Some(list.remove(index)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the links within the value, in the order of the paths.
+    ///
+    /// The links aren't collected, the iterator only keeps the lists and the maps being
+    /// iterated.
+    pub fn links(&self) -> Links<'_> {
+        Links {
+            root: Some(self),
+            stack: vec![],
+        }
+    }
+
+    /// Returns a copy of the value with every link replaced by the result of `f`,
+    /// stopping at the first error.
+    pub fn map_links<F>(&self, mut f: F) -> Result<IpldValue, IpldCoreError>
+    where
+        F: FnMut(&Cid) -> Result<Cid, IpldCoreError>,
+    {
+        fn map<F>(value: &IpldValue, f: &mut F) -> Result<IpldValue, IpldCoreError>
+        where
+            F: FnMut(&Cid) -> Result<Cid, IpldCoreError>,
+        {
+            Ok(match value {
+                IpldValue::Link(cid) => IpldValue::Link(f(cid)?),
+                IpldValue::List(list) => IpldValue::List(
+                    list.iter()
+                        .map(|value| map(value, f))
+                        .collect::<Result<_, _>>()?,
+                ),
+                IpldValue::Map(entries) => IpldValue::Map(
+                    entries
+                        .iter()
+                        .map(|(key, value)| Ok((key.clone(), map(value, f)?)))
+                        .collect::<Result<_, IpldCoreError>>()?,
+                ),
+                other => other.clone(),
+            })
+        }
+        map(self, &mut f)
+    }
+}
+
+/// An iterator over the links within an `IpldValue`, see `IpldValue::links`.
+#[derive(Clone, Debug)]
+pub struct Links<'a> {
+    root: Option<&'a IpldValue>,
+    stack: Vec<Children<'a>>,
+}
+
+#[derive(Clone, Debug)]
+enum Children<'a> {
+    List(std::slice::Iter<'a, IpldValue>),
+    Map(std::collections::btree_map::Values<'a, SortedStr, IpldValue>),
+}
+
+impl<'a> Iterator for Links<'a> {
+    type Item = &'a Cid;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let value = match self.root.take() {
+                Some(root) => root,
+                None => {
+                    let next = match self.stack.last_mut()? {
+                        Children::List(iter) => iter.next(),
+                        Children::Map(iter) => iter.next(),
+                    };
+                    match next {
+                        Some(value) => value,
+                        None => {
+                            self.stack.pop();
+                            continue;
+                        }
+                    }
+                }
+            };
+            match value {
+                IpldValue::Link(cid) => return Some(cid),
+                IpldValue::List(list) => self.stack.push(Children::List(list.iter())),
+                IpldValue::Map(map) => self.stack.push(Children::Map(map.values())),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn invalid_path<T: fmt::Display>(path: &[&str], found: T) -> IpldCoreError {
+    IpldCoreError::InvalidPath(format!("{}, found {}", path.join("/"), found))
+}
+
+impl From<bool> for IpldValue {
+    fn from(b: bool) -> Self {
+        IpldValue::Bool(b)
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for IpldValue {
+                fn from(i: $ty) -> Self {
+                    IpldValue::Integer(i.into())
+                }
+            }
+
+            impl TryFrom<IpldValue> for $ty {
+                type Error = IpldCoreError;
+
+                fn try_from(value: IpldValue) -> Result<Self, Self::Error> {
+                    match value {
+                        IpldValue::Integer(i) => <$ty>::try_from(i).map_err(|_| {
+                            IpldCoreError::InvalidType {
+                                expected: stringify!($ty),
+                                found: "integer out of range",
+                            }
+                        }),
+                        other => Err(invalid_type(stringify!($ty), &other)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, i64, i128, u8, u16, u32, u64);
+
+impl From<f32> for IpldValue {
+    fn from(f: f32) -> Self {
+        IpldValue::Float(f.into())
+    }
+}
+
+impl From<f64> for IpldValue {
+    fn from(f: f64) -> Self {
+        IpldValue::Float(f)
+    }
+}
+
+impl From<&str> for IpldValue {
+    fn from(s: &str) -> Self {
+        IpldValue::String(s.to_string())
+    }
+}
+
+impl From<String> for IpldValue {
+    fn from(s: String) -> Self {
+        IpldValue::String(s)
+    }
+}
+
+/// The bytes are a byte string, unlike `Vec<u8>` which is a list of integers.
+impl From<&[u8]> for IpldValue {
+    fn from(bytes: &[u8]) -> Self {
+        IpldValue::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<Cid> for IpldValue {
+    fn from(cid: Cid) -> Self {
+        IpldValue::Link(cid)
+    }
+}
+
+impl From<&Cid> for IpldValue {
+    fn from(cid: &Cid) -> Self {
+        IpldValue::Link(cid.clone())
+    }
+}
+
+impl<T: Into<IpldValue>> From<Option<T>> for IpldValue {
+    fn from(option: Option<T>) -> Self {
+        option.map_or(IpldValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<IpldValue>> From<Vec<T>> for IpldValue {
+    fn from(list: Vec<T>) -> Self {
+        IpldValue::List(list.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<SortedStr>, V: Into<IpldValue>> From<BTreeMap<K, V>> for IpldValue {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        IpldValue::Map(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+impl<K: Into<SortedStr>, V: Into<IpldValue>, S> From<HashMap<K, V, S>> for IpldValue {
+    fn from(map: HashMap<K, V, S>) -> Self {
+        IpldValue::Map(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+fn invalid_type(expected: &'static str, found: &IpldValue) -> IpldCoreError {
+    IpldCoreError::InvalidType {
+        expected,
+        found: found.kind(),
+    }
+}
+
+impl TryFrom<IpldValue> for bool {
+    type Error = IpldCoreError;
+
+    fn try_from(value: IpldValue) -> Result<Self, Self::Error> {
+        match value {
+            IpldValue::Bool(b) => Ok(b),
+            other => Err(invalid_type("bool", &other)),
+        }
+    }
+}
+
+impl TryFrom<IpldValue> for f64 {
+    type Error = IpldCoreError;
+
+    fn try_from(value: IpldValue) -> Result<Self, Self::Error> {
+        match value {
+            IpldValue::Float(f) => Ok(f),
+            other => Err(invalid_type("float", &other)),
+        }
+    }
+}
+
+impl TryFrom<IpldValue> for String {
+    type Error = IpldCoreError;
+
+    fn try_from(value: IpldValue) -> Result<Self, Self::Error> {
+        match value {
+            IpldValue::String(s) => Ok(s),
+            other => Err(invalid_type("string", &other)),
+        }
+    }
+}
+
+impl TryFrom<IpldValue> for Vec<u8> {
+    type Error = IpldCoreError;
+
+    fn try_from(value: IpldValue) -> Result<Self, Self::Error> {
+        match value {
+            IpldValue::Bytes(bytes) => Ok(bytes),
+            other => Err(invalid_type("bytes", &other)),
+        }
+    }
+}
+
+impl TryFrom<IpldValue> for Vec<IpldValue> {
+    type Error = IpldCoreError;
+
+    fn try_from(value: IpldValue) -> Result<Self, Self::Error> {
+        match value {
+            IpldValue::List(list) => Ok(list),
+            other => Err(invalid_type("list", &other)),
+        }
+    }
+}
+
+impl TryFrom<IpldValue> for BTreeMap<SortedStr, IpldValue> {
+    type Error = IpldCoreError;
+
+    fn try_from(value: IpldValue) -> Result<Self, Self::Error> {
+        match value {
+            IpldValue::Map(map) => Ok(map),
+            other => Err(invalid_type("map", &other)),
+        }
+    }
+}
+
+impl TryFrom<IpldValue> for Cid {
+    type Error = IpldCoreError;
+
+    fn try_from(value: IpldValue) -> Result<Self, Self::Error> {
+        match value {
+            IpldValue::Link(cid) => Ok(cid),
+            other => Err(invalid_type("link", &other)),
+        }
+    }
+}
+
 // Implement CBOR serialization for IpldValue.
 impl encode::Encode for IpldValue {
     fn encode<W: encode::Write>(&self, e: &mut Encoder<W>) -> Result<(), encode::Error<W::Error>> {
         match self {
             IpldValue::Null => e.null()?.ok(),
             IpldValue::Bool(bool) => e.bool(*bool)?.ok(),
-            IpldValue::Integer(i128) => e.i64(*i128 as i64)?.ok(),
+            IpldValue::Integer(i128) => {
+                if let Ok(u64) = u64::try_from(*i128) {
+                    e.u64(u64)?.ok()
+                } else if let Ok(i64) = i64::try_from(*i128) {
+                    e.i64(i64)?.ok()
+                } else {
+                    Err(encode::Error::Message("integer out of range"))
+                }
+            }
             IpldValue::Float(f64) => e.f64(*f64)?.ok(),
             IpldValue::Bytes(bytes) => e.bytes(bytes)?.ok(),
             IpldValue::String(string) => e.str(string)?.ok(),
@@ -176,7 +678,7 @@ impl<'b> decode::Decode<'b> for IpldValue {
             Type::U8 => Ok(IpldValue::Integer(i128::from(d.u8()?))),
             Type::U16 => Ok(IpldValue::Integer(i128::from(d.u16()?))),
             Type::U32 => Ok(IpldValue::Integer(i128::from(d.u32()?))),
-            Type::U64 => Ok(IpldValue::Integer(i128::from(d.u32()?))),
+            Type::U64 => Ok(IpldValue::Integer(i128::from(d.u64()?))),
             Type::I8 => Ok(IpldValue::Integer(i128::from(d.i8()?))),
             Type::I16 => Ok(IpldValue::Integer(i128::from(d.i16()?))),
             Type::I32 => Ok(IpldValue::Integer(i128::from(d.i32()?))),
@@ -187,8 +689,10 @@ impl<'b> decode::Decode<'b> for IpldValue {
             Type::Bytes => Ok(IpldValue::Bytes(d.bytes()?.to_vec())),
             Type::String => Ok(IpldValue::String(d.str()?.to_owned())),
             Type::Array => {
-                let array_len = d.array()?.expect("array is definite");
-                let mut array = Vec::with_capacity(array_len as usize);
+                let array_len = d
+                    .array()?
+                    .ok_or(decode::Error::Message("indefinite array is not supported"))?;
+                let mut array = Vec::new();
                 for _ in 0..array_len {
                     let obj = d.decode::<IpldValue>()?;
                     array.push(obj);
@@ -196,7 +700,9 @@ impl<'b> decode::Decode<'b> for IpldValue {
                 Ok(IpldValue::List(array))
             }
             Type::Map => {
-                let map_len = d.map()?.expect("map is definite");
+                let map_len = d
+                    .map()?
+                    .ok_or(decode::Error::Message("indefinite map is not supported"))?;
                 let mut map = BTreeMap::new();
                 for _ in 0..map_len {
                     let k = d.decode::<SortedStr>()?;
@@ -226,10 +732,12 @@ impl ser::Serialize for IpldValue {
             IpldValue::Null => serializer.serialize_none(),
             IpldValue::Bool(bool) => serializer.serialize_bool(*bool),
             IpldValue::Integer(i128) => {
-                if *i128 > 0 {
-                    serializer.serialize_u64(*i128 as u64)
+                if let Ok(u64) = u64::try_from(*i128) {
+                    serializer.serialize_u64(u64)
+                } else if let Ok(i64) = i64::try_from(*i128) {
+                    serializer.serialize_i64(i64)
                 } else {
-                    serializer.serialize_i64(*i128 as i64)
+                    serializer.serialize_i128(*i128)
                 }
             }
             IpldValue::Float(f64) => serializer.serialize_f64(*f64),
@@ -249,6 +757,10 @@ impl ser::Serialize for IpldValue {
                 }
                 m.end()
             }
+            // the serializer of `IpldValue` isn't human readable and converts it into a link.
+            IpldValue::Link(link) if !serializer.is_human_readable() => {
+                IpldLink(link.clone()).serialize(serializer)
+            }
             IpldValue::Link(link) => link.serialize(serializer),
         }
     }
@@ -393,6 +905,15 @@ impl<'de> de::Visitor<'de> for JsonVisitor {
         Ok(IpldValue::Map(values))
     }
 
+    #[inline]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let link = IpldLink::deserialize(deserializer)?;
+        Ok(IpldValue::Link(link.into_inner()))
+    }
+
     #[inline]
     fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
     where
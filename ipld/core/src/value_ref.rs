@@ -0,0 +1,125 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::BTreeMap;
+
+use cid::Cid;
+
+use crate::dag_cbor;
+use crate::error::IpldCoreError;
+use crate::value::{IpldValue, SortedStr};
+
+/// An IPLD value borrowing its strings and byte strings from the encoded block.
+///
+/// Decoding a large block into an `IpldValueRef` doesn't copy its data, the entries of
+/// the maps are kept in the order of the encoding.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IpldValueRef<'a> {
+    /// null value.
+    Null,
+    /// bool value.
+    Bool(bool),
+    /// integer value.
+    Integer(i128),
+    /// float value.
+    Float(f64),
+    /// UTF-8 text string value.
+    String(&'a str),
+    /// byte string value.
+    Bytes(&'a [u8]),
+    /// list value.
+    List(Vec<IpldValueRef<'a>>),
+    /// map value.
+    Map(Vec<(&'a str, IpldValueRef<'a>)>),
+    /// link value.
+    Link(Cid),
+}
+
+impl<'a> IpldValueRef<'a> {
+    /// Decodes the DAG-CBOR bytes, borrowing the strings and the byte strings from them,
+    /// see `dag_cbor::decode_ref`.
+    pub fn decode(cbor: &'a [u8]) -> Result<Self, IpldCoreError> {
+        dag_cbor::decode_ref(cbor)
+    }
+
+    /// Returns the name of the kind of the value in the IPLD data model, e.g. `map`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IpldValueRef::Null => "null",
+            IpldValueRef::Bool(_) => "bool",
+            IpldValueRef::Integer(_) => "integer",
+            IpldValueRef::Float(_) => "float",
+            IpldValueRef::String(_) => "string",
+            IpldValueRef::Bytes(_) => "bytes",
+            IpldValueRef::List(_) => "list",
+            IpldValueRef::Map(_) => "map",
+            IpldValueRef::Link(_) => "link",
+        }
+    }
+
+    /// Returns the string if the value is a string.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            IpldValueRef::String(s) => Some(*s),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes if the value is a byte string.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            IpldValueRef::Bytes(bytes) => Some(*bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the CID if the value is a link.
+    pub fn as_link(&self) -> Option<&Cid> {
+        match self {
+            IpldValueRef::Link(cid) => Some(cid),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the key if the value is a map, or of the index if it's a list.
+    pub fn get(&self, segment: &str) -> Option<&IpldValueRef<'a>> {
+        match self {
+            IpldValueRef::Map(map) => map
+                .iter()
+                .find(|(key, _)| *key == segment)
+                .map(|(_, value)| value),
+            IpldValueRef::List(list) => segment.parse::<usize>().ok().and_then(|i| list.get(i)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value at the path within the value, the links aren't followed.
+    pub fn get_path(&self, path: &[&str]) -> Option<&IpldValueRef<'a>> {
+        path.iter()
+            .try_fold(self, |value, segment| value.get(segment))
+    }
+
+    /// Copies the value into an owned `IpldValue`.
+    pub fn to_value(&self) -> IpldValue {
+        match self {
+            IpldValueRef::Null => IpldValue::Null,
+            IpldValueRef::Bool(b) => IpldValue::Bool(*b),
+            IpldValueRef::Integer(i) => IpldValue::Integer(*i),
+            IpldValueRef::Float(f) => IpldValue::Float(*f),
+            IpldValueRef::String(s) => IpldValue::String((*s).to_string()),
+            IpldValueRef::Bytes(bytes) => IpldValue::Bytes(bytes.to_vec()),
+            IpldValueRef::List(list) => IpldValue::List(list.iter().map(Self::to_value).collect()),
+            IpldValueRef::Map(map) => IpldValue::Map(
+                map.iter()
+                    .map(|(key, value)| (SortedStr::from(*key), value.to_value()))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+            IpldValueRef::Link(cid) => IpldValue::Link(cid.clone()),
+        }
+    }
+}
+
+impl From<&IpldValueRef<'_>> for IpldValue {
+    fn from(value: &IpldValueRef<'_>) -> Self {
+        value.to_value()
+    }
+}
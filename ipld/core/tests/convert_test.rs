@@ -0,0 +1,162 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use cid::{Cid, Codec};
+use maplit::btreemap;
+use matches::matches;
+use multihash::Code;
+use serde::{Deserialize, Serialize};
+
+use block_format::Block;
+use ipld_core::{dag_cbor, from_ipld, to_ipld, IpldCoreError, IpldLink, IpldNode, IpldValue, Node};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Kind {
+    File,
+    Symlink(String),
+    Chunk(u32, u32),
+    Dir { entries: Vec<IpldLink> },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    name: String,
+    size: u64,
+    offset: i64,
+    ratio: f64,
+    kind: Kind,
+    parent: Option<IpldLink>,
+    tags: BTreeMap<String, bool>,
+    pair: (u8, char),
+}
+
+fn cid() -> Cid {
+    Cid::try_from("QmRgutAxd8t7oGkSm4wmeuByG6M51wcTso6cubDdQtuEfL").unwrap()
+}
+
+fn entry(kind: Kind) -> Entry {
+    Entry {
+        name: "foo".into(),
+        size: u64::max_value(),
+        offset: i64::min_value(),
+        ratio: 0.5,
+        kind,
+        parent: Some(IpldLink(cid())),
+        tags: btreemap! { "a".into() => true },
+        pair: (1, 'x'),
+    }
+}
+
+#[test]
+fn test_to_ipld() {
+    let value = to_ipld(&entry(Kind::File)).unwrap();
+    assert_eq!(
+        value,
+        IpldValue::Map(btreemap! {
+            "name".into() => IpldValue::String("foo".into()),
+            "size".into() => IpldValue::Integer(u64::max_value().into()),
+            "offset".into() => IpldValue::Integer(i64::min_value().into()),
+            "ratio".into() => IpldValue::Float(0.5),
+            "kind".into() => IpldValue::String("File".into()),
+            "parent".into() => IpldValue::Link(cid()),
+            "tags".into() => IpldValue::Map(btreemap! { "a".into() => IpldValue::Bool(true) }),
+            "pair".into() => IpldValue::List(vec![
+                IpldValue::Integer(1),
+                IpldValue::String("x".into()),
+            ]),
+        })
+    );
+
+    let kind = to_ipld(&Kind::Dir {
+        entries: vec![IpldLink(cid())],
+    })
+    .unwrap();
+    assert_eq!(
+        kind,
+        IpldValue::Map(btreemap! {
+            "Dir".into() => IpldValue::Map(btreemap! {
+                "entries".into() => IpldValue::List(vec![IpldValue::Link(cid())]),
+            }),
+        })
+    );
+    assert_eq!(to_ipld(&None::<u8>).unwrap(), IpldValue::Null);
+    assert_eq!(to_ipld(&()).unwrap(), IpldValue::Null);
+}
+
+#[test]
+fn test_roundtrip() {
+    for kind in vec![
+        Kind::File,
+        Kind::Symlink("/a".into()),
+        Kind::Chunk(1, 2),
+        Kind::Dir {
+            entries: vec![IpldLink(cid()), IpldLink(cid())],
+        },
+    ] {
+        let entry = entry(kind);
+        let value = to_ipld(&entry).unwrap();
+        // through the encoding too
+        let value = dag_cbor::decode(&dag_cbor::encode(&value).unwrap()).unwrap();
+        assert_eq!(from_ipld::<Entry>(value).unwrap(), entry);
+    }
+
+    let mut map = HashMap::new();
+    map.insert("one".to_string(), IpldLink(cid()));
+    let value = to_ipld(&map).unwrap();
+    assert_eq!(from_ipld::<HashMap<String, IpldLink>>(value).unwrap(), map);
+}
+
+#[test]
+fn test_ipld_value_roundtrip() {
+    let value = IpldValue::Map(btreemap! {
+        "link".into() => IpldValue::Link(cid()),
+        "bytes".into() => IpldValue::Bytes(vec![1, 2, 3]),
+        "big".into() => IpldValue::Integer(-i128::from(u64::max_value())),
+        "list".into() => IpldValue::List(vec![IpldValue::Null, IpldValue::Float(1.5)]),
+    });
+    assert_eq!(to_ipld(&value).unwrap(), value);
+    assert_eq!(from_ipld::<IpldValue>(value.clone()).unwrap(), value);
+}
+
+#[test]
+fn test_errors() {
+    let mut map = HashMap::new();
+    map.insert(1u32, 2u32);
+    assert!(matches!(to_ipld(&map), Err(IpldCoreError::ObjErr(_))));
+
+    let value = IpldValue::String("foo".into());
+    assert!(matches!(
+        from_ipld::<u64>(value.clone()),
+        Err(IpldCoreError::ObjErr(_))
+    ));
+    assert!(from_ipld::<IpldLink>(value).is_err());
+    assert!(from_ipld::<u8>(IpldValue::Integer(256)).is_err());
+    assert!(from_ipld::<Kind>(IpldValue::String("Other".into())).is_err());
+    assert!(from_ipld::<Entry>(IpldValue::Map(BTreeMap::new())).is_err());
+}
+
+#[test]
+fn test_from_object_with_codec() {
+    let entry = entry(Kind::Chunk(3, 4));
+    let node =
+        IpldNode::from_object_with_codec(&entry, Code::Sha2_256.into(), Codec::DagCBOR).unwrap();
+    assert_eq!(node.cid().codec(), Codec::DagCBOR);
+    assert_eq!(node.obj(), &to_ipld(&entry).unwrap());
+    assert_eq!(
+        node.raw_data(),
+        dag_cbor::encode(node.obj()).unwrap().as_slice()
+    );
+    assert_eq!(node.links().len(), 1);
+    assert_eq!(node.links()[0].cid, cid());
+    assert_eq!(from_ipld::<Entry>(node.obj().clone()).unwrap(), entry);
+
+    let wrapped = IpldNode::wrap_object(node.obj(), Code::Sha2_256.into()).unwrap();
+    assert_eq!(wrapped.cid(), node.cid());
+
+    assert!(matches!(
+        IpldNode::from_object_with_codec(&entry, Code::Sha2_256.into(), Codec::DagJSON),
+        Err(IpldCoreError::UnsupportedCodec(Codec::DagJSON))
+    ));
+}
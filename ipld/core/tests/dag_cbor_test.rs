@@ -0,0 +1,303 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::convert::TryFrom;
+use std::fs;
+
+use cid::{Cid, Codec, IntoExt};
+use maplit::btreemap;
+use matches::matches;
+use multihash::Code;
+
+use block_format::{BasicBlock, Block};
+use ipld_core::{dag_cbor, dag_json, IpldCoreError, IpldNode, IpldValue};
+use ipld_format::coding::CodecRegistry;
+
+const FIXTURES_ROOT: &str = "tests/dag_cbor_fixtures/";
+
+fn fixtures() -> Vec<(String, Vec<u8>, Cid)> {
+    let content = fs::read(format!("{}expected.json", FIXTURES_ROOT)).unwrap();
+    match dag_json::decode(&content).unwrap() {
+        IpldValue::Map(map) => map
+            .into_iter()
+            .map(|(name, cid)| {
+                let data = fs::read(format!("{}{}.cbor", FIXTURES_ROOT, name)).unwrap();
+                match cid {
+                    IpldValue::Link(cid) => (name.into_inner(), data, cid),
+                    _ => unreachable!(),
+                }
+            })
+            .collect(),
+        _ => unreachable!(),
+    }
+}
+
+fn read(name: &str) -> IpldValue {
+    let data = fs::read(format!("{}{}.cbor", FIXTURES_ROOT, name)).unwrap();
+    dag_cbor::decode(&data).unwrap()
+}
+
+#[test]
+fn test_fixtures_roundtrip() {
+    let fixtures = fixtures();
+    assert_eq!(fixtures.len(), 35);
+    for (name, data, cid) in fixtures {
+        let value = dag_cbor::decode(&data).unwrap();
+        assert_eq!(dag_cbor::encode(&value).unwrap(), data, "{}", name);
+
+        let block = BasicBlock::new_with_cid(data.into(), cid.clone()).unwrap();
+        let node = IpldNode::from_block(&block).unwrap();
+        assert_eq!(node.cid(), &cid, "{}", name);
+        assert_eq!(node.obj(), &value, "{}", name);
+    }
+}
+
+#[test]
+fn test_fixtures_values() {
+    assert_eq!(read("null"), IpldValue::Null);
+    assert_eq!(read("int-24"), IpldValue::Integer(24));
+    assert_eq!(read("int-u32-max-plus-one"), IpldValue::Integer(1 << 32));
+    assert_eq!(
+        read("int-u64-max"),
+        IpldValue::Integer(u64::max_value().into())
+    );
+    assert_eq!(
+        read("int-i64-min"),
+        IpldValue::Integer(i64::min_value().into())
+    );
+    assert_eq!(
+        read("int-negative-u64-max"),
+        IpldValue::Integer(-i128::from(u64::max_value()) - 1)
+    );
+    assert_eq!(read("float-large"), IpldValue::Float(1e300));
+    match read("float-negative-zero") {
+        IpldValue::Float(f) => assert!(f == 0.0 && f.is_sign_negative()),
+        _ => unreachable!(),
+    }
+    assert_eq!(read("string-long"), IpldValue::String("a".repeat(300)));
+    assert_eq!(
+        read("link-v1"),
+        IpldValue::Link(
+            Cid::try_from("bafyreidj7kdv2gkmhycdq5bagiloawte5z6egwztbwovsk6v3xookbpyuu").unwrap()
+        )
+    );
+    assert_eq!(
+        read("map-sorting"),
+        IpldValue::Map(btreemap! {
+            "".into() => IpldValue::Integer(5),
+            "B".into() => IpldValue::Integer(4),
+            "a".into() => IpldValue::Integer(2),
+            "b".into() => IpldValue::Integer(1),
+            "aa".into() => IpldValue::Integer(3),
+            "é".into() => IpldValue::Integer(6),
+            "zzz".into() => IpldValue::Integer(7),
+        })
+    );
+    assert_eq!(
+        fs::read(format!("{}map-sorting.cbor", FIXTURES_ROOT)).unwrap(),
+        hex::decode("a760056142046161026162016261610362c3a906637a7a7a07").unwrap()
+    );
+}
+
+#[test]
+fn test_same_data_model_as_dag_json() {
+    for entry in fs::read_dir("tests/dag_json_fixtures/").unwrap() {
+        let path = entry.unwrap().path();
+        if path.file_name().unwrap() == "expected.json" {
+            continue;
+        }
+        let value = dag_json::decode(&fs::read(&path).unwrap()).unwrap();
+        let cbor = dag_cbor::encode(&value).unwrap();
+        assert_eq!(dag_cbor::decode(&cbor).unwrap(), value, "{:?}", path);
+    }
+}
+
+#[test]
+fn test_strict_decoding() {
+    let invalid = vec![
+        // non-minimal integers and lengths
+        "1817",
+        "190017",
+        "1a000000ff",
+        "1b00000000ffffffff",
+        "3817",
+        "5800",
+        "780161",
+        "9800",
+        "b800",
+        // indefinite lengths
+        "9fff",
+        "bfff",
+        "5fff",
+        "7fff",
+        // reserved additional information
+        "1c",
+        // undefined, simple values and break
+        "f7",
+        "f0",
+        "f820",
+        "ff",
+        // floats not encoded in 64 bits, and not finite
+        "f93c00",
+        "fa3fc00000",
+        "fb7ff8000000000000",
+        "fb7ff0000000000000",
+        "fbfff0000000000000",
+        // tags other than 42
+        "c074323031332d30332d32315432303a30343a30305a",
+        "c249010000000000000000",
+        // links that are not bytes, without the identity prefix, or invalid
+        "d82a6161",
+        "d82a420155",
+        "d82a4100",
+        // unsorted, duplicate and non-string keys
+        "a2616201616102",
+        "a2626161016162 02",
+        "a2616101616102",
+        "a1010203",
+        // invalid UTF-8
+        "62c328",
+        // truncated and trailing data
+        "",
+        "82",
+        "6261",
+        "1b0000",
+        "f6f6",
+    ];
+    for bytes in invalid {
+        let bytes = hex::decode(bytes.replace(' ', "")).unwrap();
+        assert!(
+            matches!(dag_cbor::decode(&bytes), Err(IpldCoreError::DagCborErr(_))
+                | Err(IpldCoreError::CidErr(_))),
+            "{} should be rejected",
+            hex::encode(&bytes)
+        );
+    }
+
+    // the non-canonical test object is accepted by the lenient decoding only
+    let non_canon = fs::read("tests/test_objects/non-canon.cbor").unwrap();
+    assert!(dag_cbor::decode(&non_canon).is_err());
+    assert!(dag_cbor::decode_lenient(&non_canon).is_ok());
+
+    // the nesting is limited
+    let mut deep = vec![0x81; 100_000];
+    deep.push(0xf6);
+    assert!(dag_cbor::decode(&deep).is_err());
+    assert!(dag_cbor::decode_ref(&deep).is_err());
+}
+
+#[test]
+fn test_lenient_decoding() {
+    let decode = |bytes: &str| dag_cbor::decode_lenient(&hex::decode(bytes).unwrap());
+    let map = decode("a2616101616202").unwrap();
+    // unsorted keys, the last duplicate key is kept
+    assert_eq!(decode("a2616202616101").unwrap(), map);
+    assert_eq!(decode("a3616105616202616101").unwrap(), map);
+    // non-minimal lengths
+    assert_eq!(decode("1817").unwrap(), IpldValue::Integer(23));
+    assert_eq!(
+        decode("7a0000000161").unwrap(),
+        IpldValue::String("a".into())
+    );
+    // floats not encoded in 64 bits, and non-finite floats
+    assert_eq!(decode("f93e00").unwrap(), IpldValue::Float(1.5));
+    assert_eq!(
+        decode("f98001").unwrap(),
+        IpldValue::Float(-(2f64.powi(-24)))
+    );
+    assert_eq!(decode("fa3fc00000").unwrap(), IpldValue::Float(1.5));
+    assert_eq!(
+        decode("f97c00").unwrap(),
+        IpldValue::Float(std::f64::INFINITY)
+    );
+    assert!(matches!(decode("fb7ff8000000000000"), Ok(IpldValue::Float(f)) if f.is_nan()));
+
+    // the other tags, simple values, indefinite lengths and trailing data are rejected
+    for bytes in &["c100", "f7", "f0", "9fff", "5f41004100ff", "f6f6", "82"] {
+        assert!(decode(bytes).is_err(), "{} should be rejected", bytes);
+    }
+    let mut deep = vec![0x81; 100_000];
+    deep.push(0xf6);
+    assert!(dag_cbor::decode_ref_lenient(&deep).is_err());
+}
+
+#[test]
+fn test_node_decoding() {
+    let registry = CodecRegistry::with_defaults();
+    let to_block = |data: Vec<u8>| {
+        let cid = Cid::new_v1(Codec::DagCBOR, Code::Sha2_256.digest(&data).into_ext());
+        BasicBlock::new_with_cid(data.into(), cid).unwrap()
+    };
+
+    // the non-canonical data is only rejected by the strict decoding
+    let non_canon = fs::read("tests/test_objects/non-canon.cbor").unwrap();
+    for data in vec![hex::decode("a2616201616101").unwrap(), non_canon] {
+        let node = IpldNode::from_cbor(&data, Code::Sha2_256.into()).unwrap();
+        assert_ne!(node.raw_data().as_ref(), data.as_slice());
+        assert_eq!(node.raw_data(), &dag_cbor::encode(node.obj()).unwrap());
+        assert!(IpldNode::from_cbor_strict(&data, Code::Sha2_256.into()).is_err());
+
+        let block = to_block(data);
+        assert_eq!(IpldNode::from_block(&block).unwrap().cid(), block.cid());
+        assert_eq!(registry.decode(&block).unwrap().cid(), block.cid());
+        assert!(IpldNode::from_block_strict(&block).is_err());
+    }
+
+    let data = hex::decode("a2616101616202").unwrap();
+    let node = IpldNode::from_cbor_strict(&data, Code::Sha2_256.into()).unwrap();
+    assert_eq!(node.raw_data().as_ref(), data.as_slice());
+    assert_eq!(
+        node,
+        IpldNode::from_cbor(&data, Code::Sha2_256.into()).unwrap()
+    );
+    let block = to_block(data);
+    assert_eq!(IpldNode::from_block_strict(&block).unwrap(), node);
+
+    // too deep nesting
+    let mut deep = vec![0x81; 100_000];
+    deep.push(0xf6);
+    assert!(IpldNode::from_cbor(&deep, Code::Sha2_256.into()).is_err());
+    assert!(registry.decode(&to_block(deep)).is_err());
+}
+
+#[test]
+fn test_encode_errors() {
+    for value in &[
+        IpldValue::Float(std::f64::NAN),
+        IpldValue::Float(std::f64::INFINITY),
+        IpldValue::List(vec![IpldValue::Float(std::f64::NEG_INFINITY)]),
+        IpldValue::Integer(i128::from(u64::max_value()) + 1),
+        IpldValue::Integer(-i128::from(u64::max_value()) - 2),
+    ] {
+        assert!(matches!(
+            dag_cbor::encode(value),
+            Err(IpldCoreError::DagCborErr(_))
+        ));
+    }
+}
+
+#[test]
+fn test_lenient_integers() {
+    // the lenient decoding reads the whole range of u64 too
+    let value = minicbor::decode::<IpldValue>(&[0x1b, 0xff, 0, 0, 0, 0, 0, 0, 1]).unwrap();
+    assert_eq!(value, IpldValue::Integer(0xff00_0000_0000_0001));
+    let encoded = minicbor::to_vec(&value).unwrap();
+    assert_eq!(encoded, dag_cbor::encode(&value).unwrap());
+    assert!(minicbor::to_vec(&IpldValue::Integer(-i128::from(u64::max_value()))).is_err());
+    assert!(minicbor::decode::<IpldValue>(&[0x9f, 0xff]).is_err());
+}
+
+#[test]
+fn test_registry() {
+    let registry = CodecRegistry::with_defaults();
+    let value = IpldValue::Map(btreemap! {
+        "big".into() => IpldValue::Integer(u64::max_value().into()),
+        "a".into() => IpldValue::Float(1.5),
+    });
+    let data = registry.encode(Codec::DagCBOR, &value).unwrap();
+    assert_eq!(data, dag_cbor::encode(&value).unwrap());
+    let node = IpldNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+    assert_eq!(node.raw_data(), data.as_slice());
+    assert!(registry
+        .encode(Codec::DagCBOR, &IpldValue::Float(std::f64::NAN))
+        .is_err());
+}
@@ -0,0 +1,221 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::convert::TryFrom;
+use std::fs;
+
+use cid::{Cid, Codec};
+use either::Either;
+use maplit::btreemap;
+use matches::matches;
+use multihash::Code;
+
+use block_format::{BasicBlock, Block};
+use ipld_core::{dag_cbor, dag_json, DagJsonNode, IpldCoreError, IpldValue};
+use ipld_format::coding::CodecRegistry;
+use ipld_format::{Node, Resolver};
+
+const FIXTURES_ROOT: &str = "tests/dag_json_fixtures/";
+
+fn fixtures() -> Vec<(String, Vec<u8>, Cid)> {
+    let content = fs::read(format!("{}expected.json", FIXTURES_ROOT)).unwrap();
+    match dag_json::decode(&content).unwrap() {
+        IpldValue::Map(map) => map
+            .into_iter()
+            .map(|(name, cid)| {
+                let data = fs::read(format!("{}{}.json", FIXTURES_ROOT, name)).unwrap();
+                match cid {
+                    IpldValue::Link(cid) => (name.into_inner(), data, cid),
+                    _ => unreachable!(),
+                }
+            })
+            .collect(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_fixtures_roundtrip() {
+    let fixtures = fixtures();
+    assert_eq!(fixtures.len(), 22);
+    for (name, data, cid) in fixtures {
+        let value = dag_json::decode(&data).unwrap();
+        assert_eq!(dag_json::encode(&value).unwrap(), data, "{}", name);
+
+        let node = DagJsonNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+        assert_eq!(node.cid(), &cid, "{}", name);
+        assert_eq!(node.raw_data(), data.as_slice(), "{}", name);
+
+        let block = BasicBlock::new_with_cid(data.into(), cid.clone()).unwrap();
+        let node = DagJsonNode::from_block(&block).unwrap();
+        assert_eq!(node.obj(), &value, "{}", name);
+    }
+}
+
+#[test]
+fn test_fixtures_values() {
+    let cid = Cid::try_from("QmRgutAxd8t7oGkSm4wmeuByG6M51wcTso6cubDdQtuEfL").unwrap();
+    let read = |name: &str| {
+        let data = fs::read(format!("{}{}.json", FIXTURES_ROOT, name)).unwrap();
+        dag_json::decode(&data).unwrap()
+    };
+    assert_eq!(read("null"), IpldValue::Null);
+    assert_eq!(read("int-max"), IpldValue::Integer(u64::max_value().into()));
+    assert_eq!(read("int-min"), IpldValue::Integer(i64::min_value().into()));
+    assert_eq!(read("float-integral"), IpldValue::Float(1.0));
+    assert_eq!(
+        read("string-escapes"),
+        IpldValue::String("a\"b\\c\n\t\u{1}/".into())
+    );
+    assert_eq!(read("bytes-empty"), IpldValue::Bytes(vec![]));
+    assert_eq!(
+        read("bytes"),
+        IpldValue::Bytes(vec![0, 1, 2, 253, 254, 255, 16])
+    );
+    assert_eq!(read("link-v0"), IpldValue::Link(cid));
+    assert_eq!(
+        read("map-sorting"),
+        IpldValue::Map(btreemap! {
+            "".into() => IpldValue::Integer(5),
+            "B".into() => IpldValue::Integer(4),
+            "a".into() => IpldValue::Integer(2),
+            "aa".into() => IpldValue::Integer(3),
+            "b".into() => IpldValue::Integer(1),
+            "é".into() => IpldValue::Integer(6),
+        })
+    );
+
+    // the same data as the DAG-CBOR test object
+    let cbor = fs::read("tests/test_objects/foo.cbor").unwrap();
+    let value = minicbor::decode::<IpldValue>(&cbor).unwrap();
+    assert_eq!(read("nested"), value);
+}
+
+#[test]
+fn test_strict_decoding() {
+    let invalid = vec![
+        // duplicate keys
+        r#"{"a":1,"a":2}"#,
+        // invalid links
+        r#"{"/":"invalid"}"#,
+        r#"{"/":1}"#,
+        r#"{"/":null}"#,
+        // a `/` key with other keys
+        r#"{"/":"QmRgutAxd8t7oGkSm4wmeuByG6M51wcTso6cubDdQtuEfL","a":1}"#,
+        // invalid bytes
+        r#"{"/":{"bytes":"AAE="}}"#,
+        r#"{"/":{"bytes":"!!"}}"#,
+        r#"{"/":{"bytes":1}}"#,
+        r#"{"/":{"bytes":"AA","a":1}}"#,
+        r#"{"/":{"other":"AA"}}"#,
+        // integers out of the range of DAG-CBOR
+        "18446744073709551616",
+        "-18446744073709551617",
+        "1e400",
+        r#"{"a":[1,100000000000000000000000000000000000000000]}"#,
+        // trailing data and invalid JSON
+        "{} {}",
+        "[1,]",
+        "",
+    ];
+    for json in invalid {
+        assert!(
+            dag_json::decode(json.as_bytes()).is_err(),
+            "{} should be rejected",
+            json
+        );
+    }
+
+    // the integers at the limits of the range, and the floats, are accepted
+    assert_eq!(
+        dag_json::decode(b"[18446744073709551615,-18446744073709551616,1e20,-0]").unwrap(),
+        IpldValue::List(vec![
+            IpldValue::Integer(u64::max_value().into()),
+            IpldValue::Integer(-i128::from(u64::max_value()) - 1),
+            IpldValue::Float(1e20),
+            IpldValue::Float(-0.0),
+        ])
+    );
+    // the same range as DAG-CBOR
+    let value = IpldValue::Integer(-i128::from(u64::max_value()) - 1);
+    let cbor = dag_cbor::encode(&value).unwrap();
+    let json = dag_json::encode(&dag_cbor::decode(&cbor).unwrap()).unwrap();
+    assert_eq!(json, b"-18446744073709551616");
+    assert_eq!(dag_json::decode(&json).unwrap(), value);
+    assert!(dag_json::decode(br#"["18446744073709551616"]"#).is_ok());
+
+    // the other maps with a `/` key deeper are regular maps
+    let value = dag_json::decode(br#"{"a":{"b":"/"},"c":{"/a":1}}"#).unwrap();
+    assert!(matches!(value, IpldValue::Map(_)));
+}
+
+#[test]
+fn test_encode_errors() {
+    for value in &[
+        IpldValue::Float(std::f64::NAN),
+        IpldValue::Float(std::f64::INFINITY),
+        IpldValue::List(vec![IpldValue::Float(std::f64::NEG_INFINITY)]),
+        IpldValue::Integer(i128::from(u64::max_value()) + 1),
+        IpldValue::Integer(-i128::from(u64::max_value()) - 2),
+    ] {
+        assert!(matches!(
+            dag_json::encode(value),
+            Err(IpldCoreError::DagJsonErr(_))
+        ));
+    }
+}
+
+#[test]
+fn test_node() {
+    let cid = Cid::try_from("QmRgutAxd8t7oGkSm4wmeuByG6M51wcTso6cubDdQtuEfL").unwrap();
+    let value = IpldValue::Map(btreemap! {
+        "name".into() => IpldValue::String("foo".into()),
+        "children".into() => IpldValue::List(vec![IpldValue::Link(cid.clone())]),
+    });
+    let node = DagJsonNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+    assert_eq!(node.cid().codec(), Codec::DagJSON);
+    assert_eq!(
+        node.raw_data(),
+        &br#"{"children":[{"/":"QmRgutAxd8t7oGkSm4wmeuByG6M51wcTso6cubDdQtuEfL"}],"name":"foo"}"#[..]
+    );
+    assert_eq!(node.links().len(), 1);
+    assert_eq!(node.tree("", None), vec!["name", "children", "children/0"]);
+
+    let (link, rest) = node.resolve_link(&["children", "0", "a"]).unwrap();
+    assert_eq!(link.cid, cid);
+    assert_eq!(rest, vec!["a"]);
+    match node.resolve(&["name"]).unwrap() {
+        (Either::Right(IpldValue::String(name)), _) => assert_eq!(name, "foo"),
+        _ => unreachable!(),
+    }
+
+    let stat = node.stat().unwrap();
+    assert_eq!(stat.num_links, 1);
+    assert_eq!(stat.links_size, 8 + 46);
+    assert_eq!(stat.data_size, node.raw_data().len() - 54);
+
+    // the whitespace of the JSON input isn't kept
+    let pretty = br#"{ "name": "foo", "children": [ { "/": "QmRgutAxd8t7oGkSm4wmeuByG6M51wcTso6cubDdQtuEfL" } ] }"#;
+    let from_json = DagJsonNode::from_json(pretty, Code::Sha2_256.into()).unwrap();
+    assert_eq!(from_json, node);
+
+    // a DAG-CBOR block isn't a DAG-JSON node
+    let cbor = Cid::new_v1(Codec::DagCBOR, node.cid().hash().to_owned());
+    let block = BasicBlock::new_with_cid(node.to_bytes(), cbor).unwrap();
+    assert!(DagJsonNode::from_block(&block).is_err());
+}
+
+#[test]
+fn test_registry() {
+    let registry = CodecRegistry::with_defaults();
+    assert!(registry.has_decoder(Codec::DagJSON));
+    assert!(registry.has_encoder::<IpldValue>(Codec::DagJSON));
+
+    let value = IpldValue::List(vec![IpldValue::Bytes(b"IPLD".to_vec())]);
+    let data = registry.encode(Codec::DagJSON, &value).unwrap();
+    assert_eq!(data, br#"[{"/":{"bytes":"SVBMRA"}}]"#);
+    let node = DagJsonNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+    let block = BasicBlock::new_with_cid(data.into(), node.cid().clone()).unwrap();
+    let decoded = registry.decode(&block).unwrap();
+    assert_eq!(decoded.cid(), node.cid());
+    assert!(decoded.links().is_empty());
+}
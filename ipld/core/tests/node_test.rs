@@ -8,7 +8,7 @@ use maplit::btreemap;
 use multihash::Code;
 
 use block_format::{BasicBlock, Block};
-use ipld_core::{IpldNode, IpldValue};
+use ipld_core::{IpldNode, IpldValue, IpldValueRef};
 use ipld_format::{Node, Resolver};
 
 #[test]
@@ -91,10 +91,10 @@ fn test_marshal_roundtrip() {
                 {\"/\":\"Qma75NN8GaM99ioqsNUF9Ho816SonoGsVrvSnqz9uL4LPF\"},\
                 {\"/\":\"QmUE28rcN99es8ntD4T3sBScfyrADkF3q8qzM1gEW82oMh\"}\
             ],\
-            \"foo\":\"bar\",\
             \"cats\":{\
                 \"qux\":{\"/\":\"QmSsM8Xm1g5WtfwkBvnttZafpDhaW6jkXeSFccECUnx7hg\"}\
             },\
+            \"foo\":\"bar\",\
             \"hello\":{\"/\":\"Qma75NN8GaM99ioqsNUF9Ho816SonoGsVrvSnqz9uL4LPF\"}\
         }"
     );
@@ -247,6 +247,8 @@ fn test_json_roundtrip() {
             "bafyreian4t6wau4jdqt6nys76dfvsn6g7an4ulbv326yzutdgnrr5cjpui",
         ),
     ];
+    // the integers out of range aren't parsed into floats
+    assert!(IpldNode::from_json("18446744073709551616", Code::Sha2_256.into()).is_err());
     for (json, expect_cid) in examples {
         let node = IpldNode::from_json(json, Code::Sha2_256.into()).unwrap();
         assert_eq!(node.cid().to_string(), expect_cid);
@@ -263,7 +265,14 @@ fn test_cbor_roundtrip() {
         "bafyreiengp2sbi6ez34a2jctv34bwyjl7yoliteleaswgcwtqzrhmpyt2m"
     );
     let cbor = node.to_cbor().unwrap();
-    assert_eq!(vec![b'`'], cbor)
+    assert_eq!(vec![b'`'], cbor);
+
+    // -2^64 is out of the range of the minicbor encoding
+    let cbor = [0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+    let cid = Cid::new_v1(Codec::DagCBOR, Code::Sha2_256.digest(&cbor).into_ext());
+    let block = BasicBlock::new_with_cid(cbor.to_vec().into(), cid).unwrap();
+    let node = IpldNode::from_block(&block).unwrap();
+    assert_eq!(node.to_cbor().unwrap(), cbor);
 }
 
 const TEST_OBJ_ROOT: &str = "tests/test_objects/";
@@ -399,3 +408,161 @@ fn test_canonical_struct_encoding() {
     let foo2 = minicbor::decode::<Foo>(&cbor).unwrap();
     assert_eq!(foo1, foo2);
 }
+
+#[test]
+fn test_default_codecs() {
+    let value = IpldValue::Map(btreemap! {
+        "name".into() => IpldValue::String("foo".to_string()),
+    });
+    let node = IpldNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+
+    // dag-cbor is registered without calling `register`
+    let block = BasicBlock::new_with_cid(node.to_bytes(), node.cid().clone()).unwrap();
+    let decoded = ipld_format::coding::decode(&block).unwrap();
+    assert_eq!(decoded.raw_data(), node.raw_data());
+    let encoded = ipld_format::coding::encode(Codec::DagCBOR, &value).unwrap();
+    assert_eq!(node.raw_data(), &encoded);
+
+    let mut registry = ipld_format::coding::CodecRegistry::new();
+    ipld_core::register_codecs(&mut registry);
+    assert_eq!(registry.codecs(), vec![Codec::Raw, Codec::DagCBOR]);
+    let bytes = IpldValue::Bytes(b"raw".to_vec());
+    assert_eq!(registry.encode(Codec::Raw, &bytes).unwrap(), b"raw");
+    assert!(registry.encode(Codec::Raw, &value).is_err());
+}
+
+#[test]
+fn test_stat() {
+    use std::collections::HashMap;
+
+    use ipld_format::daghelpers;
+    use ipld_format::{NodeGetter, Result};
+
+    struct MapGetter(HashMap<Cid, IpldNode>);
+
+    impl NodeGetter<IpldNode> for MapGetter {
+        fn get(&self, cid: &Cid) -> Result<IpldNode> {
+            self.0
+                .get(cid)
+                .cloned()
+                .ok_or(ipld_format::FormatError::NoSuchLink)
+        }
+    }
+
+    let leaf =
+        IpldNode::wrap_object(&IpldValue::String("leaf".into()), Code::Sha2_256.into()).unwrap();
+    let stat = leaf.stat().unwrap();
+    assert_eq!(stat.num_links, 0);
+    assert_eq!(stat.block_size, leaf.raw_data().len());
+    assert_eq!(stat.links_size, 0);
+    assert_eq!(stat.data_size, stat.block_size);
+    assert_eq!(stat.hash.as_bytes(), leaf.cid().hash().as_bytes());
+
+    let value = IpldValue::Map(btreemap! {
+        "a".into() => IpldValue::Link(leaf.cid().clone()),
+        "b".into() => IpldValue::List(vec![IpldValue::Link(leaf.cid().clone())]),
+    });
+    let root = IpldNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+    let stat = root.stat().unwrap();
+    assert_eq!(stat.num_links, 2);
+    assert_eq!(stat.block_size, root.raw_data().len());
+    // tag 42, byte string header, multibase prefix and 36 bytes of CID
+    assert_eq!(stat.links_size, 2 * (2 + 2 + 1 + 36));
+    assert_eq!(stat.data_size, stat.block_size - stat.links_size);
+    assert_eq!(stat.cumulative_size, stat.block_size);
+
+    let mut nodes = HashMap::new();
+    nodes.insert(leaf.cid().clone(), leaf.clone());
+    nodes.insert(root.cid().clone(), root.clone());
+    let getter = MapGetter(nodes);
+    let dag_stat = daghelpers::stat(&getter, root.cid()).unwrap();
+    assert_eq!(dag_stat.num_links, 2);
+    assert_eq!(
+        dag_stat.cumulative_size,
+        root.raw_data().len() + 2 * leaf.raw_data().len()
+    );
+}
+
+#[test]
+fn test_value_ref() {
+    let cid = Cid::new_v0(Code::Sha2_256.digest(b"something").into_ext()).unwrap();
+    let value = IpldValue::Map(btreemap! {
+        "name".into() => IpldValue::String("foo".to_string()),
+        "data".into() => IpldValue::Bytes(vec![0xab; 100]),
+        "chunks".into() => IpldValue::List(vec![
+            IpldValue::Link(cid.clone()),
+            IpldValue::Integer(-1),
+            IpldValue::Null,
+        ]),
+    });
+    let node = IpldNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+    let value_ref = node.value_ref().unwrap();
+    assert_eq!(value_ref.to_value(), value);
+    assert_eq!(IpldValue::from(&value_ref), value);
+    assert_eq!(value_ref.kind(), "map");
+
+    // the strings and the byte strings are borrowed from the block
+    let raw = node.raw_data();
+    let raw = raw.as_ptr() as usize..raw.as_ptr() as usize + raw.len();
+    let name = value_ref
+        .get("name")
+        .and_then(IpldValueRef::as_str)
+        .unwrap();
+    assert_eq!(name, "foo");
+    assert!(raw.contains(&(name.as_ptr() as usize)));
+    let data = value_ref
+        .get("data")
+        .and_then(IpldValueRef::as_bytes)
+        .unwrap();
+    assert_eq!(data.len(), 100);
+    assert!(raw.contains(&(data.as_ptr() as usize)));
+
+    assert_eq!(
+        value_ref
+            .get_path(&["chunks", "0"])
+            .and_then(IpldValueRef::as_link),
+        Some(&cid)
+    );
+    assert_eq!(
+        value_ref.get_path(&["chunks", "1"]),
+        Some(&IpldValueRef::Integer(-1))
+    );
+    assert_eq!(value_ref.get_path(&["chunks", "3"]), None);
+    assert_eq!(value_ref.get_path(&["name", "0"]), None);
+
+    // the entries are kept in the order of the encoding, which is the canonical one
+    match value_ref {
+        IpldValueRef::Map(entries) => {
+            let keys = entries.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+            assert_eq!(keys, vec!["data", "name", "chunks"]);
+        }
+        _ => unreachable!(),
+    }
+
+    assert!(IpldValueRef::decode(&[0x9f, 0xff]).is_err());
+    assert!(IpldValueRef::decode(&[0xa1, 0x01, 0x01]).is_err());
+    // the nesting is limited
+    let mut deep = vec![0x81; 100_000];
+    deep.push(0xf6);
+    assert!(IpldValueRef::decode(&deep).is_err());
+    assert!(IpldValueRef::decode(&deep[100_000 - 512..]).is_ok());
+}
+
+#[test]
+fn test_lazy_links() {
+    let cid = Cid::new_v0(Code::Sha2_256.digest(b"something").into_ext()).unwrap();
+    let value = IpldValue::Map(btreemap! {
+        "a".into() => IpldValue::List(vec![IpldValue::Link(cid.clone())]),
+        "b".into() => IpldValue::Link(cid.clone()),
+    });
+    let node = IpldNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+    // clones and decoded copies are equal, whether the links are computed or not
+    let copy = IpldNode::from_block(&node).unwrap();
+    assert_eq!(node.links().len(), 2);
+    assert_eq!(node, copy);
+    assert_eq!(copy.clone().links(), node.links());
+    assert_eq!(copy.tree("", None), vec!["a", "a/0", "b"]);
+    assert_eq!(copy.stat().unwrap().num_links, 2);
+    // the object is decoded on first use
+    assert_eq!(copy.obj(), &value);
+}
@@ -0,0 +1,192 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::HashMap;
+
+use cid::Cid;
+use either::Either;
+use maplit::btreemap;
+use matches::matches;
+use multihash::Code;
+
+use block_format::Block;
+use ipld_core::{IpldNode, IpldValue};
+use ipld_format::{FormatError, NodeGetter, PathResolver, Resolved, Result};
+
+#[derive(Default)]
+struct MapGetter(HashMap<Cid, IpldNode>);
+
+impl MapGetter {
+    fn add(&mut self, value: IpldValue) -> Cid {
+        let node = IpldNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+        let cid = node.cid().clone();
+        self.0.insert(cid.clone(), node);
+        cid
+    }
+}
+
+impl NodeGetter<IpldNode> for MapGetter {
+    fn get(&self, cid: &Cid) -> Result<IpldNode> {
+        self.0.get(cid).cloned().ok_or(FormatError::NoSuchLink)
+    }
+}
+
+/// root: {"a": {"b": [leaf]}, "name": "root"}, leaf: {"c": {"d": 1}, "next": missing}
+fn setup() -> (MapGetter, Cid, Cid, Cid) {
+    let mut getter = MapGetter::default();
+    let missing = IpldNode::wrap_object(&IpldValue::Integer(0), Code::Sha2_256.into())
+        .unwrap()
+        .cid()
+        .clone();
+    let leaf = getter.add(IpldValue::Map(btreemap! {
+        "c".into() => IpldValue::Map(btreemap! {
+            "d".into() => IpldValue::Integer(1),
+        }),
+        "next".into() => IpldValue::Link(missing.clone()),
+    }));
+    let root = getter.add(IpldValue::Map(btreemap! {
+        "a".into() => IpldValue::Map(btreemap! {
+            "b".into() => IpldValue::List(vec![IpldValue::Link(leaf.clone())]),
+        }),
+        "name".into() => IpldValue::String("root".into()),
+    }));
+    (getter, root, leaf, missing)
+}
+
+#[test]
+fn test_resolve_across_blocks() {
+    let (getter, root, leaf, _) = setup();
+    let resolver = PathResolver::new(&getter);
+
+    let resolved = resolver
+        .resolve::<IpldNode>(&format!("/ipfs/{}/a/b/0/c/d", root))
+        .unwrap();
+    assert_eq!(resolved.cids, vec![root.clone(), leaf.clone()]);
+    assert_eq!(resolved.node.cid(), &leaf);
+    assert_eq!(
+        resolved.result,
+        Resolved::Value(Either::Right(IpldValue::Integer(1)))
+    );
+
+    // without prefix, and within the root block
+    let resolved = resolver
+        .resolve::<IpldNode>(&format!("{}/name", root))
+        .unwrap();
+    assert_eq!(resolved.cids, vec![root.clone()]);
+    assert_eq!(
+        resolved.result,
+        Resolved::Value(Either::Right(IpldValue::String("root".into())))
+    );
+
+    // the root itself
+    let resolved = resolver
+        .resolve::<IpldNode>(&format!("/ipld/{}", root))
+        .unwrap();
+    assert_eq!(resolved.cids, vec![root.clone()]);
+    assert_eq!(
+        resolved.result,
+        Resolved::Value(Either::Right(resolved.node.obj().clone()))
+    );
+}
+
+#[test]
+fn test_resolve_to_link() {
+    let (getter, root, leaf, missing) = setup();
+    let resolver = PathResolver::new(&getter);
+
+    let resolved = resolver
+        .resolve::<IpldNode>(&format!("/ipfs/{}/a/b/0", root))
+        .unwrap();
+    assert_eq!(resolved.cids, vec![root.clone()]);
+    match resolved.result {
+        Resolved::Link(link) => assert_eq!(link.cid, leaf),
+        Resolved::Value(_) => panic!("should end at a link"),
+    }
+
+    // the link to the missing block isn't followed
+    let resolved = resolver
+        .resolve::<IpldNode>(&format!("/ipfs/{}/a/b/0/next", root))
+        .unwrap();
+    assert_eq!(resolved.cids, vec![root, leaf]);
+    match resolved.result {
+        Resolved::Link(link) => assert_eq!(link.cid, missing),
+        Resolved::Value(_) => panic!("should end at a link"),
+    }
+}
+
+#[test]
+fn test_resolve_errors() {
+    let (getter, root, leaf, missing) = setup();
+    let resolver = PathResolver::new(&getter);
+
+    for path in &["", "/", "/ipns/a", "/ipfs/", "/ipfs/invalid/a"] {
+        match resolver.resolve::<IpldNode>(path) {
+            Err(FormatError::InvalidPath(p)) => assert_eq!(&p, path),
+            _ => panic!("{} should be invalid", path),
+        }
+    }
+
+    match resolver.resolve::<IpldNode>(&format!("/ipfs/{}/a/b/0/c/x", root)) {
+        Err(FormatError::ResolveFailed {
+            segment,
+            index,
+            cid,
+            ..
+        }) => {
+            assert_eq!(segment, "x");
+            assert_eq!(index, 4);
+            assert_eq!(cid, leaf);
+        }
+        _ => panic!("should fail on the segment `x`"),
+    }
+
+    match resolver.resolve::<IpldNode>(&format!("/ipfs/{}/a/x/0", root)) {
+        Err(FormatError::ResolveFailed {
+            segment,
+            index,
+            cid,
+            ..
+        }) => {
+            assert_eq!(segment, "x");
+            assert_eq!(index, 1);
+            assert_eq!(cid, root);
+        }
+        _ => panic!("should fail on the segment `x`"),
+    }
+
+    // through a value that isn't a map or a list
+    match resolver.resolve::<IpldNode>(&format!("/ipfs/{}/name/x", root)) {
+        Err(FormatError::ResolveFailed {
+            segment,
+            index,
+            source,
+            ..
+        }) => {
+            assert_eq!(segment, "x");
+            assert_eq!(index, 1);
+            assert!(matches!(*source, FormatError::Other(_)));
+        }
+        _ => panic!("should fail on the segment `x`"),
+    }
+
+    // the block linked by `next` is missing
+    match resolver.resolve::<IpldNode>(&format!("/ipfs/{}/a/b/0/next/x", root)) {
+        Err(FormatError::ResolveFailed {
+            segment,
+            index,
+            cid,
+            source,
+        }) => {
+            assert_eq!(segment, "next");
+            assert_eq!(index, 3);
+            assert_eq!(cid, leaf);
+            assert!(matches!(*source, FormatError::NoSuchLink));
+        }
+        _ => panic!("should fail on the segment `next`"),
+    }
+
+    // the root block is missing
+    assert!(matches!(
+        resolver.resolve::<IpldNode>(&format!("/ipfs/{}/a", missing)),
+        Err(FormatError::NoSuchLink)
+    ));
+}
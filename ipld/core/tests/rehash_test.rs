@@ -0,0 +1,103 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use cid::{Cid, Codec, ExtCode, IntoExt};
+use matches::matches;
+use multihash::Code;
+
+use block_format::{BasicBlock, Block};
+use ipld_core::{ipld, rehash, IpldCoreError, IpldNode};
+use ipld_format::{FormatError, NodeAdder, NodeGetter, Result};
+
+#[derive(Default)]
+struct MapStore(Mutex<HashMap<Cid, IpldNode>>);
+
+impl NodeGetter<IpldNode> for MapStore {
+    fn get(&self, cid: &Cid) -> Result<IpldNode> {
+        let nodes = self.0.lock().unwrap();
+        nodes.get(cid).cloned().ok_or(FormatError::NoSuchLink)
+    }
+}
+
+impl NodeAdder<IpldNode> for MapStore {
+    fn add(&self, node: &IpldNode) -> Result<()> {
+        let mut nodes = self.0.lock().unwrap();
+        nodes.insert(node.cid().clone(), node.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_rehash() {
+    let store = MapStore::default();
+    let raw = BasicBlock::new(b"raw leaf".as_ref().into()).cid().clone();
+    let add = |value| {
+        let node = IpldNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+        store.add(&node).unwrap();
+        node.cid().clone()
+    };
+    let shared = add(ipld!({"name": "shared", "raw": raw.clone()}));
+    let a = add(ipld!({"name": "a", "children": [shared.clone()]}));
+    let root = add(ipld!({"name": "root", "children": [a, shared.clone()]}));
+
+    let prefix = Cid::new_v1(Codec::DagCBOR, Code::Blake2b256.digest(b"").into_ext()).prefix();
+    let new_root = rehash(&root, &prefix, &store, &store).unwrap();
+    assert_ne!(new_root, root);
+    let blake2b: ExtCode = Code::Blake2b256.into();
+    assert_eq!(new_root.codec(), Codec::DagCBOR);
+    assert_eq!(new_root.hash().algorithm(), blake2b);
+
+    let node = store.get(&new_root).unwrap();
+    assert_eq!(node.obj().get("name"), Some(&ipld!("root")));
+    let children = node.obj().links().cloned().collect::<Vec<_>>();
+    assert_eq!(children.len(), 2);
+    let new_shared = children[1].clone();
+    let a = store.get(&children[0]).unwrap();
+    assert_eq!(a.obj().links().collect::<Vec<_>>(), vec![&new_shared]);
+    let shared = store.get(&new_shared).unwrap();
+    assert_eq!(new_shared.hash().algorithm(), blake2b);
+    // the links to other codecs are kept
+    assert_eq!(shared.obj().get("raw"), Some(&ipld!(raw.clone())));
+    // the same data as the original node, with the new hash
+    assert_eq!(
+        shared.raw_data(),
+        IpldNode::wrap_object(shared.obj(), Code::Sha2_256.into())
+            .unwrap()
+            .raw_data()
+    );
+    // 3 nodes in each DAG
+    assert_eq!(store.0.lock().unwrap().len(), 6);
+
+    // rehashing with the same prefix gives the same DAG
+    assert_eq!(
+        rehash(&new_root, &root.prefix(), &store, &store).unwrap(),
+        root
+    );
+    assert_eq!(store.0.lock().unwrap().len(), 6);
+    assert_eq!(rehash(&raw, &prefix, &store, &store).unwrap(), raw);
+}
+
+#[test]
+fn test_rehash_errors() {
+    let store = MapStore::default();
+    let missing = IpldNode::wrap_object(&ipld!(null), Code::Sha2_256.into())
+        .unwrap()
+        .cid()
+        .clone();
+    let root = IpldNode::wrap_object(&ipld!([missing]), Code::Sha2_256.into()).unwrap();
+    store.add(&root).unwrap();
+
+    let prefix = root.cid().prefix();
+    assert!(matches!(
+        rehash(root.cid(), &prefix, &store, &store),
+        Err(IpldCoreError::FormatErr(FormatError::NoSuchLink))
+    ));
+
+    let prefix = Cid::new_v1(Codec::DagJSON, Code::Sha2_256.digest(b"").into_ext()).prefix();
+    assert!(matches!(
+        rehash(root.cid(), &prefix, &store, &store),
+        Err(IpldCoreError::UnsupportedCodec(Codec::DagJSON))
+    ));
+}
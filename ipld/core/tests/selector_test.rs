@@ -0,0 +1,237 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use cid::Cid;
+use maplit::btreemap;
+use matches::matches;
+use multihash::Code;
+
+use ipld_core::selector::{walk, RecursionLimit, Selector, VisitReason};
+use ipld_core::{dag_cbor, dag_json, ipld, IpldCoreError, IpldNode, IpldValue};
+use ipld_format::{FormatError, NodeGetter, Result};
+
+#[derive(Default)]
+struct MapGetter(HashMap<Cid, IpldNode>);
+
+impl MapGetter {
+    fn add(&mut self, value: IpldValue) -> Cid {
+        let node = IpldNode::wrap_object(&value, Code::Sha2_256.into()).unwrap();
+        let cid = node.cid().clone();
+        self.0.insert(cid.clone(), node);
+        cid
+    }
+}
+
+impl NodeGetter<IpldNode> for MapGetter {
+    fn get(&self, cid: &Cid) -> Result<IpldNode> {
+        self.0.get(cid).cloned().ok_or(FormatError::NoSuchLink)
+    }
+}
+
+/// root -> [a -> [c], b]
+fn setup() -> (MapGetter, Cid) {
+    let mut getter = MapGetter::default();
+    let c = getter.add(ipld!({"name": "c", "children": []}));
+    let b = getter.add(ipld!({"name": "b", "children": []}));
+    let a = getter.add(ipld!({"name": "a", "children": [c]}));
+    let root = getter.add(ipld!({"name": "root", "children": [a, b]}));
+    (getter, root)
+}
+
+fn explore_children(next: Selector) -> Selector {
+    Selector::ExploreFields {
+        fields: btreemap! {
+            "children".into() => Selector::ExploreAll { next: Box::new(next) },
+        },
+    }
+}
+
+/// Matches the names of the nodes, recursively through the children.
+fn names(limit: RecursionLimit) -> Selector {
+    Selector::ExploreRecursive {
+        limit,
+        sequence: Box::new(Selector::ExploreUnion(vec![
+            Selector::ExploreFields {
+                fields: btreemap! { "name".into() => Selector::Matcher },
+            },
+            explore_children(Selector::ExploreRecursiveEdge),
+        ])),
+    }
+}
+
+fn matched(getter: &MapGetter, root: &Cid, selector: &Selector) -> Vec<(String, IpldValue)> {
+    let mut matched = vec![];
+    walk(getter, root, selector, |visit| {
+        if visit.reason == VisitReason::Match {
+            matched.push((visit.path.join("/"), visit.value.clone()));
+        }
+        Ok(())
+    })
+    .unwrap();
+    matched
+}
+
+#[test]
+fn test_encoding() {
+    let selector = Selector::ExploreUnion(vec![
+        Selector::Matcher,
+        Selector::ExploreIndex {
+            index: 1,
+            next: Box::new(Selector::ExploreRange {
+                start: 0,
+                end: 2,
+                next: Box::new(Selector::Matcher),
+            }),
+        },
+        names(RecursionLimit::Depth(3)),
+        names(RecursionLimit::None),
+    ]);
+    let value = IpldValue::from(&selector);
+    let encoded = |limit| {
+        ipld!({"R": {
+            "l": limit,
+            ":>": {"|": [
+                {"f": {"f>": {"name": {".": {}}}}},
+                {"f": {"f>": {"children": {"a": {">": {"@": {}}}}}}},
+            ]},
+        }})
+    };
+    assert_eq!(
+        value,
+        ipld!({"|": [
+            {".": {}},
+            {"i": {"i": 1, ">": {"r": {"^": 0, "$": 2, ">": {".": {}}}}}},
+            (encoded(ipld!({"depth": 3}))),
+            (encoded(ipld!({"none": {}}))),
+        ]})
+    );
+
+    let json = dag_json::encode(&value).unwrap();
+    assert_eq!(
+        Selector::try_from(&dag_json::decode(&json).unwrap()).unwrap(),
+        selector
+    );
+    let cbor = dag_cbor::encode(&value).unwrap();
+    assert_eq!(
+        Selector::try_from(&dag_cbor::decode(&cbor).unwrap()).unwrap(),
+        selector
+    );
+}
+
+#[test]
+fn test_invalid() {
+    for value in vec![
+        ipld!({}),
+        ipld!({".": {}, "@": {}}),
+        ipld!({"x": {}}),
+        ipld!({"a": {}}),
+        ipld!({"i": {"i": -1, ">": {".": {}}}}),
+        ipld!({"r": {"^": 2, "$": 1, ">": {".": {}}}}),
+        ipld!({"R": {"l": {"depth": 1}, ":>": {".": {}}}}),
+        ipld!({"R": {"l": {"other": 1}, ":>": {"@": {}}}}),
+        ipld!({"a": {">": {"@": {}}}}),
+        ipld!({"|": {}}),
+    ] {
+        assert!(matches!(
+            Selector::try_from(&value),
+            Err(IpldCoreError::InvalidSelector(_))
+        ));
+    }
+}
+
+#[test]
+fn test_walk_path() {
+    let (getter, root) = setup();
+    let selector = explore_children(Selector::ExploreFields {
+        fields: btreemap! { "name".into() => Selector::Matcher },
+    });
+    let selector = Selector::ExploreFields {
+        fields: btreemap! { "children".into() => Selector::ExploreIndex {
+            index: 0,
+            next: Box::new(selector),
+        }},
+    };
+    assert_eq!(
+        matched(&getter, &root, &selector),
+        vec![("children/0/children/0/name".to_string(), ipld!("c"))]
+    );
+
+    let mut visited = vec![];
+    walk(&getter, &root, &selector, |visit| {
+        assert_eq!(visit.reason == VisitReason::Match, visit.path.len() == 5);
+        visited.push((visit.path.join("/"), visit.block.clone()));
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(visited.len(), 6);
+    assert_eq!(visited[0], (String::new(), root.clone()));
+    // the value of the link is the linked node
+    let a = visited[2].1.clone();
+    assert_ne!(a, root);
+    assert_eq!(visited[3], ("children/0/children".to_string(), a));
+}
+
+#[test]
+fn test_walk_recursive() {
+    let (getter, root) = setup();
+    let names_at = |limit| {
+        matched(&getter, &root, &names(limit))
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names_at(RecursionLimit::Depth(1)), vec![ipld!("root")]);
+    assert_eq!(
+        names_at(RecursionLimit::Depth(2)),
+        vec![ipld!("root"), ipld!("a"), ipld!("b")]
+    );
+    assert_eq!(
+        names_at(RecursionLimit::None),
+        vec![ipld!("root"), ipld!("a"), ipld!("c"), ipld!("b")]
+    );
+    assert_eq!(
+        names_at(RecursionLimit::None),
+        names_at(RecursionLimit::Depth(3))
+    );
+
+    let range = Selector::ExploreFields {
+        fields: btreemap! { "children".into() => Selector::ExploreRange {
+            start: 1,
+            end: 5,
+            next: Box::new(Selector::Matcher),
+        }},
+    };
+    let matched = matched(&getter, &root, &range);
+    assert_eq!(matched.len(), 1);
+    assert_eq!(matched[0].0, "children/1");
+    assert_eq!(matched[0].1.get_path(&["name"]), Some(&ipld!("b")));
+}
+
+#[test]
+fn test_walk_errors() {
+    let (mut getter, _) = setup();
+    let missing = IpldNode::wrap_object(&ipld!(null), Code::Sha2_256.into())
+        .unwrap()
+        .cid()
+        .clone();
+    let root = getter.add(ipld!({"name": "root", "children": [missing]}));
+    assert!(matches!(
+        walk(&getter, &root, &names(RecursionLimit::None), |_| Ok(())),
+        Err(IpldCoreError::FormatErr(FormatError::NoSuchLink))
+    ));
+    // the missing block isn't loaded if it isn't explored
+    assert_eq!(
+        matched(&getter, &root, &names(RecursionLimit::Depth(1))),
+        vec![("name".to_string(), ipld!("root"))]
+    );
+
+    let mut count = 0;
+    let result = walk(&getter, &root, &Selector::Matcher, |_| {
+        count += 1;
+        Err(IpldCoreError::NonLink)
+    });
+    assert!(matches!(result, Err(IpldCoreError::NonLink)));
+    assert_eq!(count, 1);
+}
@@ -0,0 +1,200 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use cid::Cid;
+use maplit::btreemap;
+use matches::matches;
+
+use ipld_core::{ipld, IpldCoreError, IpldValue, SortedStr};
+
+fn cid() -> Cid {
+    Cid::try_from("QmRgutAxd8t7oGkSm4wmeuByG6M51wcTso6cubDdQtuEfL").unwrap()
+}
+
+#[test]
+fn test_macro() {
+    let name = String::from("foo");
+    let value = ipld!({
+        "name": name,
+        "size": 1 + 2,
+        "chunks": [null, -1, 1.5, [true, []], {}, cid()],
+        "data": &b"bytes"[..],
+        ("dyn".to_string() + "amic"): {"a": null, "b": [1, 2,],},
+    });
+    assert_eq!(
+        value,
+        IpldValue::Map(btreemap! {
+            "name".into() => IpldValue::String("foo".into()),
+            "size".into() => IpldValue::Integer(3),
+            "chunks".into() => IpldValue::List(vec![
+                IpldValue::Null,
+                IpldValue::Integer(-1),
+                IpldValue::Float(1.5),
+                IpldValue::List(vec![IpldValue::Bool(true), IpldValue::List(vec![])]),
+                IpldValue::Map(BTreeMap::new()),
+                IpldValue::Link(cid()),
+            ]),
+            "data".into() => IpldValue::Bytes(b"bytes".to_vec()),
+            "dynamic".into() => IpldValue::Map(btreemap! {
+                "a".into() => IpldValue::Null,
+                "b".into() => IpldValue::List(vec![IpldValue::Integer(1), IpldValue::Integer(2)]),
+            }),
+        })
+    );
+    assert_eq!(ipld!(null), IpldValue::Null);
+    assert_eq!(ipld!("a"), IpldValue::String("a".into()));
+    assert_eq!(ipld!([]), IpldValue::List(vec![]));
+}
+
+#[test]
+fn test_get_path() {
+    let value = ipld!({"a": [{"b": cid()}, 2], "c": "d"});
+    assert_eq!(value.get_path(&[]), Some(&value));
+    assert_eq!(
+        value.get_path(&["a", "0", "b"]),
+        Some(&IpldValue::Link(cid()))
+    );
+    assert_eq!(
+        value.get_path(&["a", "1"]).and_then(IpldValue::as_i64),
+        Some(2)
+    );
+    assert_eq!(
+        value.get_path(&["c"]).and_then(IpldValue::as_str),
+        Some("d")
+    );
+    assert_eq!(value.get_path(&["a", "2"]), None);
+    assert_eq!(value.get_path(&["a", "x"]), None);
+    assert_eq!(value.get_path(&["c", "0"]), None);
+    // the links aren't followed
+    assert_eq!(value.get_path(&["a", "0", "b", "x"]), None);
+
+    let mut value = value;
+    *value.get_path_mut(&["a", "1"]).unwrap() = ipld!("two");
+    assert_eq!(value.get_path(&["a", "1"]), Some(&ipld!("two")));
+}
+
+#[test]
+fn test_set_path() {
+    let mut value = ipld!({});
+    assert_eq!(value.set_path(&["a", "b", "c"], 1).unwrap(), None);
+    assert_eq!(value, ipld!({"a": {"b": {"c": 1}}}));
+    assert_eq!(
+        value.set_path(&["a", "b", "c"], "x").unwrap(),
+        Some(ipld!(1))
+    );
+
+    value.set_path(&["l"], vec![1, 2]).unwrap();
+    assert_eq!(value.set_path(&["l", "1"], 3).unwrap(), Some(ipld!(2)));
+    assert_eq!(value.set_path(&["l", "2"], 4).unwrap(), None);
+    assert_eq!(value.get_path(&["l"]), Some(&ipld!([1, 3, 4])));
+
+    // the null values are replaced by maps
+    value.set_path(&["n"], IpldValue::Null).unwrap();
+    value.set_path(&["n", "m"], true).unwrap();
+    assert_eq!(value.get_path(&["n"]), Some(&ipld!({"m": true})));
+
+    for path in &[&["l", "4"][..], &["l", "x", "y"], &["a", "b", "c", "d"]] {
+        assert!(matches!(
+            value.set_path(path, 0),
+            Err(IpldCoreError::InvalidPath(_))
+        ));
+    }
+
+    assert_eq!(
+        value.set_path(&[], 0).unwrap().map(|v| v.kind()),
+        Some("map")
+    );
+    assert_eq!(value, ipld!(0));
+}
+
+#[test]
+fn test_remove_path() {
+    let mut value = ipld!({"a": [1, {"b": 2}, 3], "c": 4});
+    assert_eq!(value.remove_path(&["a", "1", "b"]), Some(ipld!(2)));
+    assert_eq!(value.remove_path(&["a", "0"]), Some(ipld!(1)));
+    assert_eq!(value.remove_path(&["a", "5"]), None);
+    assert_eq!(value.remove_path(&["x", "y"]), None);
+    assert_eq!(value.remove_path(&[]), None);
+    assert_eq!(value.remove_path(&["c"]), Some(ipld!(4)));
+    assert_eq!(value, ipld!({"a": [{}, 3]}));
+}
+
+#[test]
+fn test_conversions() {
+    assert_eq!(
+        IpldValue::from(u64::max_value()).as_u64(),
+        Some(u64::max_value())
+    );
+    assert_eq!(IpldValue::from(u64::max_value()).as_i64(), None);
+    assert_eq!(IpldValue::from(-1i8).as_i64(), Some(-1));
+    assert_eq!(IpldValue::from(1.5f32).as_f64(), Some(1.5));
+    assert_eq!(IpldValue::from(1).as_f64(), None);
+    assert_eq!(IpldValue::from(cid()).as_link(), Some(&cid()));
+    assert_eq!(IpldValue::from(&b"ab"[..]).as_bytes(), Some(&b"ab"[..]));
+    assert_eq!(IpldValue::from(Some(true)).as_bool(), Some(true));
+    assert!(IpldValue::from(None::<bool>).is_null());
+    assert_eq!(
+        IpldValue::from(vec!["a", "b"]).as_list().map(Vec::len),
+        Some(2)
+    );
+
+    let mut map = HashMap::new();
+    map.insert("a".to_string(), 1u32);
+    let value = IpldValue::from(map);
+    assert_eq!(value.as_map().map(BTreeMap::len), Some(1));
+    let map: BTreeMap<SortedStr, IpldValue> = BTreeMap::try_from(value).unwrap();
+    assert_eq!(map[&SortedStr::from("a")], ipld!(1));
+
+    assert_eq!(u8::try_from(ipld!(255)).unwrap(), 255);
+    assert!(matches!(
+        u8::try_from(ipld!(256)),
+        Err(IpldCoreError::InvalidType { expected: "u8", .. })
+    ));
+    assert!(matches!(
+        i64::try_from(ipld!("1")),
+        Err(IpldCoreError::InvalidType {
+            expected: "i64",
+            found: "string"
+        })
+    ));
+    assert_eq!(String::try_from(ipld!("s")).unwrap(), "s");
+    assert_eq!(Vec::<u8>::try_from(ipld!(&[1u8][..])).unwrap(), vec![1]);
+    assert_eq!(
+        Vec::<IpldValue>::try_from(ipld!([1])).unwrap(),
+        vec![ipld!(1)]
+    );
+    assert_eq!(Cid::try_from(ipld!(cid())).unwrap(), cid());
+    assert!(bool::try_from(ipld!(null)).is_err());
+    assert!(f64::try_from(ipld!(1)).is_err());
+}
+
+#[test]
+fn test_links() {
+    let other = Cid::new_v1(cid().codec(), cid().hash().to_owned());
+    let value = ipld!({"a": [cid(), {"b": other.clone()}], "c": cid(), "d": [[]]});
+    assert_eq!(
+        value.links().collect::<Vec<_>>(),
+        vec![&cid(), &other, &cid()]
+    );
+    assert_eq!(ipld!(cid()).links().count(), 1);
+    assert_eq!(ipld!({"a": [1, {}], "b": "c"}).links().count(), 0);
+
+    // converts the links to CIDv1
+    let mapped = value
+        .map_links(|cid| Ok(Cid::new_v1(cid.codec(), cid.hash().to_owned())))
+        .unwrap();
+    assert_eq!(
+        mapped,
+        ipld!({"a": [other.clone(), {"b": other.clone()}], "c": other.clone(), "d": [[]]})
+    );
+
+    let mut count = 0;
+    let result = value.map_links(|_| {
+        count += 1;
+        Err(IpldCoreError::InvalidLink)
+    });
+    assert!(matches!(result, Err(IpldCoreError::InvalidLink)));
+    assert_eq!(count, 1);
+}
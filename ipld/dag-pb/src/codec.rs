@@ -0,0 +1,27 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use block_format::Block;
+use cid::Codec;
+
+use ipld_format::coding::{CodecPlugin, CodecRegistry};
+use ipld_format::FormatError;
+
+use crate::node::ProtoNode;
+
+/// Registers the codecs of this library in the registry:
+/// the `dag-pb` decoder and the `dag-pb` encoder of `ProtoNode`.
+///
+/// They are registered in the default registries without calling it.
+pub fn register_codecs(registry: &mut CodecRegistry) {
+    registry.register_decoder(Codec::DagProtobuf, |block| {
+        let node = ProtoNode::from_block(block).map_err(|e| FormatError::Other(Box::new(e)))?;
+        Ok(Box::new(node))
+    });
+    registry.register_encoder(Codec::DagProtobuf, |node: &ProtoNode| {
+        Ok(node.raw_data().to_vec())
+    });
+}
+
+inventory::submit! {
+    CodecPlugin::new(register_codecs)
+}
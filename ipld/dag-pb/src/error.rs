@@ -0,0 +1,27 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+/// Type alias to use this library's [`DagPbError`] type in a `Result`.
+pub type Result<T> = std::result::Result<T, DagPbError>;
+
+/// Errors generated from this library.
+#[derive(Debug, thiserror::Error)]
+pub enum DagPbError {
+    /// The protobuf encoding of the node is invalid.
+    #[error("invalid dag-pb protobuf: {0}")]
+    InvalidProtobuf(String),
+    /// The block is not a `dag-pb` block.
+    #[error("expected a dag-pb block, found codec: {0:?}")]
+    NotDagPb(cid::Codec),
+    /// No link has the name.
+    #[error("no link named: {0}")]
+    NoSuchLink(String),
+    /// The path is empty, there are no more links to resolve.
+    #[error("end of path, no more links to resolve")]
+    EndOfPath,
+    /// CID error.
+    #[error("cid error: {0}")]
+    CidErr(#[from] cid::Error),
+    /// Block format error.
+    #[error("block format error: {0}")]
+    BlockErr(#[from] block_format::BlockFormatError),
+}
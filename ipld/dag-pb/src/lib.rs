@@ -0,0 +1,15 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+//! The merkledag protobuf (`dag-pb`) format of IPLD in Rust.
+//! Port from the `ProtoNode` of the [Go implementation](https://github.com/ipfs/go-merkledag).
+
+#![deny(missing_docs)]
+
+mod codec;
+mod error;
+mod node;
+mod pb;
+
+pub use self::codec::register_codecs;
+pub use self::error::{DagPbError, Result};
+pub use self::node::ProtoNode;
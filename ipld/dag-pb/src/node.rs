@@ -0,0 +1,278 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use bytes::Bytes;
+use cid::{Cid, Codec};
+
+use block_format::{Block, BlockBuilder};
+use ipld_format::{FormatError, Link, Node, NodeGetter, NodeStat, Resolver};
+
+use crate::error::{DagPbError, Result};
+use crate::pb;
+
+/// `ProtoNode` represents a node of the merkledag protobuf (`dag-pb`) format,
+/// which has some data and a list of named links.
+///
+/// Unlike the Go implementation, the node is encoded eagerly on every change,
+/// so the encoding, the CID and the stat are always available by reference.
+/// The links are kept sorted by name, stably, as the canonical encoding requires.
+#[derive(Clone, Debug)]
+pub struct ProtoNode {
+    links: Vec<Link>,
+    data: Option<Bytes>,
+    builder: BlockBuilder,
+    encoded: Bytes,
+    cid: Cid,
+    stat: NodeStat,
+}
+
+impl Default for ProtoNode {
+    fn default() -> Self {
+        Self::new_with(vec![], None, BlockBuilder::new())
+    }
+}
+
+impl ProtoNode {
+    /// Creates a new `ProtoNode` without data and links, its CID is version 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `ProtoNode` with the data, its CID is version 0.
+    pub fn with_data(data: Bytes) -> Self {
+        Self::new_with(vec![], Some(data), BlockBuilder::new())
+    }
+
+    fn new_with(mut links: Vec<Link>, data: Option<Bytes>, builder: BlockBuilder) -> Self {
+        links.sort_by(|a, b| a.name.cmp(&b.name));
+        let encoded = Bytes::from(pb::encode(&links, data.as_deref()));
+        let cid = builder
+            .cid(&encoded)
+            .expect("the builder builds dag-pb CIDs, checked by `set_cid_builder`");
+        let stat = compute_stat(&cid, &links, data.as_deref(), &encoded);
+        ProtoNode {
+            links,
+            data,
+            builder,
+            encoded,
+            cid,
+            stat,
+        }
+    }
+
+    /// Re-encodes the node after a change.
+    fn refresh(&mut self) {
+        let links = std::mem::replace(&mut self.links, vec![]);
+        *self = Self::new_with(links, self.data.take(), self.builder.clone());
+    }
+
+    /// Decodes the protobuf encoded node, its CID is built with the default builder.
+    pub fn from_protobuf(encoded: Bytes) -> Result<Self> {
+        let builder = BlockBuilder::new();
+        let cid = builder.cid(&encoded)?;
+        Self::decode(encoded, builder, cid)
+    }
+
+    /// Decodes a `dag-pb` block into a `ProtoNode`, which keeps the CID of the block.
+    pub fn from_block<B: Block + ?Sized>(block: &B) -> Result<Self> {
+        let cid = block.cid();
+        if cid.codec() != Codec::DagProtobuf {
+            return Err(DagPbError::NotDagPb(cid.codec()));
+        }
+        let builder = BlockBuilder::new_v1(Codec::DagProtobuf, cid.hash().algorithm())
+            .with_version(cid.version());
+        Self::decode(block.to_bytes(), builder, cid.clone())
+    }
+
+    fn decode(encoded: Bytes, builder: BlockBuilder, cid: Cid) -> Result<Self> {
+        let (links, data) = pb::decode(&encoded)?;
+        let stat = compute_stat(&cid, &links, data.as_deref(), &encoded);
+        // the links are kept in the encoded order, so the encoding is kept as is.
+        Ok(ProtoNode {
+            links,
+            data,
+            builder,
+            encoded,
+            cid,
+            stat,
+        })
+    }
+
+    /// Returns a deep copy of the node, which can be modified independently.
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    /// Returns the data of the node, which is empty when the node has no data.
+    pub fn data(&self) -> &[u8] {
+        self.data.as_deref().unwrap_or_default()
+    }
+
+    /// Sets the data of the node.
+    pub fn set_data(&mut self, data: Bytes) {
+        self.data = Some(data);
+        self.refresh();
+    }
+
+    /// Returns the builder of the CID of the node.
+    pub fn cid_builder(&self) -> &BlockBuilder {
+        &self.builder
+    }
+
+    /// Sets the builder of the CID of the node, its codec is always `dag-pb`.
+    ///
+    /// The node is unchanged if the builder can't build the CID,
+    /// e.g. a version 0 builder with a hash function other than sha2-256.
+    pub fn set_cid_builder(&mut self, builder: BlockBuilder) -> Result<()> {
+        let builder = builder.with_codec(Codec::DagProtobuf);
+        let cid = builder.cid(&self.encoded)?;
+        self.stat.hash = stat_hash(&cid);
+        self.builder = builder;
+        self.cid = cid;
+        Ok(())
+    }
+
+    /// Replaces the links of the node.
+    pub fn set_links(&mut self, links: Vec<Link>) {
+        self.links = links;
+        self.refresh();
+    }
+
+    /// Adds a link named `name` to the node, its size is the cumulative size of the node.
+    pub fn add_node_link<T: Node>(&mut self, name: &str, node: &T) {
+        self.add_raw_link(
+            name,
+            Link {
+                name: String::new(),
+                size: node.size(),
+                cid: node.cid().clone(),
+            },
+        );
+    }
+
+    /// Adds a copy of the link, named `name`, to the node.
+    pub fn add_raw_link(&mut self, name: &str, mut link: Link) {
+        link.name = name.to_string();
+        self.links.push(link);
+        self.refresh();
+    }
+
+    /// Removes all the links named `name` from the node.
+    pub fn remove_node_link(&mut self, name: &str) -> Result<()> {
+        let len = self.links.len();
+        self.links.retain(|link| link.name != name);
+        if self.links.len() == len {
+            return Err(DagPbError::NoSuchLink(name.to_string()));
+        }
+        self.refresh();
+        Ok(())
+    }
+
+    /// Returns the first link named `name`.
+    pub fn get_node_link(&self, name: &str) -> Result<&Link> {
+        self.links
+            .iter()
+            .find(|link| link.name == name)
+            .ok_or_else(|| DagPbError::NoSuchLink(name.to_string()))
+    }
+
+    /// Gets the node linked by the first link named `name` from the `NodeGetter`.
+    pub fn get_linked_node<T: Node>(
+        &self,
+        ng: &impl NodeGetter<T>,
+        name: &str,
+    ) -> ipld_format::Result<T> {
+        let link = self
+            .get_node_link(name)
+            .map_err(|_| FormatError::NoSuchLink)?;
+        link.node(ng)
+    }
+
+    /// Returns a copy of the node in which the links named `name`, if any,
+    /// are replaced by a link to the passed node.
+    pub fn update_node_link<T: Node>(&self, name: &str, node: &T) -> Self {
+        let mut copy = self.copy();
+        let _ = copy.remove_node_link(name);
+        copy.add_node_link(name, node);
+        copy
+    }
+}
+
+fn stat_hash(cid: &Cid) -> multihash::Multihash {
+    multihash::Multihash::from_bytes(cid.hash().as_bytes().to_vec())
+        .expect("the hash of a CID is a valid multihash")
+}
+
+fn compute_stat(cid: &Cid, links: &[Link], data: Option<&[u8]>, encoded: &[u8]) -> NodeStat {
+    let data_size = data.map_or(0, <[u8]>::len);
+    let block_size = encoded.len();
+    NodeStat {
+        hash: stat_hash(cid),
+        num_links: links.len(),
+        block_size,
+        // includes the framing.
+        links_size: block_size - data_size,
+        data_size,
+        cumulative_size: links
+            .iter()
+            .fold(block_size as u64, |size, link| size + link.size)
+            as usize,
+    }
+}
+
+impl Node for ProtoNode {
+    fn resolve_link(&self, path: &[&str]) -> ipld_format::Result<(Link, Vec<String>)> {
+        let (first, rest) = path
+            .split_first()
+            .ok_or_else(|| FormatError::Other(Box::new(DagPbError::EndOfPath)))?;
+        let link = self
+            .get_node_link(first)
+            .map_err(|_| FormatError::NoSuchLink)?;
+        Ok((
+            link.clone(),
+            rest.iter().map(|s| (*s).to_string()).collect(),
+        ))
+    }
+
+    fn links(&self) -> Vec<&Link> {
+        self.links.iter().collect()
+    }
+
+    fn stat(&self) -> ipld_format::Result<&NodeStat> {
+        Ok(&self.stat)
+    }
+
+    /// Returns the cumulative size of the node, i.e. the size of its block
+    /// and the sizes of its links, like the Go implementation.
+    fn size(&self) -> u64 {
+        self.stat.cumulative_size as u64
+    }
+}
+
+impl Resolver for ProtoNode {
+    type Output = Link;
+
+    /// Resolves the first segment of the path as the name of a link.
+    fn resolve(&self, path: &[&str]) -> ipld_format::Result<(Self::Output, Vec<String>)> {
+        self.resolve_link(path)
+    }
+
+    /// Returns the names of the links, the paths within a `ProtoNode` have a single level.
+    fn tree(&self, path: &str, _depth: Option<usize>) -> Vec<String> {
+        if !path.is_empty() {
+            return vec![];
+        }
+        self.links.iter().map(|link| link.name.clone()).collect()
+    }
+}
+
+impl Block for ProtoNode {
+    fn raw_data(&self) -> &Bytes {
+        &self.encoded
+    }
+}
+
+impl AsRef<Cid> for ProtoNode {
+    fn as_ref(&self) -> &Cid {
+        &self.cid
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+//! The protobuf layout of the `dag-pb` nodes:
+//!
+//! ```protobuf
+//! message PBLink {
+//!   optional bytes Hash = 1;
+//!   optional string Name = 2;
+//!   optional uint64 Tsize = 3;
+//! }
+//!
+//! message PBNode {
+//!   repeated PBLink Links = 2;
+//!   optional bytes Data = 1;
+//! }
+//! ```
+//!
+//! The canonical encoding writes the links before the data, unlike the field order,
+//! so it's implemented by hand.
+
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use cid::Cid;
+
+use ipld_format::Link;
+
+use crate::error::{DagPbError, Result};
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_LEN: u64 = 2;
+
+/// Encodes the links, in order, and the data in the canonical `PBNode` layout.
+///
+/// The name and the size of the links are always written, like the Go implementation does.
+pub fn encode(links: &[Link], data: Option<&[u8]>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for link in links {
+        let hash = link.cid.to_bytes();
+        let mut pblink = Vec::with_capacity(hash.len() + link.name.len() + 16);
+        write_bytes(&mut pblink, 1, &hash);
+        write_bytes(&mut pblink, 2, link.name.as_bytes());
+        write_varint(&mut pblink, 3 << 3 | WIRE_VARINT);
+        write_varint(&mut pblink, link.size);
+        write_bytes(&mut buf, 2, &pblink);
+    }
+    if let Some(data) = data {
+        write_bytes(&mut buf, 1, data);
+    }
+    buf
+}
+
+/// Decodes a `PBNode` into its links, in the encoded order, and its data.
+pub fn decode(bytes: &Bytes) -> Result<(Vec<Link>, Option<Bytes>)> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let mut links = vec![];
+    let mut data = None;
+    while !reader.is_empty() {
+        match reader.read_key()? {
+            (1, WIRE_LEN) if data.is_none() => data = Some(reader.read_bytes()?),
+            (2, WIRE_LEN) => links.push(decode_link(&reader.read_bytes()?)?),
+            (field, wire) => return Err(unexpected_field("PBNode", field, wire)),
+        }
+    }
+    Ok((links, data))
+}
+
+fn decode_link(bytes: &Bytes) -> Result<Link> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let (mut cid, mut name, mut size) = (None, None, None);
+    while !reader.is_empty() {
+        match reader.read_key()? {
+            (1, WIRE_LEN) if cid.is_none() => cid = Some(Cid::try_from(&reader.read_bytes()?[..])?),
+            (2, WIRE_LEN) if name.is_none() => {
+                let bytes = reader.read_bytes()?;
+                let s = std::str::from_utf8(&bytes).map_err(|_| {
+                    DagPbError::InvalidProtobuf("the name of a link is not UTF-8".into())
+                })?;
+                name = Some(s.to_string());
+            }
+            (3, WIRE_VARINT) if size.is_none() => size = Some(reader.read_varint()?),
+            (field, wire) => return Err(unexpected_field("PBLink", field, wire)),
+        }
+    }
+    let cid =
+        cid.ok_or_else(|| DagPbError::InvalidProtobuf("the hash of a link is missing".into()))?;
+    Ok(Link {
+        name: name.unwrap_or_default(),
+        size: size.unwrap_or_default(),
+        cid,
+    })
+}
+
+fn unexpected_field(message: &str, field: u64, wire: u64) -> DagPbError {
+    DagPbError::InvalidProtobuf(format!(
+        "unexpected field {} with wire type {} in {}",
+        field, wire, message
+    ))
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(buf, field << 3 | WIRE_LEN);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    bytes: &'a Bytes,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| DagPbError::InvalidProtobuf("truncated varint".into()))?;
+            self.pos += 1;
+            if shift == 63 && byte > 1 {
+                break;
+            }
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(DagPbError::InvalidProtobuf("varint overflows u64".into()))
+    }
+
+    /// Reads the key of a field, returns the field number and the wire type.
+    fn read_key(&mut self) -> Result<(u64, u64)> {
+        let key = self.read_varint()?;
+        Ok((key >> 3, key & 0x7))
+    }
+
+    fn read_bytes(&mut self) -> Result<Bytes> {
+        let len = self.read_varint()? as usize;
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| DagPbError::InvalidProtobuf("truncated bytes".into()))?;
+        let bytes = self.bytes.slice(self.pos..end);
+        self.pos = end;
+        Ok(bytes)
+    }
+}
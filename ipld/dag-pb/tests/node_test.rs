@@ -0,0 +1,249 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use cid::{Cid, Codec, IntoExt};
+use matches::matches;
+use multihash::Code;
+
+use block_format::{BasicBlock, Block, BlockBuilder};
+use ipld_dag_pb::{DagPbError, ProtoNode};
+use ipld_format::coding::CodecRegistry;
+use ipld_format::{FormatError, Node, Resolver};
+
+/// The UnixFS data of an empty directory.
+const EMPTY_DIR: &[u8] = &[0x08, 0x01];
+
+/// The UnixFS file node of `hello world`, as added by go-ipfs.
+fn hello_world() -> ProtoNode {
+    let mut data = vec![0x08, 0x02, 0x12, 0x0b];
+    data.extend_from_slice(b"hello world");
+    data.extend_from_slice(&[0x18, 0x0b]);
+    ProtoNode::with_data(data.into())
+}
+
+#[test]
+fn test_known_cids() {
+    let node = ProtoNode::new();
+    assert!(node.raw_data().is_empty());
+    assert_eq!(
+        node.cid().to_string(),
+        "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n"
+    );
+
+    let mut dir = ProtoNode::with_data(Bytes::from_static(EMPTY_DIR));
+    assert_eq!(dir.raw_data(), &[0x0a, 0x02, 0x08, 0x01][..]);
+    assert_eq!(
+        dir.cid().to_string(),
+        "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn"
+    );
+    dir.set_cid_builder(BlockBuilder::new_v1(Codec::Raw, Code::Sha2_256.into()))
+        .unwrap();
+    assert_eq!(dir.cid().codec(), Codec::DagProtobuf);
+    assert_eq!(
+        dir.cid().to_string(),
+        "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354"
+    );
+    // a CIDv0 is always hashed with sha2-256
+    let r = dir.set_cid_builder(BlockBuilder::new().with_hash(Code::Blake2b256.into()));
+    assert!(matches!(r, Err(DagPbError::BlockErr(_))));
+    assert_eq!(
+        dir.cid().to_string(),
+        "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354"
+    );
+
+    let file = hello_world();
+    assert_eq!(
+        file.cid().to_string(),
+        "Qmf412jQZiuVUtdgnB36FXFX7xg5V6KEbSJ4dpQuhkLyfD"
+    );
+    assert_eq!(file.size(), 19);
+}
+
+#[test]
+fn test_links_and_roundtrip() {
+    let file = hello_world();
+    let mut dir = ProtoNode::with_data(Bytes::from_static(EMPTY_DIR));
+    dir.add_node_link("hello.txt", &file);
+    assert_eq!(
+        hex::encode(dir.raw_data()),
+        "12310a221220f852c7fa62f971817f54d8a80dcd63fcf7098b3cbde9ae8ec1ee449013ec5db0\
+         120968656c6c6f2e74787418130a020801"
+    );
+    assert_eq!(
+        dir.cid().to_string(),
+        "QmNxvA5bwvPGgMXbmtyhxA1cKFdvQXnsGnZLCGor3AzYxJ"
+    );
+    assert_eq!(dir.size(), dir.raw_data().len() as u64 + 19);
+
+    let stat = dir.stat().unwrap();
+    assert_eq!(stat.num_links, 1);
+    assert_eq!(stat.block_size, 55);
+    assert_eq!(stat.links_size, 55 - EMPTY_DIR.len());
+    assert_eq!(stat.data_size, EMPTY_DIR.len());
+    assert_eq!(stat.cumulative_size, 55 + 19);
+    assert_eq!(stat.hash.as_bytes(), dir.cid().hash().as_bytes());
+
+    let block = BasicBlock::new_with_cid(dir.to_bytes(), dir.cid().clone()).unwrap();
+    let decoded = ProtoNode::from_block(&block).unwrap();
+    assert_eq!(decoded.cid(), dir.cid());
+    assert_eq!(decoded.raw_data(), dir.raw_data());
+    assert_eq!(decoded.data(), EMPTY_DIR);
+    assert_eq!(decoded.links(), dir.links());
+    assert_eq!(decoded.links()[0].name, "hello.txt");
+    assert_eq!(decoded.links()[0].size, 19);
+    assert_eq!(decoded.links()[0].cid, *file.cid());
+    assert_eq!(decoded.stat().unwrap(), dir.stat().unwrap());
+
+    let decoded = ProtoNode::from_protobuf(dir.to_bytes()).unwrap();
+    assert_eq!(decoded.cid(), dir.cid());
+}
+
+#[test]
+fn test_link_sorting() {
+    let (a, b) = (
+        ProtoNode::with_data("a".into()),
+        ProtoNode::with_data("b".into()),
+    );
+    let mut sorted = ProtoNode::new();
+    sorted.add_node_link("a", &a);
+    sorted.add_node_link("b", &b);
+    let mut unsorted = ProtoNode::new();
+    unsorted.add_node_link("b", &b);
+    unsorted.add_node_link("a", &a);
+    assert_eq!(unsorted.raw_data(), sorted.raw_data());
+    assert_eq!(unsorted.cid(), sorted.cid());
+    let names = unsorted.tree("", None);
+    assert_eq!(names, vec!["a", "b"]);
+    assert!(unsorted.tree("a", None).is_empty());
+
+    // links with the same name keep their order
+    unsorted.add_node_link("a", &b);
+    let cids = unsorted
+        .links()
+        .into_iter()
+        .map(|link| link.cid.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        cids,
+        vec![a.cid().clone(), b.cid().clone(), b.cid().clone()]
+    );
+}
+
+#[test]
+fn test_modify_links() {
+    let (a, b) = (
+        ProtoNode::with_data("a".into()),
+        ProtoNode::with_data("b".into()),
+    );
+    let mut node = ProtoNode::new();
+    node.add_node_link("a", &a);
+    node.add_node_link("b", &b);
+    let original = node.copy();
+
+    assert_eq!(node.get_node_link("a").unwrap().cid, *a.cid());
+    assert!(matches!(
+        node.get_node_link("c"),
+        Err(DagPbError::NoSuchLink(ref name)) if name == "c"
+    ));
+
+    node.remove_node_link("a").unwrap();
+    assert_eq!(node.links().len(), 1);
+    assert_ne!(node.cid(), original.cid());
+    assert!(matches!(
+        node.remove_node_link("a"),
+        Err(DagPbError::NoSuchLink(_))
+    ));
+    // the copy is unchanged
+    assert_eq!(original.links().len(), 2);
+
+    let updated = original.update_node_link("a", &b);
+    assert_eq!(updated.get_node_link("a").unwrap().cid, *b.cid());
+    assert_eq!(original.get_node_link("a").unwrap().cid, *a.cid());
+
+    let mut data = ProtoNode::new();
+    data.set_data("data".into());
+    assert_eq!(data.data(), b"data");
+    assert_eq!(data.cid(), ProtoNode::with_data("data".into()).cid());
+
+    node.set_links(vec![]);
+    assert_eq!(node.cid(), ProtoNode::new().cid());
+}
+
+#[test]
+fn test_resolve() {
+    let file = hello_world();
+    let mut dir = ProtoNode::new();
+    dir.add_node_link("hello.txt", &file);
+
+    let (link, rest) = dir.resolve_link(&["hello.txt", "x"]).unwrap();
+    assert_eq!(link.cid, *file.cid());
+    assert_eq!(rest, vec!["x"]);
+    let (link, rest) = dir.resolve(&["hello.txt"]).unwrap();
+    assert_eq!(link.cid, *file.cid());
+    assert!(rest.is_empty());
+    assert!(matches!(
+        dir.resolve_link(&["missing"]),
+        Err(FormatError::NoSuchLink)
+    ));
+    assert!(dir.resolve_link(&[]).is_err());
+}
+
+#[test]
+fn test_decode_errors() {
+    let invalid: &[&[u8]] = &[
+        // truncated data
+        &[0x0a, 0x05, 0x01],
+        // unknown field
+        &[0x1a, 0x00],
+        // data with varint wire type
+        &[0x08, 0x01],
+        // twice the data
+        &[0x0a, 0x00, 0x0a, 0x00],
+        // link without hash
+        &[0x12, 0x02, 0x18, 0x00],
+        // link with an invalid hash
+        &[0x12, 0x03, 0x0a, 0x01, 0x00],
+        // truncated varint
+        &[0x12, 0x80],
+    ];
+    for bytes in invalid {
+        assert!(
+            ProtoNode::from_protobuf(Bytes::from_static(bytes)).is_err(),
+            "{:?} should be invalid",
+            bytes
+        );
+    }
+
+    // the data before the links is decoded as is
+    let file = hello_world();
+    let mut encoded = vec![0x0a, 0x00, 0x12, 0x24, 0x0a, 0x22];
+    encoded.extend_from_slice(&file.cid().to_bytes());
+    let node = ProtoNode::from_protobuf(encoded.clone().into()).unwrap();
+    assert_eq!(node.raw_data(), &encoded[..]);
+    assert_eq!(node.links()[0].cid, *file.cid());
+    assert_eq!(node.links()[0].name, "");
+
+    let cid = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(b"").into_ext());
+    let block = BasicBlock::new_with_cid(Bytes::new(), cid).unwrap();
+    assert!(matches!(
+        ProtoNode::from_block(&block),
+        Err(DagPbError::NotDagPb(Codec::Raw))
+    ));
+}
+
+#[test]
+fn test_registry() {
+    let registry = CodecRegistry::with_defaults();
+    assert!(registry.has_decoder(Codec::DagProtobuf));
+
+    let file = hello_world();
+    let encoded = registry.encode(Codec::DagProtobuf, &file).unwrap();
+    assert_eq!(file.raw_data(), &encoded);
+    let cid = Cid::try_from("Qmf412jQZiuVUtdgnB36FXFX7xg5V6KEbSJ4dpQuhkLyfD").unwrap();
+    let block = BasicBlock::new_with_cid(encoded.into(), cid.clone()).unwrap();
+    let node = registry.decode(&block).unwrap();
+    assert_eq!(node.cid(), &cid);
+    assert_eq!(node.size(), 19);
+}
@@ -1,47 +1,218 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
-use std::collections::HashMap;
+use std::any::{Any, TypeId};
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 use std::sync::RwLock;
 
 use block_format::Block;
-use cid::Codec;
+use bytes::Bytes;
+use cid::{Cid, Codec};
 
 use crate::error::{FormatError, Result};
 use crate::format::Node;
+use crate::raw::RawNode;
 
 lazy_static::lazy_static! {
-    static ref BLOCK_DECODERS: RwLock<HashMap<Codec, Arc<DecodeBlockFunc>>> = RwLock::new(HashMap::new());
+    static ref GLOBAL_REGISTRY: RwLock<Arc<CodecRegistry>> =
+        RwLock::new(Arc::new(CodecRegistry::with_defaults()));
 }
 
-type DecodeBlockFunc = dyn Fn(&dyn Block) -> Result<Box<dyn Node>> + Send + Sync;
+/// The function decoding a block into a node.
+pub type DecodeBlockFunc = dyn Fn(&dyn Block) -> Result<Box<dyn Node>> + Send + Sync;
 
-/// Register decoder for all blocks with the passed codec.
+/// The function encoding a value of the data model `V` into the bytes of a block.
+pub type EncodeFunc<V> = dyn Fn(&V) -> Result<Vec<u8>> + Send + Sync;
+
+/// A codec implementation registered in the default registries by the crate implementing it,
+/// in the same way as the `init` registration of the Go implementation.
+///
+/// ```ignore
+/// inventory::submit! {
+///     ipld_format::coding::CodecPlugin::new(|registry| {
+///         registry.register_decoder(Codec::DagCBOR, decode_block);
+///     })
+/// }
+/// ```
+pub struct CodecPlugin {
+    register: fn(&mut CodecRegistry),
+}
+
+impl CodecPlugin {
+    /// Creates a new plugin registering its codecs with the `register` function.
+    pub fn new(register: fn(&mut CodecRegistry)) -> Self {
+        CodecPlugin { register }
+    }
+}
+
+inventory::collect!(CodecPlugin);
+
+/// CodecRegistry maps the codecs to their decoders, which decode blocks into nodes,
+/// and to their encoders, which encode values of a data model into blocks.
+///
+/// Encoders are registered per data model type, e.g. the `IpldValue` of `ipld-core`,
+/// so a codec can have encoders for several data models.
+#[derive(Clone, Default)]
+pub struct CodecRegistry {
+    decoders: HashMap<Codec, Arc<DecodeBlockFunc>>,
+    encoders: HashMap<(Codec, TypeId), Arc<dyn Any + Send + Sync>>,
+}
+
+impl CodecRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with the `raw` codec and the codecs of the registered `CodecPlugin`s,
+    /// which is the content of the global registry by default.
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register_decoder(Codec::Raw, |block| {
+            Ok(Box::new(RawNode::from_block(block)?))
+        });
+        registry.register_encoder(Codec::Raw, |data: &Bytes| Ok(data.to_vec()));
+        for plugin in inventory::iter::<CodecPlugin> {
+            (plugin.register)(&mut registry);
+        }
+        registry
+    }
+
+    /// Registers the decoder for all blocks with the passed codec.
+    ///
+    /// This will silently replace any existing registered block decoder.
+    pub fn register_decoder<F>(&mut self, codec: Codec, decoder: F)
+    where
+        F: Fn(&dyn Block) -> Result<Box<dyn Node>> + Send + Sync + 'static,
+    {
+        self.decoders.insert(codec, Arc::new(decoder));
+    }
+
+    /// Registers the encoder of the values of the data model `V` with the passed codec.
+    ///
+    /// This will silently replace any existing registered encoder of `V`.
+    pub fn register_encoder<V, F>(&mut self, codec: Codec, encoder: F)
+    where
+        V: 'static,
+        F: Fn(&V) -> Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        let encoder: Arc<EncodeFunc<V>> = Arc::new(encoder);
+        self.encoders
+            .insert((codec, TypeId::of::<V>()), Arc::new(encoder));
+    }
+
+    /// Returns true if a decoder is registered for the codec.
+    pub fn has_decoder(&self, codec: Codec) -> bool {
+        self.decoders.contains_key(&codec)
+    }
+
+    /// Returns true if an encoder of the data model `V` is registered for the codec.
+    pub fn has_encoder<V: 'static>(&self, codec: Codec) -> bool {
+        self.encoders.contains_key(&(codec, TypeId::of::<V>()))
+    }
+
+    /// Returns the codecs having a decoder or an encoder, ordered by their multicodec code.
+    pub fn codecs(&self) -> Vec<Codec> {
+        let codecs = self
+            .decoders
+            .keys()
+            .chain(self.encoders.keys().map(|(codec, _)| codec))
+            .map(|codec| (u64::from(*codec), *codec))
+            .collect::<BTreeSet<_>>();
+        codecs.into_iter().map(|(_, codec)| codec).collect()
+    }
+
+    /// Decodes the block into a node with the decoder corresponding to the codec of its CID.
+    pub fn decode(&self, block: &dyn Block) -> Result<Box<dyn Node>> {
+        let codec = block.cid().codec();
+        let decoder = self
+            .decoders
+            .get(&codec)
+            .ok_or(FormatError::DecoderNotRegister(codec))?;
+        decoder(block)
+    }
+
+    /// Decodes the data of the block with given CID into a node, without copying the data
+    /// into a block first, e.g. for the data read with `Blockstore::get_bytes`.
+    ///
+    /// The data is not verified against the CID, this is left to the blockstore.
+    pub fn decode_bytes(&self, cid: &Cid, data: Bytes) -> Result<Box<dyn Node>> {
+        self.decode(&BytesBlock { cid, data })
+    }
+
+    /// Encodes the value of the data model `V` with the encoder registered for the codec.
+    pub fn encode<V: 'static>(&self, codec: Codec, value: &V) -> Result<Vec<u8>> {
+        let encoder = self
+            .encoders
+            .get(&(codec, TypeId::of::<V>()))
+            .and_then(|encoder| encoder.downcast_ref::<Arc<EncodeFunc<V>>>())
+            .ok_or(FormatError::EncoderNotRegister(codec))?;
+        encoder(value)
+    }
+}
+
+/// Returns the global registry, used by `decode` and `encode`.
+pub fn global() -> Arc<CodecRegistry> {
+    GLOBAL_REGISTRY
+        .read()
+        .expect("get instance read lock failed")
+        .clone()
+}
+
+/// Replaces the global registry.
+pub fn set_global(registry: CodecRegistry) {
+    let mut global = GLOBAL_REGISTRY
+        .write()
+        .expect("get instance write lock failed");
+    *global = Arc::new(registry);
+}
+
+/// Register decoder for all blocks with the passed codec in the global registry.
 ///
 /// This will silently replace any existing registered block decoders.
 pub fn register<F>(codec: Codec, decoder: F)
 where
     F: Fn(&dyn Block) -> Result<Box<dyn Node>> + Send + Sync + 'static,
 {
-    let mut block_decoders = BLOCK_DECODERS
+    let mut global = GLOBAL_REGISTRY
         .write()
         .expect("get instance write lock failed");
-    block_decoders.insert(codec, Arc::new(decoder));
+    Arc::make_mut(&mut global).register_decoder(codec, decoder);
 }
 
-/// Decode block into node with the decode function corresponding to the codec of the block's CID.
+/// Decode block into node with the decode function corresponding to the codec of the block's CID,
+/// in the global registry.
 pub fn decode(block: &impl Block) -> Result<Box<dyn Node>> {
-    let codec = block.cid().codec();
-    let decoder_func = {
-        // just get lock and release, let decode function could be parallel
-        let block_decoders = BLOCK_DECODERS
-            .read()
-            .expect("get instance read lock failed");
-        // get a copy of arc pointer
-        block_decoders
-            .get(&codec)
-            .ok_or(FormatError::DecoderNotRegister(codec))?
-            .clone()
-    };
-    decoder_func(block)
+    // just get a copy of the registry and release the lock, let decode function could be parallel
+    global().decode(block)
+}
+
+/// Decode the data of the block with given CID into node, see `CodecRegistry::decode_bytes`,
+/// in the global registry.
+pub fn decode_bytes(cid: &Cid, data: Bytes) -> Result<Box<dyn Node>> {
+    global().decode_bytes(cid, data)
+}
+
+/// Encodes the value of the data model `V` with the encoder registered for the codec,
+/// in the global registry.
+pub fn encode<V: 'static>(codec: Codec, value: &V) -> Result<Vec<u8>> {
+    global().encode(codec, value)
+}
+
+/// The block made of the CID and the data read from a blockstore, which are trusted.
+struct BytesBlock<'a> {
+    cid: &'a Cid,
+    data: Bytes,
+}
+
+impl<'a> Block for BytesBlock<'a> {
+    fn raw_data(&self) -> &Bytes {
+        &self.data
+    }
+}
+
+impl<'a> AsRef<Cid> for BytesBlock<'a> {
+    fn as_ref(&self) -> &Cid {
+        self.cid
+    }
 }
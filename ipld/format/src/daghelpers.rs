@@ -1 +1,295 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use cid::Cid;
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use futures::{FutureExt, StreamExt};
+
+use crate::error::{FormatError, Result};
+use crate::format::{Link, Node, NodeStat};
+use crate::merkledag::{LinkGetter, NodeAdder, NodeGetter};
+
+/// The default number of concurrent fetches of `fetch_graph` and `enumerate_children_async`.
+pub const DEFAULT_CONCURRENCY: usize = 32;
+
+/// Gets the nodes of the given CIDs with a single batch of the `NodeGetter`,
+/// returns the results in the order of the CIDs.
+pub fn get_nodes<T, NG>(ng: &NG, cids: &[Cid]) -> Vec<Result<T>>
+where
+    T: Node,
+    NG: NodeGetter<T> + Sync,
+{
+    let mut nodes = block_on(ng.get_many(cids).take(cids.len()).collect::<Vec<_>>());
+    // the getter may end the stream early.
+    for cid in &cids[nodes.len()..] {
+        nodes.push(Err(FormatError::FetchFailed(cid.clone())));
+    }
+    nodes
+}
+
+/// Gets the children of the given node with a single batch of the `NodeGetter`,
+/// returns the results in the order of the links.
+pub fn get_dag<T, NG>(ng: &NG, root: &T) -> Vec<Result<T>>
+where
+    T: Node,
+    NG: NodeGetter<T> + Sync,
+{
+    let cids = root
+        .links()
+        .into_iter()
+        .map(|link| link.cid.clone())
+        .collect::<Vec<_>>();
+    get_nodes(ng, &cids)
+}
+
+/// Copies the DAG of the given root from a DAG service to another,
+/// the nodes shared by several parents are copied once.
+pub fn copy<T, F, D>(from: &F, to: &D, root: &Cid) -> Result<()>
+where
+    T: Node,
+    F: NodeGetter<T> + Sync,
+    D: NodeAdder<T>,
+{
+    let mut seen = HashSet::new();
+    seen.insert(root.clone());
+    let mut stack = vec![from.get(root)?];
+    while let Some(node) = stack.pop() {
+        to.add(&node)?;
+        let cids = node
+            .links()
+            .into_iter()
+            .map(|link| link.cid.clone())
+            .filter(|cid| seen.insert(cid.clone()))
+            .collect::<Vec<_>>();
+        for child in get_nodes(from, &cids) {
+            stack.push(child?);
+        }
+    }
+    Ok(())
+}
+
+/// Walks the DAG of the given root in depth-first pre-order, calling `visit` once
+/// for every distinct CID. The children of a CID are walked only if `visit` returns true.
+pub fn walk<T, LG, F>(lg: &LG, root: &Cid, mut visit: F) -> Result<()>
+where
+    T: Node,
+    LG: LinkGetter<T>,
+    F: FnMut(&Cid) -> bool,
+{
+    let mut seen = HashSet::new();
+    let mut stack = vec![root.clone()];
+    while let Some(cid) = stack.pop() {
+        if !seen.insert(cid.clone()) || !visit(&cid) {
+            continue;
+        }
+        let links = lg.get_links(&cid)?;
+        stack.extend(links.into_iter().rev().map(|link| link.cid));
+    }
+    Ok(())
+}
+
+/// The state shared by the workers of `walk_parallel`.
+struct WalkState {
+    queue: Vec<Cid>,
+    seen: HashSet<Cid>,
+    /// The number of CIDs being visited.
+    active: usize,
+    error: Option<FormatError>,
+}
+
+/// Walks the DAG of the given root like `walk`, with up to `concurrency` CIDs being
+/// visited at the same time, so the CIDs are visited in no particular order.
+///
+/// The walk stops at the first error, which is returned.
+pub fn walk_parallel<T, LG, F>(lg: Arc<LG>, root: &Cid, concurrency: usize, visit: F) -> Result<()>
+where
+    T: Node + 'static,
+    LG: LinkGetter<T> + Send + Sync + 'static,
+    F: Fn(&Cid) -> bool + Send + Sync + 'static,
+{
+    let mut seen = HashSet::new();
+    seen.insert(root.clone());
+    let state = WalkState {
+        queue: vec![root.clone()],
+        seen,
+        active: 0,
+        error: None,
+    };
+    let shared = Arc::new((Mutex::new(state), Condvar::new()));
+    let visit = Arc::new(visit);
+    let workers = (0..concurrency.max(1))
+        .map(|_| {
+            let (lg, visit, shared) = (lg.clone(), visit.clone(), shared.clone());
+            thread::spawn(move || walk_worker::<T, _, _>(&*lg, &*visit, &shared))
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        worker.join().expect("walk worker panicked");
+    }
+    let mut state = shared.0.lock().expect("get lock failed");
+    match state.error.take() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn walk_worker<T, LG, F>(lg: &LG, visit: &F, shared: &(Mutex<WalkState>, Condvar))
+where
+    T: Node,
+    LG: LinkGetter<T>,
+    F: Fn(&Cid) -> bool,
+{
+    let (lock, cvar) = shared;
+    loop {
+        let cid = {
+            let mut state = lock.lock().expect("get lock failed");
+            loop {
+                if state.error.is_some() {
+                    return;
+                }
+                if let Some(cid) = state.queue.pop() {
+                    state.active += 1;
+                    break cid;
+                }
+                // nothing to visit and nothing being visited, the walk is over.
+                if state.active == 0 {
+                    return;
+                }
+                state = cvar.wait(state).expect("get lock failed");
+            }
+        };
+        let result = if visit(&cid) {
+            lg.get_links(&cid)
+        } else {
+            Ok(vec![])
+        };
+        let mut state = lock.lock().expect("get lock failed");
+        state.active -= 1;
+        match result {
+            Ok(links) => {
+                for link in links {
+                    if state.seen.insert(link.cid.clone()) {
+                        state.queue.push(link.cid);
+                    }
+                }
+            }
+            Err(e) => {
+                if state.error.is_none() {
+                    state.error = Some(e);
+                }
+            }
+        }
+        cvar.notify_all();
+    }
+}
+
+/// A `LinkGetter` getting every node, including the `raw` ones,
+/// so that all the nodes of the DAG are fetched.
+struct GetLinksDirect<NG>(Arc<NG>);
+
+impl<T: Node, NG: NodeGetter<T>> NodeGetter<T> for GetLinksDirect<NG> {
+    fn get(&self, cid: &Cid) -> Result<T> {
+        self.0.get(cid)
+    }
+}
+
+impl<T: Node, NG: NodeGetter<T>> LinkGetter<T> for GetLinksDirect<NG> {
+    fn get_links(&self, cid: &Cid) -> Result<Vec<Link>> {
+        let node = self.0.get(cid)?;
+        Ok(node.links().into_iter().cloned().collect())
+    }
+}
+
+/// Fetches every node of the DAG of the given root,
+/// e.g. to make them available locally when the `NodeGetter` fetches them from the network.
+pub fn fetch_graph<T, NG>(ng: Arc<NG>, root: &Cid) -> Result<()>
+where
+    T: Node + 'static,
+    NG: NodeGetter<T> + Send + Sync + 'static,
+{
+    let lg = Arc::new(GetLinksDirect(ng));
+    walk_parallel::<T, _, _>(lg, root, DEFAULT_CONCURRENCY, |_| true)
+}
+
+/// Walks the DAG of the given root like `walk_parallel` with `DEFAULT_CONCURRENCY`,
+/// in a background thread. The returned future resolves when the walk is over.
+pub fn enumerate_children_async<T, LG, F>(
+    lg: Arc<LG>,
+    root: Cid,
+    visit: F,
+) -> impl Future<Output = Result<()>>
+where
+    T: Node + 'static,
+    LG: LinkGetter<T> + Send + Sync + 'static,
+    F: Fn(&Cid) -> bool + Send + Sync + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = tx.send(walk_parallel::<T, _, _>(
+            lg,
+            &root,
+            DEFAULT_CONCURRENCY,
+            visit,
+        ));
+    });
+    rx.map(|result| result.unwrap_or_else(|e| Err(FormatError::Other(Box::new(e)))))
+}
+
+/// Computes the cumulative size of the DAG of the given root, i.e. the size of its block
+/// and the cumulative sizes of its children, the way `ipfs object stat` reports it.
+///
+/// A sub-DAG linked several times counts each time, but it's fetched once.
+pub fn cumulative_size<T, NG>(ng: &NG, root: &Cid) -> Result<usize>
+where
+    T: Node,
+    NG: NodeGetter<T>,
+{
+    let mut sizes = HashMap::<Cid, usize>::new();
+    // (cid, the children are pushed), the children are summed up after them.
+    let mut stack = vec![(root.clone(), None)];
+    while let Some((cid, node)) = stack.pop() {
+        if sizes.contains_key(&cid) {
+            continue;
+        }
+        match node {
+            None => {
+                let node: T = ng.get(&cid)?;
+                let children = node
+                    .links()
+                    .into_iter()
+                    .map(|link| link.cid.clone())
+                    .collect::<Vec<_>>();
+                let block_size = node.size() as usize;
+                stack.push((cid, Some((block_size, children.clone()))));
+                for child in children {
+                    if !sizes.contains_key(&child) {
+                        stack.push((child, None));
+                    }
+                }
+            }
+            Some((block_size, children)) => {
+                let size = children.iter().fold(block_size, |size, child| {
+                    size + sizes.get(child).expect("the children are computed first")
+                });
+                sizes.insert(cid, size);
+            }
+        }
+    }
+    Ok(sizes[root])
+}
+
+/// Returns the `NodeStat` of the given root, with the cumulative size of its DAG.
+pub fn stat<T, NG>(ng: &NG, root: &Cid) -> Result<NodeStat>
+where
+    T: Node,
+    NG: NodeGetter<T>,
+{
+    let mut stat = ng.get(root)?.stat()?.clone();
+    stat.cumulative_size = cumulative_size(ng, root)?;
+    Ok(stat)
+}
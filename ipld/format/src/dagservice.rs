@@ -0,0 +1,92 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::sync::RwLock;
+
+use block_format::{BasicBlock, Block};
+use cid::Cid;
+use ipfs_blockstore::Blockstore;
+
+use crate::coding;
+use crate::error::Result;
+use crate::format::Node;
+use crate::merkledag::{DAGService, LinkGetter, NodeAdder, NodeGetter};
+
+/// BlockstoreDagService is a `DAGService` on top of a `Blockstore`.
+///
+/// The nodes are stored as their encoded blocks,
+/// and decoded with the decoder registered in `coding` for the codec of their CID.
+pub struct BlockstoreDagService<BS: Blockstore> {
+    bs: RwLock<BS>,
+}
+
+impl<BS: Blockstore> BlockstoreDagService<BS> {
+    /// Creates a new `BlockstoreDagService` on top of the blockstore.
+    pub fn new(bs: BS) -> Self {
+        BlockstoreDagService {
+            bs: RwLock::new(bs),
+        }
+    }
+
+    /// Consumes the DAG service, returning the underlying blockstore.
+    pub fn into_inner(self) -> BS {
+        self.bs.into_inner().expect("get instance lock failed")
+    }
+}
+
+fn to_block<T: Node>(node: &T) -> Result<BasicBlock> {
+    Ok(BasicBlock::new_with_cid(
+        node.to_bytes(),
+        node.cid().clone(),
+    )?)
+}
+
+impl<BS: Blockstore> NodeGetter<Box<dyn Node>> for BlockstoreDagService<BS> {
+    fn get(&self, cid: &Cid) -> Result<Box<dyn Node>> {
+        let data = self
+            .bs
+            .read()
+            .expect("get instance read lock failed")
+            .get_bytes(cid)?;
+        coding::decode_bytes(cid, data)
+    }
+}
+
+impl<BS: Blockstore> LinkGetter<Box<dyn Node>> for BlockstoreDagService<BS> {}
+
+impl<BS: Blockstore> NodeAdder<Box<dyn Node>> for BlockstoreDagService<BS> {
+    fn add(&self, node: &Box<dyn Node>) -> Result<()> {
+        let block = to_block(node)?;
+        self.bs
+            .write()
+            .expect("get instance write lock failed")
+            .put(block)?;
+        Ok(())
+    }
+
+    fn add_many(&self, nodes: &[Box<dyn Node>]) -> Result<()> {
+        let blocks = nodes.iter().map(to_block).collect::<Result<Vec<_>>>()?;
+        self.bs
+            .write()
+            .expect("get instance write lock failed")
+            .put_many(&blocks)?;
+        Ok(())
+    }
+}
+
+impl<BS: Blockstore> DAGService<Box<dyn Node>> for BlockstoreDagService<BS> {
+    fn remove(&self, cid: &Cid) -> Result<()> {
+        self.bs
+            .read()
+            .expect("get instance read lock failed")
+            .delete_block(cid)?;
+        Ok(())
+    }
+
+    fn remove_many(&self, cids: &[Cid]) -> Result<()> {
+        self.bs
+            .read()
+            .expect("get instance read lock failed")
+            .delete_many(cids)?;
+        Ok(())
+    }
+}
@@ -15,6 +15,12 @@ pub enum FormatError {
     /// Cannot find the decoder corresponding to codec.
     #[error("this code has not register decoder: {0:?}")]
     DecoderNotRegister(cid::Codec),
+    /// Cannot find the encoder corresponding to codec.
+    #[error("this code has not register encoder: {0:?}")]
+    EncoderNotRegister(cid::Codec),
+    /// Raw nodes can only be built from blocks of the `raw` codec.
+    #[error("raw nodes cannot be decoded from non-raw blocks: {0:?}")]
+    NotRawBlock(cid::Codec),
     /// More than the depth of path.
     #[error("depth is larger than path, depth: {0}, path len: {1}")]
     DepthError(usize, usize),
@@ -22,7 +28,7 @@ pub enum FormatError {
     #[error("depth is not init yet")]
     DepthNotInit,
     /// Cannot go down, no child.
-    #[error("can't go down, the child does not exist, depth: {0}, index: {1}, child: {0}")]
+    #[error("can't go down, the child does not exist, depth: {0}, index: {1}, child: {2}")]
     DownNoChild(usize, usize, usize),
     /// Cannot go up, already on root.
     #[error("can't go up, already on root")]
@@ -30,12 +36,50 @@ pub enum FormatError {
     /// No more child nodes.
     #[error("can't go to the next child, no more child nodes in this parent")]
     NextNoChild,
+    /// The walker reached the end of the DAG.
+    #[error("end of DAG")]
+    EndOfDag,
     /// No child exist at the index.
     #[error("child not exist for this index. index: {0}")]
     NoChild(usize),
     /// Link not found.
     #[error("no such link found")]
     NoSuchLink,
+    /// The path resolves to a value that isn't a link.
+    #[error("the path resolves to a value that isn't a link")]
+    NonLink,
+    /// The background fetch of the node failed.
+    #[error("failed to fetch the node: {0}")]
+    FetchFailed(cid::Cid),
+    /// The path is invalid.
+    #[error("invalid path: {0}")]
+    InvalidPath(String),
+    /// Failed to resolve a segment of the path given to a `Resolver`.
+    #[error("failed to resolve path segment {index}: {source}")]
+    SegmentFailed {
+        /// The index of the failing segment in the path given to the resolver.
+        index: usize,
+        /// The error resolving the segment.
+        source: Box<FormatError>,
+    },
+    /// Failed to resolve a segment of a path.
+    #[error("failed to resolve path segment {index} `{segment}` in block {cid}: {source}")]
+    ResolveFailed {
+        /// The failing segment.
+        segment: String,
+        /// The index of the failing segment in the path, without the root CID.
+        index: usize,
+        /// The CID of the block in which the segment is resolved.
+        cid: cid::Cid,
+        /// The error resolving the segment.
+        source: Box<FormatError>,
+    },
+    /// Block format error.
+    #[error("block format error: {0}")]
+    BlockFormat(#[from] block_format::BlockFormatError),
+    /// Blockstore error.
+    #[error("blockstore error: {0}")]
+    Blockstore(#[from] ipfs_blockstore::BlockstoreError),
     /// Other error.
     #[error("other err: {0}")]
     Other(Box<dyn std::error::Error + Send + Sync>),
@@ -1,6 +1,7 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
 use block_format::Block;
+use bytes::Bytes;
 use cid::Cid;
 
 use crate::error::Result;
@@ -10,7 +11,7 @@ use crate::merkledag::NodeGetter;
 /// Node is the base interface all IPLD nodes must implement.
 ///
 /// Nodes are **Immutable** and all methods defined on the interface are **Thread Safe**.
-pub trait Node: Block {
+pub trait Node: Block + Send + Sync {
     /// A helper function that calls resolve and asserts the output is a link.
     fn resolve_link(&self, path: &[&str]) -> Result<(Link, Vec<String>)>;
 
@@ -24,6 +25,36 @@ pub trait Node: Block {
     fn size(&self) -> u64;
 }
 
+impl Node for Box<dyn Node> {
+    fn resolve_link(&self, path: &[&str]) -> Result<(Link, Vec<String>)> {
+        (**self).resolve_link(path)
+    }
+
+    fn links(&self) -> Vec<&Link> {
+        (**self).links()
+    }
+
+    fn stat(&self) -> Result<&NodeStat> {
+        (**self).stat()
+    }
+
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+}
+
+impl Block for Box<dyn Node> {
+    fn raw_data(&self) -> &Bytes {
+        (**self).raw_data()
+    }
+}
+
+impl AsRef<Cid> for Box<dyn Node> {
+    fn as_ref(&self) -> &Cid {
+        (**self).as_ref()
+    }
+}
+
 /// Resolver is the interface that operate path.
 pub trait Resolver {
     /// The found object by resolving a path through this node.
@@ -69,12 +100,13 @@ impl Link {
     }
 
     /// Returns the MerkleDAG Node that this link points to.
-    pub fn node<T: Node>(&self, ng: &impl NodeGetter<T>) -> Result<impl Node> {
-        Ok(ng.get(&self.cid))
+    pub fn node<T: Node>(&self, ng: &impl NodeGetter<T>) -> Result<T> {
+        ng.get(&self.cid)
     }
 }
 
 /// NodeStat is a statistics object for a Node. Mostly sizes.
+#[derive(Clone, PartialEq, Eq)]
 pub struct NodeStat {
     /// The multihash of node.
     pub hash: multihash::Multihash,
@@ -6,17 +6,24 @@
 #![deny(missing_docs)]
 #![allow(unused)]
 
-/// Provides `register` and `decode` methods.
+/// Provides the `CodecRegistry` and the global `register`, `decode` and `encode` methods.
 pub mod coding;
-mod daghelpers;
+/// Provides helpers to get, copy and walk DAGs.
+pub mod daghelpers;
+mod dagservice;
 mod error;
 mod format;
 mod merkledag;
 mod navipld;
+mod path;
+mod raw;
 mod walker;
 
+pub use self::dagservice::BlockstoreDagService;
 pub use self::error::{FormatError, Result};
 pub use self::format::{Link, Node, NodeStat, Resolver};
-pub use self::merkledag::{DAGService, LinkGetter, NodeAdder, NodeGetter};
-pub use self::navipld::NavigableIpldNode;
+pub use self::merkledag::{DAGService, LinkGetter, NodeAdder, NodeGetter, NodeStream};
+pub use self::navipld::{NavigableIpldNode, DEFAULT_PRELOAD_SIZE};
+pub use self::path::{PathResolver, Resolved, ResolvedPath};
+pub use self::raw::RawNode;
 pub use self::walker::{NavigableNode, Walker};
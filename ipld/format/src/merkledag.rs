@@ -1,32 +1,78 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
-use cid::Cid;
+use cid::{Cid, Codec};
+use futures::stream::{self, BoxStream, StreamExt};
 
 use crate::error::Result;
 use crate::format::{Link, Node};
 
+/// The stream of nodes returned by `NodeGetter::get_many`,
+/// which yields the results in the order of the requested CIDs.
+pub type NodeStream<'a, T> = BoxStream<'a, Result<T>>;
+
 /// The basic Node resolution service.
 pub trait NodeGetter<T: Node> {
     /// Get retrieves nodes by CID. Depending on the NodeGetter
     /// implementation, this may involve fetching the Node from a remote
     /// machine; consider setting a deadline to stop it.
-    fn get(&self, cid: &Cid) -> T;
+    fn get(&self, cid: &Cid) -> Result<T>;
+
+    /// Returns a stream of the nodes of the given CIDs, in the same order.
+    ///
+    /// Implementations able to fetch several nodes in one round-trip should override it,
+    /// the default one gets the nodes one by one as the stream is polled.
+    fn get_many<'a>(&'a self, cids: &'a [Cid]) -> NodeStream<'a, T>
+    where
+        Self: Sync,
+        T: 'a,
+    {
+        stream::iter(cids.iter().map(move |cid| self.get(cid))).boxed()
+    }
 }
 
 /// NodeAdder adds nodes to a DAG.
 pub trait NodeAdder<T: Node> {
     /// Add adds a node to this DAG.
-    fn add(&self, node: T);
+    fn add(&self, node: &T) -> Result<()>;
+
+    /// Adds many nodes to this DAG.
+    ///
+    /// Consider using a batch of the underlying store if it's available,
+    /// the default implementation adds the nodes one by one.
+    fn add_many(&self, nodes: &[T]) -> Result<()> {
+        for node in nodes {
+            self.add(node)?;
+        }
+        Ok(())
+    }
 }
 
 /// NodeGetters can optionally implement this interface to make finding linked objects faster.
 pub trait LinkGetter<T: Node>: NodeGetter<T> {
-    /// returns the children of the node referred to by the given CID.
-    fn get_links(node: &Cid) -> Result<Vec<Link>>;
+    /// Returns the children of the node referred to by the given CID.
+    ///
+    /// The default implementation gets the node, except for `raw` nodes which have no links.
+    fn get_links(&self, cid: &Cid) -> Result<Vec<Link>> {
+        if cid.codec() == Codec::Raw {
+            return Ok(vec![]);
+        }
+        let node = self.get(cid)?;
+        Ok(node.links().into_iter().cloned().collect())
+    }
 }
 
 /// DAGService is an IPFS Merkle DAG service.
 pub trait DAGService<T: Node>: NodeGetter<T> + NodeAdder<T> {
-    /// remove a node, referred to by the given CID, from this DAG.
-    fn remove(cid: &Cid) -> Result<()>;
+    /// Remove a node, referred to by the given CID, from this DAG.
+    fn remove(&self, cid: &Cid) -> Result<()>;
+
+    /// Removes many nodes from this DAG.
+    ///
+    /// The default implementation removes the nodes one by one.
+    fn remove_many(&self, cids: &[Cid]) -> Result<()> {
+        for cid in cids {
+            self.remove(cid)?;
+        }
+        Ok(())
+    }
 }
@@ -1,29 +1,75 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
+use std::any::Any;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
 use cid::Cid;
+use futures::executor::block_on;
+use futures::StreamExt;
 
+use crate::error::{FormatError, Result};
 use crate::format::Node;
 use crate::merkledag::NodeGetter;
+use crate::walker::NavigableNode;
+
+/// The default number of children fetched in advance when a child is fetched.
+pub const DEFAULT_PRELOAD_SIZE: usize = 10;
+
+/// The state of the fetch of a child.
+enum ChildPromise<N> {
+    /// The child hasn't been requested yet.
+    Unrequested,
+    /// The child is being fetched in the background.
+    Requested(Receiver<Result<N>>),
+    /// The child has been handed out, it's fetched again if requested again.
+    Fetched,
+}
 
 /// NavigableIPLDNode implements the `NavigableNode` interface wrapping an IPLD `Node`.
+///
+/// Fetching a child also prefetches the following children in the background,
+/// with a single `get_many` of the `NodeGetter`, so that walking the DAG doesn't
+/// pay one round-trip per node.
 pub struct NavigableIpldNode<N: Node, NG: NodeGetter<N>> {
     node: N,
-    node_getter: NG,
+    node_getter: Arc<NG>,
     child_cids: Vec<Cid>,
+    child_promises: Mutex<Vec<ChildPromise<N>>>,
+    preload_size: usize,
 }
 
-impl<N: Node, NG: NodeGetter<N>> NavigableIpldNode<N, NG> {
+impl<N, NG> NavigableIpldNode<N, NG>
+where
+    N: Node + 'static,
+    NG: NodeGetter<N> + Send + Sync + 'static,
+{
     /// Create a `NavigableIpldNode` wrapping the provided `node`.
-    pub fn new(node: N, node_getter: NG) -> Self {
+    pub fn new(node: N, node_getter: Arc<NG>) -> Self {
+        Self::with_preload_size(node, node_getter, DEFAULT_PRELOAD_SIZE)
+    }
+
+    /// Create a `NavigableIpldNode` wrapping the provided `node`, which prefetches up to
+    /// `preload_size` children at a time, 0 disables the prefetch.
+    ///
+    /// The children of the node use the same `preload_size`.
+    pub fn with_preload_size(node: N, node_getter: Arc<NG>, preload_size: usize) -> Self {
         let child_cids = node
             .links()
             .into_iter()
             .map(|link| link.cid.clone())
+            .collect::<Vec<_>>();
+        let child_promises = child_cids
+            .iter()
+            .map(|_| ChildPromise::Unrequested)
             .collect();
         NavigableIpldNode {
             node,
             node_getter,
             child_cids,
+            child_promises: Mutex::new(child_promises),
+            preload_size,
         }
     }
 
@@ -34,6 +80,84 @@ impl<N: Node, NG: NodeGetter<N>> NavigableIpldNode<N, NG> {
 
     /// Return the number of links (of child nodes) in this node.
     pub fn child_total(&self) -> usize {
-        self.node.links().len()
+        self.child_cids.len()
+    }
+
+    /// Returns the `NavigableIpldNode` behind a `NavigableNode`, e.g. the active node of a
+    /// `Walker`, or `None` if it isn't a `NavigableIpldNode<N, NG>`.
+    pub fn extract(node: &dyn NavigableNode) -> Option<&Self> {
+        node.as_any()?.downcast_ref::<Self>()
+    }
+
+    /// Requests in the background the children in the window of `preload_size` children
+    /// starting at `child_index`, skipping the ones already requested.
+    fn preload(&self, promises: &mut [ChildPromise<N>], child_index: usize) {
+        let end = promises.len().min(child_index + self.preload_size);
+        let mut cids = vec![];
+        let mut senders = vec![];
+        for (index, promise) in promises.iter_mut().enumerate().take(end).skip(child_index) {
+            if let ChildPromise::Unrequested = promise {
+                let (sender, receiver) = mpsc::channel();
+                *promise = ChildPromise::Requested(receiver);
+                cids.push(self.child_cids[index].clone());
+                senders.push(sender);
+            }
+        }
+        if cids.is_empty() {
+            return;
+        }
+        let node_getter = self.node_getter.clone();
+        thread::spawn(move || {
+            let mut nodes = node_getter.get_many(&cids);
+            for sender in senders {
+                match block_on(nodes.next()) {
+                    // the receiver is gone if the parent node has been dropped.
+                    Some(node) => {
+                        let _ = sender.send(node);
+                    }
+                    None => break,
+                }
+            }
+        });
+    }
+}
+
+impl<N, NG> NavigableNode for NavigableIpldNode<N, NG>
+where
+    N: Node + 'static,
+    NG: NodeGetter<N> + Send + Sync + 'static,
+{
+    fn child_total(&self) -> usize {
+        self.child_cids.len()
+    }
+
+    fn fetch_child(&self, child_index: usize) -> Result<Arc<dyn NavigableNode>> {
+        let cid = self
+            .child_cids
+            .get(child_index)
+            .ok_or(FormatError::NoChild(child_index))?;
+        let promise = {
+            let mut promises = self.child_promises.lock().expect("get lock failed");
+            if let ChildPromise::Unrequested = promises[child_index] {
+                self.preload(&mut promises, child_index);
+            }
+            std::mem::replace(&mut promises[child_index], ChildPromise::Fetched)
+        };
+        let child = match promise {
+            ChildPromise::Requested(receiver) => receiver
+                .recv()
+                .map_err(|_| FormatError::FetchFailed(cid.clone()))??,
+            // prefetch disabled or child already handed out.
+            _ => self.node_getter.get(cid)?,
+        };
+        Ok(Arc::new(Self::with_preload_size(
+            child,
+            self.node_getter.clone(),
+            self.preload_size,
+        )))
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
     }
 }
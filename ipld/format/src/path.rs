@@ -0,0 +1,147 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::convert::TryFrom;
+
+use cid::Cid;
+
+use crate::error::{FormatError, Result};
+use crate::format::{Link, Node, Resolver};
+use crate::merkledag::NodeGetter;
+
+/// The end of a resolved path.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resolved<O> {
+    /// The path ends at a link, which isn't followed.
+    Link(Link),
+    /// The path ends at a value of the last node, as returned by `Resolver::resolve`.
+    Value(O),
+}
+
+/// The result of `PathResolver::resolve`.
+pub struct ResolvedPath<T: Resolver> {
+    /// The CIDs of the blocks traversed, from the root to the last node.
+    pub cids: Vec<Cid>,
+    /// The last node, which contains the end of the path.
+    pub node: T,
+    /// The end of the path.
+    pub result: Resolved<T::Output>,
+}
+
+/// PathResolver resolves `/ipfs/<cid>/a/b/0/c`-style paths across block boundaries.
+///
+/// The path is resolved through a node until a link is reached, then the linked node
+/// is fetched from the `NodeGetter` and the rest of the path is resolved through it,
+/// until the path is exhausted.
+pub struct PathResolver<'a, NG> {
+    ng: &'a NG,
+}
+
+impl<'a, NG> PathResolver<'a, NG> {
+    /// Creates a new `PathResolver` fetching the nodes from the `NodeGetter`.
+    pub fn new(ng: &'a NG) -> Self {
+        PathResolver { ng }
+    }
+
+    /// Resolves the path, which is `<cid>/a/b`, optionally prefixed by `/ipfs/` or `/ipld/`.
+    ///
+    /// The errors of the path segments are `FormatError::ResolveFailed`,
+    /// which names the failing segment.
+    pub fn resolve<T>(&self, path: &str) -> Result<ResolvedPath<T>>
+    where
+        T: Node + Resolver,
+        NG: NodeGetter<T>,
+    {
+        let (root, segments) = split_path(path)?;
+        let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+        let mut node = self.ng.get(&root)?;
+        let mut cids = vec![root];
+        // the number of segments resolved through the previous nodes.
+        let mut offset = 0;
+        loop {
+            let rest = &segments[offset..];
+            let cid = cids.last().expect("at least the root").clone();
+            if !rest.is_empty() {
+                match node.resolve_link(rest) {
+                    Ok((link, remaining)) => {
+                        if remaining.is_empty() {
+                            return Ok(ResolvedPath {
+                                cids,
+                                node,
+                                result: Resolved::Link(link),
+                            });
+                        }
+                        offset += rest.len() - remaining.len();
+                        node = self.ng.get(&link.cid).map_err(|e| {
+                            // the last segment resolved is the link.
+                            resolve_failed(&segments, offset.saturating_sub(1), cid, e)
+                        })?;
+                        cids.push(link.cid);
+                        continue;
+                    }
+                    // the path doesn't end at a link, it's resolved to a value below.
+                    Err(FormatError::NoSuchLink) | Err(FormatError::NonLink) => {}
+                    Err(e) => return Err(segment_failed(&segments, offset, cid, e)),
+                }
+            }
+            return match node.resolve(rest) {
+                Ok((value, remaining)) => {
+                    if !remaining.is_empty() {
+                        // the rest of the path would have to be resolved through a link.
+                        let index = offset + rest.len() - remaining.len();
+                        return Err(resolve_failed(
+                            &segments,
+                            index,
+                            cid,
+                            FormatError::NoSuchLink,
+                        ));
+                    }
+                    Ok(ResolvedPath {
+                        cids,
+                        node,
+                        result: Resolved::Value(value),
+                    })
+                }
+                Err(e) => Err(segment_failed(&segments, offset, cid, e)),
+            };
+        }
+    }
+}
+
+/// The error of a resolver, the failing segment is the first one of the path
+/// given to the resolver, unless the error names another one.
+fn segment_failed(segments: &[&str], offset: usize, cid: Cid, e: FormatError) -> FormatError {
+    match e {
+        FormatError::SegmentFailed { index, source } => {
+            resolve_failed(segments, offset + index, cid, *source)
+        }
+        e => resolve_failed(segments, offset, cid, e),
+    }
+}
+
+fn resolve_failed(segments: &[&str], index: usize, cid: Cid, e: FormatError) -> FormatError {
+    FormatError::ResolveFailed {
+        segment: segments
+            .get(index)
+            .map(|s| (*s).to_string())
+            .unwrap_or_default(),
+        index,
+        cid,
+        source: Box::new(e),
+    }
+}
+
+/// Splits the path into the root CID and the segments.
+fn split_path(path: &str) -> Result<(Cid, Vec<String>)> {
+    let mut segments = path.split('/').filter(|s| !s.is_empty());
+    if path.starts_with('/') {
+        match segments.next() {
+            Some("ipfs") | Some("ipld") => {}
+            _ => return Err(FormatError::InvalidPath(path.to_string())),
+        }
+    }
+    let root = segments
+        .next()
+        .ok_or_else(|| FormatError::InvalidPath(path.to_string()))?;
+    let root = Cid::try_from(root).map_err(|_| FormatError::InvalidPath(path.to_string()))?;
+    Ok((root, segments.map(ToString::to_string).collect()))
+}
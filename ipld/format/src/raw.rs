@@ -0,0 +1,115 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use block_format::{BasicBlock, Block, BlockBuilder};
+use bytes::Bytes;
+use cid::{Cid, Codec};
+use multihash::Code;
+
+use crate::error::{FormatError, Result};
+use crate::format::{Link, Node, NodeStat, Resolver};
+
+/// RawNode is a node of the `raw` codec, whose data is the whole block, e.g. a leaf of an
+/// UnixFS file. It has no links and no path can be resolved through it.
+#[derive(Clone, Debug)]
+pub struct RawNode {
+    block: BasicBlock,
+    stat: NodeStat,
+}
+
+impl RawNode {
+    /// Creates a new `RawNode` with the data, and its CID is version 1 with a sha2-256 hash.
+    pub fn new(data: Bytes) -> Self {
+        let builder = BlockBuilder::new_v1(Codec::Raw, Code::Sha2_256.into());
+        Self::new_with_builder(data, &builder).expect("the builder builds raw CIDv1")
+    }
+
+    /// Creates a new `RawNode` with the data, and its CID is built by the builder,
+    /// which must build blocks of the `raw` codec.
+    pub fn new_with_builder(data: Bytes, builder: &BlockBuilder) -> Result<Self> {
+        let block = builder.build(data)?;
+        Self::from_basic_block(block)
+    }
+
+    /// Decodes a block of the `raw` codec into a `RawNode`,
+    /// the data of the block is verified against its CID.
+    pub fn from_block<B: Block + ?Sized>(block: &B) -> Result<Self> {
+        let block = BasicBlock::new_with_cid(block.to_bytes(), block.cid().clone())?;
+        Self::from_basic_block(block)
+    }
+
+    fn from_basic_block(block: BasicBlock) -> Result<Self> {
+        let codec = block.cid().codec();
+        if codec != Codec::Raw {
+            return Err(FormatError::NotRawBlock(codec));
+        }
+        let hash = multihash::Multihash::from_bytes(block.cid().hash().as_bytes().to_vec())
+            .map_err(|e| FormatError::Other(Box::new(e)))?;
+        let size = block.raw_data().len();
+        let stat = NodeStat {
+            hash,
+            num_links: 0,
+            block_size: size,
+            links_size: 0,
+            data_size: size,
+            cumulative_size: size,
+        };
+        Ok(RawNode { block, stat })
+    }
+
+    /// Returns the block of the node.
+    pub fn block(&self) -> &BasicBlock {
+        &self.block
+    }
+
+    /// Consumes the node, returns its block.
+    pub fn into_block(self) -> BasicBlock {
+        self.block
+    }
+}
+
+impl Node for RawNode {
+    fn resolve_link(&self, _path: &[&str]) -> Result<(Link, Vec<String>)> {
+        Err(FormatError::NoSuchLink)
+    }
+
+    fn links(&self) -> Vec<&Link> {
+        vec![]
+    }
+
+    fn stat(&self) -> Result<&NodeStat> {
+        Ok(&self.stat)
+    }
+
+    fn size(&self) -> u64 {
+        self.block.raw_data().len() as u64
+    }
+}
+
+impl Resolver for RawNode {
+    type Output = Bytes;
+
+    /// Resolves the empty path to the data of the node, any other path fails.
+    fn resolve(&self, path: &[&str]) -> Result<(Self::Output, Vec<String>)> {
+        if path.is_empty() {
+            Ok((self.block.to_bytes(), vec![]))
+        } else {
+            Err(FormatError::NoSuchLink)
+        }
+    }
+
+    fn tree(&self, _path: &str, _depth: Option<usize>) -> Vec<String> {
+        vec![]
+    }
+}
+
+impl Block for RawNode {
+    fn raw_data(&self) -> &Bytes {
+        self.block.raw_data()
+    }
+}
+
+impl AsRef<Cid> for RawNode {
+    fn as_ref(&self) -> &Cid {
+        self.block.cid()
+    }
+}
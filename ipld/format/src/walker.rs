@@ -1,5 +1,6 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
+use std::any::Any;
 use std::sync::Arc;
 
 use crate::error::{FormatError, Result};
@@ -11,6 +12,12 @@ pub trait NavigableNode {
 
     /// Returns the child of this node pointed to by `child_index`.
     fn fetch_child(&self, child_index: usize) -> Result<Arc<dyn NavigableNode>>;
+
+    /// Returns the node as `Any`, so that the concrete node can be recovered from
+    /// a `dyn NavigableNode`, e.g. by `NavigableIpldNode::extract`.
+    fn as_any(&self) -> Option<&dyn Any> {
+        None
+    }
 }
 
 /// Walker provides methods to move through a DAG of nodes that implement the `NavigableNode`
@@ -21,31 +28,220 @@ pub trait NavigableNode {
 /// you provide a Visitor function that will be called for each node the Walker traverses.
 /// The Visitor can read data from those nodes and, optionally, direct the movement of the Walker.
 pub struct Walker {
-    stack: Vec<Arc<dyn NavigableNode>>,
+    root: Arc<dyn NavigableNode>,
+    /// The nodes from the root to the active node, empty until the first `down`.
+    path: Vec<Arc<dyn NavigableNode>>,
+    /// The index of the active child of every node in `path`,
+    /// i.e. the child the Walker will go down to.
+    child_index: Vec<usize>,
+    paused: bool,
+    ended: bool,
 }
 
 impl Walker {
     /// Creates a new `Walker` from a `root` NavigableNode.
+    ///
+    /// The Walker doesn't point to the root until the first `down` (or `iterate`, `seek`).
     pub fn new(root: Arc<dyn NavigableNode>) -> Walker {
-        Walker { stack: vec![root] }
+        Walker {
+            root,
+            path: vec![],
+            child_index: vec![],
+            paused: false,
+            ended: false,
+        }
     }
 
     /// Returns the `NavigableNode` that `Walker` is pointing
     /// to at the moment. It changes when `up` or `down` is called.
     pub fn active_node(&self) -> Result<&Arc<dyn NavigableNode>> {
-        self.stack.last().ok_or(FormatError::NextNoChild)
+        self.path.last().ok_or(FormatError::DepthNotInit)
+    }
+
+    /// Returns the index of the child of the active node the Walker would go down to.
+    pub fn active_child_index(&self) -> Result<usize> {
+        self.child_index
+            .last()
+            .cloned()
+            .ok_or(FormatError::DepthNotInit)
+    }
+
+    /// Returns the depth of the active node, the root is at depth 0.
+    pub fn depth(&self) -> Result<usize> {
+        self.path
+            .len()
+            .checked_sub(1)
+            .ok_or(FormatError::DepthNotInit)
+    }
+
+    /// Returns the path of nodes from the root to the active node.
+    pub fn path(&self) -> &[Arc<dyn NavigableNode>] {
+        &self.path
+    }
+
+    /// Moves the Walker down to the active child of the active node,
+    /// the first call moves the Walker to the root.
+    pub fn down(&mut self) -> Result<()> {
+        let child = self.fetch_child()?;
+        self.path.push(child);
+        self.child_index.push(0);
+        Ok(())
+    }
+
+    /// Moves the Walker up to the parent of the active node.
+    pub fn up(&mut self) -> Result<()> {
+        match self.path.len() {
+            0 => Err(FormatError::DepthNotInit),
+            1 => Err(FormatError::UpOnRoot),
+            _ => {
+                self.path.pop();
+                self.child_index.pop();
+                Ok(())
+            }
+        }
+    }
+
+    /// Moves the active child index of the active node to the next child.
+    pub fn next_child(&mut self) -> Result<()> {
+        let child_total = self.active_node()?.child_total();
+        let index = self
+            .child_index
+            .last_mut()
+            .expect("path and child_index have the same length");
+        if *index + 1 >= child_total {
+            return Err(FormatError::NextNoChild);
+        }
+        *index += 1;
+        Ok(())
+    }
+
+    /// Pauses the current `iterate` or `seek` after the Visitor returns,
+    /// the Walker stays on the active node and a later `iterate` resumes from it.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Ends the walk after the Visitor returns, as if the end of the DAG was reached.
+    pub fn end(&mut self) {
+        self.ended = true;
+    }
+
+    /// Iterates the DAG in depth-first pre-order, calling the Visitor on every node,
+    /// until the Visitor pauses the Walker, in which case `Ok(())` is returned, or the end of
+    /// the DAG is reached, in which case `FormatError::EndOfDag` is returned.
+    ///
+    /// Nodes reachable through different paths are visited every time.
+    pub fn iterate<F>(&mut self, mut visitor: F) -> Result<()>
+    where
+        F: FnMut(&mut Walker) -> Result<()>,
+    {
+        loop {
+            self.advance()?;
+            visitor(self)?;
+            if let Some(result) = self.control() {
+                return result;
+            }
+        }
+    }
+
+    /// Seeks a node going down from the active node (from the root on the first call),
+    /// calling the Visitor on every node reached.
+    ///
+    /// The Visitor directs the Walker by moving the active child index with `next_child`,
+    /// and pauses the Walker when the target node is reached. The seek also stops when
+    /// a node without children is reached.
+    pub fn seek<F>(&mut self, mut visitor: F) -> Result<()>
+    where
+        F: FnMut(&mut Walker) -> Result<()>,
+    {
+        loop {
+            match self.down() {
+                Ok(()) => {}
+                Err(FormatError::DownNoChild(..)) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+            visitor(self)?;
+            if let Some(result) = self.control() {
+                return result;
+            }
+        }
+    }
+
+    /// Returns the child the Walker would go down to.
+    fn fetch_child(&self) -> Result<Arc<dyn NavigableNode>> {
+        let (node, index) = match (self.path.last(), self.child_index.last()) {
+            (Some(node), Some(index)) => (node, *index),
+            // the first `down` goes to the root.
+            _ => return Ok(self.root.clone()),
+        };
+        let child_total = node.child_total();
+        if index >= child_total {
+            return Err(FormatError::DownNoChild(
+                self.path.len() - 1,
+                index,
+                child_total,
+            ));
+        }
+        node.fetch_child(index)
+    }
+
+    /// Moves to the next node in depth-first pre-order.
+    fn advance(&mut self) -> Result<()> {
+        if self.ended {
+            return Err(FormatError::EndOfDag);
+        }
+        if self.path.is_empty() {
+            return self.down();
+        }
+        match self.down() {
+            Err(FormatError::DownNoChild(..)) => {}
+            result => return result,
+        }
+        loop {
+            match self.next_child() {
+                Ok(()) => return self.down(),
+                Err(FormatError::NextNoChild) => {}
+                Err(e) => return Err(e),
+            }
+            match self.up() {
+                Ok(()) => {}
+                Err(FormatError::UpOnRoot) => {
+                    self.ended = true;
+                    return Err(FormatError::EndOfDag);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Consumes the pause and end requests of the Visitor.
+    fn control(&mut self) -> Option<Result<()>> {
+        if self.ended {
+            self.paused = false;
+            return Some(Err(FormatError::EndOfDag));
+        }
+        if self.paused {
+            self.paused = false;
+            return Some(Ok(()));
+        }
+        None
     }
 }
 
+/// Iterates the DAG in depth-first pre-order.
+///
+/// The iteration ends after the first error, e.g. when a child can't be fetched.
 impl Iterator for Walker {
-    type Item = Arc<dyn NavigableNode>;
+    type Item = Result<Arc<dyn NavigableNode>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let node = self.stack.pop()?;
-        for i in (0..node.child_total()).rev() {
-            let node = node.fetch_child(i).ok()?;
-            self.stack.push(node);
+        match self.advance() {
+            Ok(()) => Some(self.active_node().map(Clone::clone)),
+            Err(FormatError::EndOfDag) => None,
+            Err(e) => {
+                self.ended = true;
+                Some(Err(e))
+            }
         }
-        Some(node)
     }
 }
@@ -1,8 +1,12 @@
+#![allow(dead_code)]
+
+use std::convert::TryFrom;
 use std::sync::Arc;
 
 use block_format::Block;
 use bytes::Bytes;
 use cid::{Cid, Codec, IntoExt};
+use multihash::Code;
 
 use ipld_format::{FormatError, Link, NavigableNode, Node, NodeStat, Resolver, Result};
 
@@ -84,3 +88,83 @@ impl NavigableNode for N {
             .ok_or(FormatError::NoChild(child_index))
     }
 }
+
+/// A `dag-pb` node made of a name and the CIDs of its children, one per line.
+#[derive(Clone)]
+pub struct TreeNode {
+    cid: Cid,
+    data: Bytes,
+    links: Vec<Link>,
+}
+
+impl TreeNode {
+    pub fn new(name: &str, children: &[&TreeNode]) -> Self {
+        let mut data = name.to_string();
+        for child in children {
+            data.push('\n');
+            data.push_str(&child.cid.to_string());
+        }
+        let cid = Cid::new_v1(
+            Codec::DagProtobuf,
+            Code::Sha2_256.digest(data.as_bytes()).into_ext(),
+        );
+        TreeNode {
+            cid,
+            data: Bytes::from(data),
+            links: children
+                .iter()
+                .map(|child| Link::new_with_cid(child.cid.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        let data = std::str::from_utf8(&self.data).unwrap();
+        data.split('\n').next().unwrap()
+    }
+
+    pub fn decode(block: &dyn Block) -> Result<Box<dyn Node>> {
+        let data = std::str::from_utf8(block.raw_data()).map_err(|_| FormatError::DecodeError)?;
+        let links = data
+            .split('\n')
+            .skip(1)
+            .map(|cid| Cid::try_from(cid).map(Link::new_with_cid))
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| FormatError::DecodeError)?;
+        Ok(Box::new(TreeNode {
+            cid: block.cid().clone(),
+            data: Bytes::copy_from_slice(block.raw_data()),
+            links,
+        }))
+    }
+}
+
+impl Node for TreeNode {
+    fn resolve_link(&self, _path: &[&str]) -> Result<(Link, Vec<String>)> {
+        unimplemented!()
+    }
+
+    fn links(&self) -> Vec<&Link> {
+        self.links.iter().collect()
+    }
+
+    fn stat(&self) -> Result<&NodeStat> {
+        unimplemented!()
+    }
+
+    fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+}
+
+impl Block for TreeNode {
+    fn raw_data(&self) -> &Bytes {
+        &self.data
+    }
+}
+
+impl AsRef<Cid> for TreeNode {
+    fn as_ref(&self) -> &Cid {
+        &self.cid
+    }
+}
@@ -1,9 +1,13 @@
 mod common;
 
 use block_format::{BasicBlock, Block};
+use bytes::Bytes;
 use cid::{Cid, Codec, IntoExt};
-use ipld_format::coding::{decode, register};
-use ipld_format::{Node, Result};
+use matches::matches;
+use multihash::Code;
+
+use ipld_format::coding::{decode, register, CodecRegistry};
+use ipld_format::{FormatError, Node, Result};
 
 use self::common::EmptyNode;
 
@@ -37,3 +41,55 @@ fn test_decode() {
 fn test_init() {
     init2();
 }
+
+#[test]
+fn test_registry() {
+    let mut registry = CodecRegistry::new();
+    assert!(registry.codecs().is_empty());
+    let id = Cid::new_v1(Codec::DagCBOR, multihash::Identity::digest(b"").into_ext());
+    let block = BasicBlock::new_with_cid(vec![].into(), id.clone()).unwrap();
+    assert!(matches!(
+        registry.decode(&block),
+        Err(FormatError::DecoderNotRegister(Codec::DagCBOR))
+    ));
+
+    registry.register_decoder(Codec::DagCBOR, decode_fu);
+    registry.register_encoder(Codec::Raw, |s: &String| Ok(s.as_bytes().to_vec()));
+    assert_eq!(registry.codecs(), vec![Codec::Raw, Codec::DagCBOR]);
+    assert!(registry.has_decoder(Codec::DagCBOR));
+    assert!(!registry.has_decoder(Codec::Raw));
+    assert!(registry.has_encoder::<String>(Codec::Raw));
+    assert!(!registry.has_encoder::<Vec<u8>>(Codec::Raw));
+
+    assert!(registry.decode(&block).is_ok());
+    assert_eq!(
+        registry.encode(Codec::Raw, &"abc".to_string()).unwrap(),
+        b"abc"
+    );
+    // encoders are registered per data model
+    assert!(matches!(
+        registry.encode(Codec::Raw, &b"abc".to_vec()),
+        Err(FormatError::EncoderNotRegister(Codec::Raw))
+    ));
+    assert!(matches!(
+        registry.encode(Codec::DagCBOR, &"abc".to_string()),
+        Err(FormatError::EncoderNotRegister(Codec::DagCBOR))
+    ));
+}
+
+#[test]
+fn test_default_registry() {
+    let registry = CodecRegistry::with_defaults();
+    assert!(registry.has_decoder(Codec::Raw));
+
+    let data = Bytes::from_static(b"raw data");
+    let encoded = registry.encode(Codec::Raw, &data).unwrap();
+    assert_eq!(encoded, data);
+    let cid = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(&encoded).into_ext());
+    let block = BasicBlock::new_with_cid(encoded.into(), cid.clone()).unwrap();
+    let node = registry.decode(&block).unwrap();
+    assert_eq!(node.cid(), &cid);
+    assert_eq!(node.raw_data(), &data[..]);
+    assert!(node.links().is_empty());
+    assert_eq!(node.size(), 8);
+}
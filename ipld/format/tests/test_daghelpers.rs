@@ -0,0 +1,208 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use block_format::Block;
+use cid::Cid;
+use futures::executor::block_on;
+use matches::matches;
+
+use ipld_format::daghelpers::{
+    copy, cumulative_size, enumerate_children_async, fetch_graph, get_dag, get_nodes, walk,
+    walk_parallel,
+};
+use ipld_format::{DAGService, FormatError, LinkGetter, Node, NodeAdder, NodeGetter, Result};
+
+use self::common::TreeNode;
+
+#[derive(Default)]
+struct MemDag {
+    nodes: Mutex<HashMap<Cid, TreeNode>>,
+    gets: Mutex<usize>,
+}
+
+impl NodeGetter<TreeNode> for MemDag {
+    fn get(&self, cid: &Cid) -> Result<TreeNode> {
+        *self.gets.lock().unwrap() += 1;
+        self.nodes
+            .lock()
+            .unwrap()
+            .get(cid)
+            .cloned()
+            .ok_or(FormatError::NoSuchLink)
+    }
+}
+
+impl LinkGetter<TreeNode> for MemDag {}
+
+impl NodeAdder<TreeNode> for MemDag {
+    fn add(&self, node: &TreeNode) -> Result<()> {
+        self.nodes
+            .lock()
+            .unwrap()
+            .insert(node.cid().clone(), node.clone());
+        Ok(())
+    }
+}
+
+impl DAGService<TreeNode> for MemDag {
+    fn remove(&self, cid: &Cid) -> Result<()> {
+        self.nodes.lock().unwrap().remove(cid);
+        Ok(())
+    }
+}
+
+/// root -> (a -> (a1, shared), b -> (shared))
+fn setup() -> (MemDag, Vec<TreeNode>) {
+    let shared = TreeNode::new("shared", &[]);
+    let a1 = TreeNode::new("a1", &[]);
+    let a = TreeNode::new("a", &[&a1, &shared]);
+    let b = TreeNode::new("b", &[&shared]);
+    let root = TreeNode::new("root", &[&a, &b]);
+    let nodes = vec![root, a, a1, shared, b];
+    let dag = MemDag::default();
+    dag.add_many(&nodes).unwrap();
+    (dag, nodes)
+}
+
+fn names(nodes: &[TreeNode]) -> Arc<HashMap<Cid, String>> {
+    Arc::new(
+        nodes
+            .iter()
+            .map(|node| (node.cid().clone(), node.name().to_string()))
+            .collect(),
+    )
+}
+
+#[test]
+fn test_get_nodes() {
+    let (dag, nodes) = setup();
+    let missing = TreeNode::new("missing", &[]);
+    let cids = vec![
+        nodes[4].cid().clone(),
+        missing.cid().clone(),
+        nodes[0].cid().clone(),
+    ];
+    let got = get_nodes(&dag, &cids);
+    assert_eq!(got.len(), 3);
+    assert_eq!(got[0].as_ref().unwrap().name(), "b");
+    assert!(matches!(got[1], Err(FormatError::NoSuchLink)));
+    assert_eq!(got[2].as_ref().unwrap().name(), "root");
+
+    let children = get_dag(&dag, &nodes[0])
+        .into_iter()
+        .map(|node| node.unwrap().name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(children, vec!["a", "b"]);
+}
+
+#[test]
+fn test_copy() {
+    let (from, nodes) = setup();
+    let to = MemDag::default();
+    copy(&from, &to, nodes[0].cid()).unwrap();
+    assert_eq!(to.nodes.lock().unwrap().len(), 5);
+    // the shared node is fetched once.
+    assert_eq!(*from.gets.lock().unwrap(), 5);
+
+    // copy a sub-DAG
+    let to = MemDag::default();
+    copy(&from, &to, nodes[4].cid()).unwrap();
+    assert_eq!(to.nodes.lock().unwrap().len(), 2);
+
+    from.remove(nodes[2].cid()).unwrap();
+    let to = MemDag::default();
+    let r = copy(&from, &to, nodes[0].cid());
+    assert!(matches!(r, Err(FormatError::NoSuchLink)));
+}
+
+#[test]
+fn test_walk() {
+    let (dag, nodes) = setup();
+    let names = names(&nodes);
+    let mut visited = vec![];
+    walk(&dag, nodes[0].cid(), |cid| {
+        visited.push(names[cid].clone());
+        true
+    })
+    .unwrap();
+    assert_eq!(visited, vec!["root", "a", "a1", "shared", "b"]);
+
+    // the children of "a" are skipped
+    let mut visited = vec![];
+    walk(&dag, nodes[0].cid(), |cid| {
+        visited.push(names[cid].clone());
+        names[cid] != "a"
+    })
+    .unwrap();
+    assert_eq!(visited, vec!["root", "a", "b", "shared"]);
+
+    dag.remove(nodes[3].cid()).unwrap();
+    let r = walk(&dag, nodes[0].cid(), |_| true);
+    assert!(matches!(r, Err(FormatError::NoSuchLink)));
+}
+
+#[test]
+fn test_walk_parallel() {
+    let (dag, nodes) = setup();
+    let dag = Arc::new(dag);
+    let names = names(&nodes);
+    for concurrency in &[1, 4] {
+        let visited = Arc::new(Mutex::new(vec![]));
+        let (names, v) = (names.clone(), visited.clone());
+        walk_parallel(dag.clone(), nodes[0].cid(), *concurrency, move |cid| {
+            v.lock().unwrap().push(names[cid].clone());
+            true
+        })
+        .unwrap();
+        let mut visited = visited.lock().unwrap().clone();
+        visited.sort();
+        assert_eq!(visited, vec!["a", "a1", "b", "root", "shared"]);
+    }
+
+    dag.remove(nodes[2].cid()).unwrap();
+    let r = walk_parallel(dag, nodes[0].cid(), 4, |_| true);
+    assert!(matches!(r, Err(FormatError::NoSuchLink)));
+}
+
+#[test]
+fn test_fetch_graph() {
+    let (dag, nodes) = setup();
+    let dag = Arc::new(dag);
+    fetch_graph(dag.clone(), nodes[0].cid()).unwrap();
+    assert_eq!(*dag.gets.lock().unwrap(), 5);
+}
+
+#[test]
+fn test_enumerate_children_async() {
+    let (dag, nodes) = setup();
+    let count = Arc::new(Mutex::new(0));
+    let c = count.clone();
+    let future = enumerate_children_async(Arc::new(dag), nodes[0].cid().clone(), move |_| {
+        *c.lock().unwrap() += 1;
+        true
+    });
+    block_on(future).unwrap();
+    assert_eq!(*count.lock().unwrap(), 5);
+}
+
+#[test]
+fn test_cumulative_size() {
+    let (dag, nodes) = setup();
+    let size = |i: usize| nodes[i].size() as usize;
+    // root -> (a -> (a1, shared), b -> (shared))
+    let a = size(1) + size(2) + size(3);
+    let b = size(4) + size(3);
+    assert_eq!(
+        cumulative_size(&dag, nodes[0].cid()).unwrap(),
+        size(0) + a + b
+    );
+    // the shared node is fetched once.
+    assert_eq!(*dag.gets.lock().unwrap(), 5);
+    assert_eq!(cumulative_size(&dag, nodes[4].cid()).unwrap(), b);
+
+    dag.remove(nodes[3].cid()).unwrap();
+    let r = cumulative_size(&dag, nodes[0].cid());
+    assert!(matches!(r, Err(FormatError::NoSuchLink)));
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use block_format::{BasicBlock, Block};
+use bytes::Bytes;
+use cid::{Cid, Codec, IntoExt};
+use futures::executor::block_on;
+use futures::StreamExt;
+use matches::matches;
+use multihash::Code;
+
+use ipfs_blockstore::{Blockstore, BlockstoreError, MemBlockstore};
+use ipld_format::{
+    coding, BlockstoreDagService, DAGService, FormatError, Link, LinkGetter, Node, NodeAdder,
+    NodeGetter,
+};
+
+use self::common::TreeNode;
+
+// the test nodes are registered like the codecs of the other crates.
+inventory::submit! {
+    coding::CodecPlugin::new(|registry| {
+        registry.register_decoder(Codec::DagProtobuf, TreeNode::decode);
+    })
+}
+
+/// root -> (a -> (a1), b)
+fn setup() -> Vec<Box<dyn Node>> {
+    let a1 = TreeNode::new("a1", &[]);
+    let a = TreeNode::new("a", &[&a1]);
+    let b = TreeNode::new("b", &[]);
+    let root = TreeNode::new("root", &[&a, &b]);
+    vec![root, a, a1, b]
+        .into_iter()
+        .map(|node| Box::new(node) as Box<dyn Node>)
+        .collect()
+}
+
+#[test]
+fn test_blockstore_dag_service_add_get() {
+    let nodes = setup();
+    let dag = BlockstoreDagService::new(MemBlockstore::default());
+    dag.add(&nodes[0]).unwrap();
+    dag.add_many(&nodes[1..]).unwrap();
+
+    for node in &nodes {
+        let got = dag.get(node.cid()).unwrap();
+        assert_eq!(got.cid(), node.cid());
+        assert_eq!(got.raw_data(), node.raw_data());
+        assert_eq!(got.links(), node.links());
+    }
+    let link = Link::new_with_cid(nodes[1].cid().clone());
+    assert_eq!(link.node(&dag).unwrap().links(), nodes[1].links());
+
+    let cids = nodes
+        .iter()
+        .rev()
+        .map(|node| node.cid().clone())
+        .collect::<Vec<_>>();
+    let got = block_on(dag.get_many(&cids).collect::<Vec<_>>());
+    assert_eq!(got.len(), 4);
+    for (result, cid) in got.into_iter().zip(&cids) {
+        assert_eq!(result.unwrap().cid(), cid);
+    }
+
+    let missing = TreeNode::new("missing", &[]);
+    assert!(matches!(
+        dag.get(missing.cid()),
+        Err(FormatError::Blockstore(BlockstoreError::NotFound(_)))
+    ));
+    let bs = dag.into_inner();
+    assert_eq!(bs.all_keys().unwrap().len(), 4);
+}
+
+#[test]
+fn test_blockstore_dag_service_get_links() {
+    let nodes = setup();
+    let dag = BlockstoreDagService::new(MemBlockstore::default());
+    dag.add_many(&nodes).unwrap();
+
+    let links = dag.get_links(nodes[0].cid()).unwrap();
+    assert_eq!(
+        links.iter().map(|link| &link.cid).collect::<Vec<_>>(),
+        vec![nodes[1].cid(), nodes[3].cid()]
+    );
+    assert!(dag.get_links(nodes[2].cid()).unwrap().is_empty());
+
+    // raw nodes have no links, they are not fetched
+    let raw = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(b"raw").into_ext());
+    assert!(dag.get_links(&raw).unwrap().is_empty());
+
+    // blocks without registered decoder can't be decoded
+    let block = BasicBlock::new_with_cid(
+        Bytes::from_static(b"git"),
+        Cid::new_v1(Codec::GitRaw, Code::Sha2_256.digest(b"git").into_ext()),
+    )
+    .unwrap();
+    let mut bs = dag.into_inner();
+    bs.put(block.clone()).unwrap();
+    let dag = BlockstoreDagService::new(bs);
+    assert!(matches!(
+        dag.get(block.cid()),
+        Err(FormatError::DecoderNotRegister(Codec::GitRaw))
+    ));
+}
+
+#[test]
+fn test_blockstore_dag_service_remove() {
+    let nodes = setup();
+    let dag = BlockstoreDagService::new(MemBlockstore::default());
+    dag.add_many(&nodes).unwrap();
+
+    dag.remove(nodes[0].cid()).unwrap();
+    assert!(dag.get(nodes[0].cid()).is_err());
+    let cids = nodes[1..3]
+        .iter()
+        .map(|node| node.cid().clone())
+        .collect::<Vec<_>>();
+    dag.remove_many(&cids).unwrap();
+    assert!(dag.get(nodes[1].cid()).is_err());
+    assert!(dag.get(nodes[2].cid()).is_err());
+    assert!(dag.get(nodes[3].cid()).is_ok());
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use block_format::Block;
+use cid::Cid;
+use futures::stream::{self, StreamExt};
+use matches::matches;
+
+use ipld_format::{
+    FormatError, NavigableIpldNode, NavigableNode, NodeGetter, NodeStream, Result, Walker,
+};
+
+use self::common::TreeNode;
+
+/// A NodeGetter recording the batches of `get_many` and the single `get`s.
+#[derive(Default)]
+struct MapGetter {
+    nodes: HashMap<Cid, TreeNode>,
+    batches: Mutex<Vec<usize>>,
+    gets: Mutex<usize>,
+}
+
+impl MapGetter {
+    fn add(&mut self, node: &TreeNode) {
+        self.nodes.insert(node.cid().clone(), node.clone());
+    }
+
+    fn find(&self, cid: &Cid) -> Result<TreeNode> {
+        self.nodes.get(cid).cloned().ok_or(FormatError::NoSuchLink)
+    }
+}
+
+impl NodeGetter<TreeNode> for MapGetter {
+    fn get(&self, cid: &Cid) -> Result<TreeNode> {
+        *self.gets.lock().unwrap() += 1;
+        self.find(cid)
+    }
+
+    fn get_many<'a>(&'a self, cids: &'a [Cid]) -> NodeStream<'a, TreeNode> {
+        self.batches.lock().unwrap().push(cids.len());
+        let nodes = cids.iter().map(|cid| self.find(cid)).collect::<Vec<_>>();
+        stream::iter(nodes).boxed()
+    }
+}
+
+type Nav = NavigableIpldNode<TreeNode, MapGetter>;
+
+fn name(node: &Arc<dyn NavigableNode>) -> String {
+    let node = Nav::extract(&**node).unwrap();
+    node.ipld_node().name().to_string()
+}
+
+/// root -> (a -> (a1, a2), b, c -> (c1, c2, c3, c4, c5))
+fn setup() -> (TreeNode, MapGetter) {
+    let a1 = TreeNode::new("a1", &[]);
+    let a2 = TreeNode::new("a2", &[]);
+    let a = TreeNode::new("a", &[&a1, &a2]);
+    let b = TreeNode::new("b", &[]);
+    let c_children = (1..=5)
+        .map(|i| TreeNode::new(&format!("c{}", i), &[]))
+        .collect::<Vec<_>>();
+    let c = TreeNode::new("c", &c_children.iter().collect::<Vec<_>>());
+    let root = TreeNode::new("root", &[&a, &b, &c]);
+
+    let mut getter = MapGetter::default();
+    for node in vec![&a1, &a2, &a, &b, &c].into_iter().chain(&c_children) {
+        getter.add(node);
+    }
+    (root, getter)
+}
+
+fn walk(root: Nav) -> Vec<String> {
+    Walker::new(Arc::new(root))
+        .map(|node| name(&node.unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_navigable_ipld_node_walk() {
+    let (root, getter) = setup();
+    let getter = Arc::new(getter);
+    let names = walk(Nav::new(root, getter.clone()));
+    assert_eq!(
+        names,
+        vec!["root", "a", "a1", "a2", "b", "c", "c1", "c2", "c3", "c4", "c5"]
+    );
+    // the children of every node are fetched in a single batch.
+    assert_eq!(*getter.batches.lock().unwrap(), vec![3, 2, 5]);
+    assert_eq!(*getter.gets.lock().unwrap(), 0);
+}
+
+#[test]
+fn test_navigable_ipld_node_preload_size() {
+    let (root, getter) = setup();
+    let getter = Arc::new(getter);
+    let names = walk(Nav::with_preload_size(root, getter.clone(), 2));
+    assert_eq!(names.len(), 11);
+    assert_eq!(*getter.batches.lock().unwrap(), vec![2, 2, 1, 2, 2, 1]);
+    assert_eq!(*getter.gets.lock().unwrap(), 0);
+
+    // no prefetch
+    let (root, getter) = setup();
+    let getter = Arc::new(getter);
+    let names = walk(Nav::with_preload_size(root, getter.clone(), 0));
+    assert_eq!(names.len(), 11);
+    assert!(getter.batches.lock().unwrap().is_empty());
+    assert_eq!(*getter.gets.lock().unwrap(), 10);
+}
+
+#[test]
+fn test_navigable_ipld_node_fetch_child() {
+    let (root, getter) = setup();
+    let getter = Arc::new(getter);
+    let root = Nav::new(root, getter.clone());
+    assert_eq!(NavigableNode::child_total(&root), 3);
+    assert!(matches!(root.fetch_child(3), Err(FormatError::NoChild(3))));
+
+    assert_eq!(name(&root.fetch_child(2).unwrap()), "c");
+    assert_eq!(name(&root.fetch_child(1).unwrap()), "b");
+    // a child handed out is fetched again.
+    assert_eq!(name(&root.fetch_child(2).unwrap()), "c");
+    assert_eq!(*getter.batches.lock().unwrap(), vec![1, 1]);
+    assert_eq!(*getter.gets.lock().unwrap(), 1);
+}
+
+#[test]
+fn test_navigable_ipld_node_fetch_error() {
+    let (root, mut getter) = setup();
+    // drop the node "b"
+    getter.nodes.retain(|_, node| node.name() != "b");
+    let getter = Arc::new(getter);
+    let results = Walker::new(Arc::new(Nav::new(root, getter)))
+        .map(|node| node.map(|node| name(&node)))
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 5);
+    assert_eq!(results[3].as_ref().unwrap(), "a2");
+    assert!(matches!(results[4], Err(FormatError::NoSuchLink)));
+}
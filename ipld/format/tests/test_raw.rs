@@ -0,0 +1,72 @@
+use block_format::{BasicBlock, Block, BlockBuilder};
+use bytes::Bytes;
+use cid::{Cid, Codec, IntoExt};
+use matches::matches;
+use multihash::Code;
+
+use ipld_format::coding::CodecRegistry;
+use ipld_format::{FormatError, Node, RawNode, Resolver};
+
+#[test]
+fn test_raw_node() {
+    let data = Bytes::from_static(b"some raw data");
+    let node = RawNode::new(data.clone());
+    let cid = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(&data).into_ext());
+    assert_eq!(node.cid(), &cid);
+    assert_eq!(node.raw_data(), &data[..]);
+    assert_eq!(node.size(), data.len() as u64);
+    assert!(node.links().is_empty());
+    assert!(node.tree("", None).is_empty());
+
+    let stat = node.stat().unwrap();
+    assert_eq!(stat.hash.as_bytes(), cid.hash().as_bytes());
+    assert_eq!(stat.num_links, 0);
+    assert_eq!(stat.block_size, data.len());
+    assert_eq!(stat.links_size, 0);
+    assert_eq!(stat.data_size, data.len());
+    assert_eq!(stat.cumulative_size, data.len());
+
+    let builder = BlockBuilder::new_v1(Codec::Raw, Code::Blake2b256.into());
+    let node = RawNode::new_with_builder(data.clone(), &builder).unwrap();
+    assert_eq!(node.cid(), &builder.cid(&data).unwrap());
+    assert!(matches!(
+        RawNode::new_with_builder(data, &BlockBuilder::new()),
+        Err(FormatError::NotRawBlock(Codec::DagProtobuf))
+    ));
+}
+
+#[test]
+fn test_raw_resolve() {
+    let node = RawNode::new(Bytes::from_static(b"data"));
+    let (data, rest) = node.resolve(&[]).unwrap();
+    assert_eq!(data, Bytes::from_static(b"data"));
+    assert!(rest.is_empty());
+    assert!(matches!(node.resolve(&["a"]), Err(FormatError::NoSuchLink)));
+    assert!(matches!(
+        node.resolve_link(&["a"]),
+        Err(FormatError::NoSuchLink)
+    ));
+}
+
+#[test]
+fn test_raw_from_block() {
+    let data = Bytes::from_static(b"leaf");
+    let cid = Cid::new_v1(Codec::Raw, Code::Sha2_256.digest(&data).into_ext());
+    let block = BasicBlock::new_with_cid(data.clone(), cid.clone()).unwrap();
+    let node = RawNode::from_block(&block).unwrap();
+    assert_eq!(node.cid(), &cid);
+    assert_eq!(node.block().raw_data(), &data[..]);
+
+    // identity data
+    let id = Cid::new_v1(Codec::Raw, multihash::Identity::digest(b"id").into_ext());
+    let block = BasicBlock::new_with_cid(Bytes::from_static(b"id"), id.clone()).unwrap();
+    let node = CodecRegistry::with_defaults().decode(&block).unwrap();
+    assert_eq!(node.cid(), &id);
+    assert_eq!(node.stat().unwrap().data_size, 2);
+
+    let block = BasicBlock::new(data);
+    assert!(matches!(
+        RawNode::from_block(&block),
+        Err(FormatError::NotRawBlock(Codec::DagProtobuf))
+    ));
+}
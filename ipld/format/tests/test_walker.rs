@@ -2,10 +2,54 @@ mod common;
 
 use std::sync::Arc;
 
-use ipld_format::Walker;
+use matches::matches;
+
+use ipld_format::{FormatError, NavigableNode, Result, Walker};
 
 use self::common::{EmptyNode, N};
 
+/// A node whose children can't be fetched.
+struct Broken;
+
+impl NavigableNode for Broken {
+    fn child_total(&self) -> usize {
+        1
+    }
+
+    fn fetch_child(&self, _child_index: usize) -> Result<Arc<dyn NavigableNode>> {
+        Err(FormatError::NoSuchLink)
+    }
+}
+
+fn leaf() -> Arc<dyn NavigableNode> {
+    Arc::new(N {
+        inner: EmptyNode::new(),
+        child: vec![],
+    })
+}
+
+fn node(child: Vec<Arc<dyn NavigableNode>>) -> Arc<dyn NavigableNode> {
+    Arc::new(N {
+        inner: EmptyNode::new(),
+        child,
+    })
+}
+
+fn addr(node: &Arc<dyn NavigableNode>) -> usize {
+    &**node as *const dyn NavigableNode as *const () as usize
+}
+
+/// root -> (n1 -> n1_1, n2 -> (n2_1, n2_2)), in pre-order.
+fn dag() -> Vec<Arc<dyn NavigableNode>> {
+    let n2_2 = leaf();
+    let n2_1 = leaf();
+    let n2 = node(vec![n2_1.clone(), n2_2.clone()]);
+    let n1_1 = leaf();
+    let n1 = node(vec![n1_1.clone()]);
+    let root = node(vec![n1.clone(), n2.clone()]);
+    vec![root, n1, n1_1, n2, n2_1, n2_2]
+}
+
 #[test]
 fn test_walker() {
     let n2_2 = Arc::new(N {
@@ -59,3 +103,135 @@ fn test_walker() {
     let counter = Walker::new(root).count();
     assert_eq!(counter, 5);
 }
+
+#[test]
+fn test_walker_iterator_order() {
+    let nodes = dag();
+    let visited = Walker::new(nodes[0].clone())
+        .map(|node| addr(&node.unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(visited, nodes.iter().map(addr).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_walker_navigation() {
+    let nodes = dag();
+    let mut walker = Walker::new(nodes[0].clone());
+    assert!(matches!(
+        walker.active_node(),
+        Err(FormatError::DepthNotInit)
+    ));
+    assert!(matches!(walker.up(), Err(FormatError::DepthNotInit)));
+
+    walker.down().unwrap();
+    assert_eq!(addr(walker.active_node().unwrap()), addr(&nodes[0]));
+    assert_eq!(walker.depth().unwrap(), 0);
+    assert!(matches!(walker.up(), Err(FormatError::UpOnRoot)));
+
+    walker.next_child().unwrap();
+    assert_eq!(walker.active_child_index().unwrap(), 1);
+    assert!(matches!(walker.next_child(), Err(FormatError::NextNoChild)));
+
+    walker.down().unwrap();
+    assert_eq!(addr(walker.active_node().unwrap()), addr(&nodes[3]));
+    walker.next_child().unwrap();
+    walker.down().unwrap();
+    assert_eq!(addr(walker.active_node().unwrap()), addr(&nodes[5]));
+    assert_eq!(walker.depth().unwrap(), 2);
+    assert_eq!(walker.path().len(), 3);
+    assert!(matches!(
+        walker.down(),
+        Err(FormatError::DownNoChild(2, 0, 0))
+    ));
+
+    walker.up().unwrap();
+    walker.up().unwrap();
+    assert_eq!(addr(walker.active_node().unwrap()), addr(&nodes[0]));
+    assert_eq!(walker.active_child_index().unwrap(), 1);
+}
+
+#[test]
+fn test_walker_iterate() {
+    let nodes = dag();
+    let mut walker = Walker::new(nodes[0].clone());
+    let mut visited = vec![];
+    let r = walker.iterate(|walker| {
+        visited.push(addr(walker.active_node()?));
+        Ok(())
+    });
+    assert!(matches!(r, Err(FormatError::EndOfDag)));
+    assert_eq!(visited, nodes.iter().map(addr).collect::<Vec<_>>());
+    // the walk is over
+    assert!(matches!(
+        walker.iterate(|_| Ok(())),
+        Err(FormatError::EndOfDag)
+    ));
+}
+
+#[test]
+fn test_walker_pause_and_end() {
+    let nodes = dag();
+    let mut walker = Walker::new(nodes[0].clone());
+    let mut visited = vec![];
+    walker
+        .iterate(|walker| {
+            visited.push(addr(walker.active_node()?));
+            if visited.len() == 3 {
+                walker.pause();
+            }
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(addr(walker.active_node().unwrap()), addr(&nodes[2]));
+
+    // resume from the active node
+    let r = walker.iterate(|walker| {
+        visited.push(addr(walker.active_node()?));
+        if visited.len() == 5 {
+            walker.end();
+        }
+        Ok(())
+    });
+    assert!(matches!(r, Err(FormatError::EndOfDag)));
+    assert_eq!(visited, nodes[..5].iter().map(addr).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_walker_seek() {
+    let nodes = dag();
+    let mut walker = Walker::new(nodes[0].clone());
+    // seek the last child of the last child
+    walker
+        .seek(|walker| {
+            while walker.next_child().is_ok() {}
+            if walker.depth()? == 2 {
+                walker.pause();
+            }
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(addr(walker.active_node().unwrap()), addr(&nodes[5]));
+
+    // the seek stops on a node without children
+    let mut walker = Walker::new(nodes[0].clone());
+    walker.seek(|_| Ok(())).unwrap();
+    assert_eq!(addr(walker.active_node().unwrap()), addr(&nodes[2]));
+}
+
+#[test]
+fn test_walker_fetch_child_error() {
+    let root = node(vec![leaf(), Arc::new(Broken)]);
+    let results = Walker::new(root.clone()).collect::<Vec<_>>();
+    assert_eq!(results.len(), 4);
+    assert!(results[..3].iter().all(|r| r.is_ok()));
+    assert!(matches!(results[3], Err(FormatError::NoSuchLink)));
+
+    let mut walker = Walker::new(root);
+    let mut count = 0;
+    let r = walker.iterate(|_| {
+        count += 1;
+        Ok(())
+    });
+    assert!(matches!(r, Err(FormatError::NoSuchLink)));
+    assert_eq!(count, 3);
+}
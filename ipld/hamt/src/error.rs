@@ -16,12 +16,15 @@ pub enum Error {
     #[error("reach hash buf max depth, attempted to traverse hamt beyond max depth")]
     MaxDepth,
 
-    #[error("not found for key: {0}")]
-    NotFound(String),
+    #[error("not found for key: {0:?}")]
+    NotFound(Vec<u8>),
 
     #[error("incorrectly formed HAMT, corrupted some where")]
     InvalidFormatHAMT,
 
+    #[error("cid mismatch, expected: {expected}, found: {found}")]
+    CidMismatch { expected: Cid, found: Cid },
+
     #[error("other err: {0}")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
@@ -2,35 +2,68 @@
 
 use std::cmp::Ordering;
 
-/// ```go
-/// func (d *digest64) Sum64() uint64 {
-///     h1, _ = (*digest128)(d).Sum128()
-///     return h1
-/// }
-/// ```
-/// murmur3 hash for a bytes value. using hash128 but just pick half for result
-#[cfg(not(feature = "test-hash"))]
-pub fn hash<T: AsRef<[u8]>>(v: T) -> [u8; 8] {
-    let result =
-        murmur3::murmur3_x64_128(&mut v.as_ref(), 0).expect("murmur3 hash shouldn't be fail");
-    // to big-ending sequence
-    let all: [u8; 16] = result.to_be_bytes();
-    // digest64 is half a digest128.
-    let mut bytes = [0_u8; 8];
-    // drop other half
-    bytes.copy_from_slice(&all[8..]);
-    bytes
+/// A hash function of the keys of `Hamt`, the bits of the digest index the nodes from
+/// the root, `bit_width` bits for each level.
+///
+/// The hash function isn't recorded in the blocks, a `Hamt` must be loaded with the one it was
+/// built with. `Box<dyn HashAlgorithm>` could be used to choose it at runtime.
+pub trait HashAlgorithm {
+    /// hash the bytes of the key.
+    fn hash(&self, key: &[u8]) -> Vec<u8>;
+}
+
+impl<H: HashAlgorithm + ?Sized> HashAlgorithm for Box<H> {
+    fn hash(&self, key: &[u8]) -> Vec<u8> {
+        (**self).hash(key)
+    }
+}
+
+/// murmur3 hash of the go-hamt-ipld v1 format.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Murmur3;
+
+impl HashAlgorithm for Murmur3 {
+    /// ```go
+    /// func (d *digest64) Sum64() uint64 {
+    ///     h1, _ = (*digest128)(d).Sum128()
+    ///     return h1
+    /// }
+    /// ```
+    /// murmur3 hash for a bytes value. using hash128 but just pick half for result
+    fn hash(&self, mut key: &[u8]) -> Vec<u8> {
+        let result = murmur3::murmur3_x64_128(&mut key, 0).expect("murmur3 hash shouldn't be fail");
+        // to big-ending sequence
+        let all: [u8; 16] = result.to_be_bytes();
+        // digest64 is half a digest128, drop other half
+        all[8..].to_vec()
+    }
+}
+
+/// sha256 hash of the go-hamt-ipld v2 format, used by Filecoin.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Sha256;
+
+impl HashAlgorithm for Sha256 {
+    fn hash(&self, key: &[u8]) -> Vec<u8> {
+        multihash::Sha2_256::digest(key).digest().to_vec()
+    }
 }
 
 /// replace hash function. jus for testing
 /// `identityHash` just copy v to output
-#[cfg(feature = "test-hash")]
-pub fn hash<T: AsRef<[u8]>>(v: T) -> [u8; 32] {
-    let mut bytes = [0_u8; 32];
-    for (index, byte) in v.as_ref().iter().take(32).enumerate() {
-        bytes[index] = *byte;
+#[cfg(test)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Identity;
+
+#[cfg(test)]
+impl HashAlgorithm for Identity {
+    fn hash(&self, key: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0_u8; 32];
+        for (index, byte) in key.iter().take(32).enumerate() {
+            bytes[index] = *byte;
+        }
+        bytes
     }
-    bytes
 }
 
 /// hashBits is a helper that allows the reading of the 'next n bits' as an integer.
@@ -4,6 +4,8 @@ use block_format::Block as BlockT;
 use cid::{Cid, Codec};
 use serde::{de::DeserializeOwned, Serialize};
 
+use ipld_core::{dag_cbor, from_ipld, IpldNode};
+
 use crate::error::*;
 
 pub trait Blockstore {
@@ -12,26 +14,14 @@ pub trait Blockstore {
 }
 
 pub trait CborIpldStore {
+    /// Gets the object of the CID, decoded from DAG-CBOR.
     fn get<T: DeserializeOwned>(&self, c: &Cid) -> Result<T>;
-    fn put<T: Serialize + HasCid>(&mut self, v: T) -> Result<Cid>;
-}
 
-/// A trait that represents whether a CID exists.
-pub trait HasCid {
-    /// Whether a CID exists.
-    fn has_cid(&self) -> Option<&Cid>;
-}
-
-impl<T> HasCid for T {
-    default fn has_cid(&self) -> Option<&Cid> {
-        None
-    }
-}
-
-impl<T: AsRef<Cid>> HasCid for T {
-    fn has_cid(&self) -> Option<&Cid> {
-        Some(self.as_ref())
-    }
+    /// Puts the object encoded in DAG-CBOR and returns its CID.
+    ///
+    /// If the CID of the object is expected, the new CID is built with the same codec and
+    /// hash, and must be equal to it; otherwise it's a CIDv1 hashed with Blake2b-256.
+    fn put<T: Serialize>(&mut self, v: T, expected: Option<&Cid>) -> Result<Cid>;
 }
 
 #[derive(Debug)]
@@ -45,36 +35,33 @@ impl<B: Blockstore> BasicCborIpldStore<B> {
         BasicCborIpldStore { blocks: b }
     }
 }
+
 impl<B: Blockstore> CborIpldStore for BasicCborIpldStore<B> {
     fn get<T: DeserializeOwned>(&self, c: &Cid) -> Result<T> {
         let blk = self.blocks.get(c)?;
-        let data = (*blk).raw_data();
-        let r = ipld_core::decode_into(data)?;
+        let value = dag_cbor::decode((*blk).raw_data())?;
+        let r = from_ipld(value)?;
         Ok(r)
     }
 
-    fn put<T: Serialize + HasCid>(&mut self, v: T) -> Result<Cid> {
-        let mut hash_type = multihash::Code::Blake2b256;
-        let mut codec = Codec::DagCBOR;
-
-        // if this type has cid, would use this cid config
-        let exp_cid_hash = if let Some(cid) = v.has_cid() {
-            let perf = cid.prefix();
-            hash_type = perf.mh_type;
-            codec = perf.codec;
-            Some(cid.hash().to_owned())
-        } else {
-            None
+    fn put<T: Serialize>(&mut self, v: T, expected: Option<&Cid>) -> Result<Cid> {
+        // if the cid is expected, would use its config
+        let (hash_type, codec) = match expected {
+            Some(cid) => (cid.hash().algorithm(), cid.codec()),
+            None => (multihash::Code::Blake2b256.into(), Codec::DagCBOR),
         };
 
-        let node = ipld_core::IpldNode::from_object_with_codec(v, hash_type, codec)?;
+        let node = IpldNode::from_object_with_codec(&v, hash_type, codec)?;
         let cid = node.cid().clone(); // this cid is calc from node
-        self.blocks.put(node)?;
-
-        if let Some(hash) = exp_cid_hash {
-            // if has expected cid, then this expected hash
-            assert_eq!(hash, cid.hash());
+        if let Some(expected) = expected {
+            if *expected != cid {
+                return Err(Error::CidMismatch {
+                    expected: expected.clone(),
+                    found: cid,
+                });
+            }
         }
+        self.blocks.put(node)?;
 
         Ok(cid)
     }
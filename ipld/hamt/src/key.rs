@@ -0,0 +1,40 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::borrow::Borrow;
+
+use serde::{Deserialize, Serialize};
+
+/// A binary key of `Hamt`, serialized as a CBOR byte string like in go-hamt-ipld v2.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BytesKey(#[serde(with = "serde_bytes")] pub Vec<u8>);
+
+impl AsRef<[u8]> for BytesKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Borrow<[u8]> for BytesKey {
+    fn borrow(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for BytesKey {
+    fn from(bytes: Vec<u8>) -> Self {
+        BytesKey(bytes)
+    }
+}
+
+impl From<&[u8]> for BytesKey {
+    fn from(bytes: &[u8]) -> Self {
+        BytesKey(bytes.to_vec())
+    }
+}
+
+impl From<&str> for BytesKey {
+    fn from(s: &str) -> Self {
+        BytesKey(s.as_bytes().to_vec())
+    }
+}
@@ -3,14 +3,16 @@
 //! A implementation of `ipld hamt` in Rust.
 
 #![allow(clippy::bool_comparison, clippy::type_complexity, clippy::or_fun_call)]
-#![feature(specialization)]
 
 mod error;
 mod hash;
 mod ipld;
+mod key;
 pub mod node;
 #[cfg(test)]
 mod tests;
 
-pub use self::ipld::{cst_from_bstore, BasicCborIpldStore, Blockstore, CborIpldStore, HasCid};
-pub use self::node::{Hamt, DEFAULT_BIT_WIDTH};
+pub use self::hash::{HashAlgorithm, Murmur3, Sha256};
+pub use self::ipld::{cst_from_bstore, BasicCborIpldStore, Blockstore, CborIpldStore};
+pub use self::key::BytesKey;
+pub use self::node::{diff, Change, Hamt, Iter, Order, DEFAULT_BIT_WIDTH};
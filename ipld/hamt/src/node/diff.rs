@@ -0,0 +1,153 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bigint::U256;
+use cid::Cid;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{Item, Node, KV};
+use crate::error::*;
+use crate::ipld::CborIpldStore;
+
+/// A change of a key between two roots of a `Hamt`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<K, V> {
+    /// the key is only in the new root.
+    Add { key: K, value: V },
+    /// the key is only in the old root.
+    Remove { key: K, value: V },
+    /// the key is in both roots, with different values.
+    Modify { key: K, before: V, after: V },
+}
+
+impl<K, V> Change<K, V> {
+    /// the changed key.
+    pub fn key(&self) -> &K {
+        match self {
+            Change::Add { key, .. } | Change::Remove { key, .. } | Change::Modify { key, .. } => {
+                key
+            }
+        }
+    }
+}
+
+/// Returns the changes from the `Hamt` of `old_root` to the one of `new_root`, in key order.
+///
+/// The two trees are walked side by side, the subtrees with the same cid are equal and
+/// skipped without being loaded. When a leaf is replaced by a subtree, or the other way
+/// around, all key/values of the subtree are loaded to be compared with the leaf.
+pub fn diff<K, V, B>(bs: &B, old_root: &Cid, new_root: &Cid) -> Result<Vec<Change<K, V>>>
+where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + PartialEq + Serialize + DeserializeOwned,
+    B: CborIpldStore,
+{
+    let mut changes = vec![];
+    if old_root != new_root {
+        let old: Node<K, V> = bs.get(old_root)?;
+        diff_node(bs, old, bs.get(new_root)?, &mut changes)?;
+    }
+    changes.sort_by(|a, b| a.key().cmp(b.key()));
+    Ok(changes)
+}
+
+/// the items of the node indexed by their bit position.
+fn indexed_items<K, V>(node: Node<K, V>) -> BTreeMap<usize, Item<K, V>> {
+    let bitfield = node.bitfield;
+    let max_bits = std::mem::size_of::<U256>() * 8;
+    (0..max_bits)
+        .filter(|i| bitfield.bit(*i))
+        .zip(node.items.into_inner())
+        .collect()
+}
+
+fn diff_node<K, V, B>(
+    bs: &B,
+    old: Node<K, V>,
+    new: Node<K, V>,
+    changes: &mut Vec<Change<K, V>>,
+) -> Result<()>
+where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + PartialEq + Serialize + DeserializeOwned,
+    B: CborIpldStore,
+{
+    let mut old = indexed_items(old);
+    let mut new = indexed_items(new);
+    let indexes = old
+        .keys()
+        .chain(new.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    for index in indexes {
+        match (old.remove(&index), new.remove(&index)) {
+            (Some(Item::Link(old)), Some(Item::Link(new))) => {
+                // the subtrees are equal if the cids are the same
+                if old != new {
+                    let old: Node<K, V> = bs.get(&old)?;
+                    diff_node(bs, old, bs.get(&new)?, changes)?;
+                }
+            }
+            (old, new) => {
+                let mut before = KV::new();
+                if let Some(item) = old {
+                    collect_kvs(bs, item, &mut before)?;
+                }
+                let mut after = KV::new();
+                if let Some(item) = new {
+                    collect_kvs(bs, item, &mut after)?;
+                }
+                diff_kvs(before, after, changes);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// collect all key/values of the item, loading the links.
+fn collect_kvs<K, V, B>(bs: &B, item: Item<K, V>, kvs: &mut KV<K, V>) -> Result<()>
+where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+    B: CborIpldStore,
+{
+    let node = match item {
+        Item::Leaf(leaf) => {
+            kvs.extend(leaf);
+            return Ok(());
+        }
+        Item::Link(cid) => bs.get(&cid)?,
+        Item::Ptr(node) => *node,
+    };
+    for item in node.items.into_inner() {
+        collect_kvs(bs, item, kvs)?;
+    }
+    Ok(())
+}
+
+fn diff_kvs<K: Ord, V: PartialEq>(
+    mut before: KV<K, V>,
+    after: KV<K, V>,
+    changes: &mut Vec<Change<K, V>>,
+) {
+    for (key, value) in after {
+        match before.remove(&key) {
+            None => changes.push(Change::Add { key, value }),
+            Some(old) => {
+                if old != value {
+                    changes.push(Change::Modify {
+                        key,
+                        before: old,
+                        after: value,
+                    })
+                }
+            }
+        }
+    }
+    changes.extend(
+        before
+            .into_iter()
+            .map(|(key, value)| Change::Remove { key, value }),
+    );
+}
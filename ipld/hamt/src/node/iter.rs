@@ -0,0 +1,88 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+use std::collections::btree_map;
+use std::slice;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{Item, Node, KV};
+use crate::error::*;
+use crate::ipld::CborIpldStore;
+
+/// The order of the traversal of the key/values in `Hamt::for_each_ordered`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// the order of the hashes of the keys, which is the order of the tree.
+    /// the keys of a leaf, which share the bits of the hash at its depth, are in key order.
+    Hash,
+    /// the order of the keys, the key/values of the whole tree are collected first.
+    Key,
+}
+
+/// Iterator over the key/values of a `Hamt` in hash order.
+///
+/// `Link` items are loaded from the store when they're reached, and replaced by their
+/// node like in `Hamt::find`, so that a following traversal doesn't load them again.
+pub struct Iter<'a, K, V, B>
+where
+    B: CborIpldStore,
+{
+    bs: &'a B,
+    /// the items left of every node from the root to the current one.
+    stack: Vec<slice::IterMut<'a, Item<K, V>>>,
+    /// the key/values left of the current leaf.
+    leaf: Option<btree_map::Iter<'a, K, V>>,
+}
+
+impl<'a, K, V, B> Iter<'a, K, V, B>
+where
+    B: CborIpldStore,
+{
+    pub(crate) fn new(bs: &'a B, root: &'a mut Node<K, V>) -> Self {
+        Iter {
+            bs,
+            stack: vec![root.items.get_mut().iter_mut()],
+            leaf: None,
+        }
+    }
+}
+
+impl<'a, K, V, B> Iterator for Iter<'a, K, V, B>
+where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+    B: CborIpldStore,
+{
+    type Item = Result<(&'a K, &'a V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((k, v)) = self.leaf.as_mut().and_then(|leaf| leaf.next()) {
+                return Some(Ok((k, v)));
+            }
+            self.leaf = None;
+
+            let item = match self.stack.last_mut()?.next() {
+                Some(item) => item,
+                None => {
+                    // all items of the current node are visited, back to the parent
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            if let Err(e) = item.load_item(self.bs) {
+                // the traversal can't go on, stop after returning the error
+                self.stack.clear();
+                return Some(Err(e));
+            }
+            match item {
+                Item::Link(_) => unreachable!("after `load_item`, should not be Link now"),
+                Item::Ptr(node) => self.stack.push(node.items.get_mut().iter_mut()),
+                Item::Leaf(kvs) => {
+                    let kvs: &'a KV<K, V> = kvs;
+                    self.leaf = Some(kvs.iter());
+                }
+            }
+        }
+    }
+}
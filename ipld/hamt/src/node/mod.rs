@@ -1,60 +1,71 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
+mod diff;
+mod iter;
 pub mod trait_impl;
 
+use std::borrow::Borrow;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 
 use bigint::U256;
 use cid::Cid;
-use ipld_cbor::{cbor_value_to_struct, struct_to_cbor_value};
 use serde::{de::DeserializeOwned, Serialize};
-use serde_cbor::Value;
 
 use crate::error::*;
-use crate::hash::{hash, HashBits};
+use crate::hash::{HashAlgorithm, HashBits, Murmur3};
 use crate::ipld::CborIpldStore;
 
+pub use self::diff::{diff, Change};
+pub use self::iter::{Iter, Order};
+
 const ARRAY_WIDTH: usize = 3;
 pub const DEFAULT_BIT_WIDTH: u32 = 8;
 
 /// Hamt struct, hold root node. for public, we use `Hamt`, not `Node`
 /// current `Hamt` is not thread safe, if want to use `Hamt` is multi thread, must use
 /// lock to wrap `Hamt`
-pub struct Hamt<B>
+///
+/// The keys are indexed by the hash `H` of their bytes, and are serialized as they are, e.g.
+/// `String` keys are CBOR text strings like in go-hamt-ipld v1, and `BytesKey` keys are
+/// CBOR byte strings like in go-hamt-ipld v2.
+pub struct Hamt<K, V, H, B>
 where
+    H: HashAlgorithm,
     B: CborIpldStore,
 {
     /// root node of `Hamt`
-    root: Node,
+    root: Node<K, V>,
     /// database to store the relationship of cid and node
     bs: B,
+    /// hash function of the keys
+    hasher: H,
     bit_width: u32,
 }
 
-pub type KV = BTreeMap<String, Value>;
-pub type KVT = (String, Value);
+pub type KV<K, V> = BTreeMap<K, V>;
+pub type KVT<K, V> = (K, V);
 
 /// Item would be `Link` `Ptr` and `Leaf`, but in factor, `Ptr` is the cache of `Link`.
 /// when call `load_item`, the `Link` would convert to `Ptr`.
 /// when call `flush`, the `Ptr` would refresh the `Link`
 /// when serialize/deserialize, should not serialize `Ptr`, otherwise would panic.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 #[cfg_attr(test, derive(Clone))]
-pub enum Item {
+pub enum Item<K, V> {
     Link(Cid),
-    Ptr(Box<Node>),
-    Leaf(KV),
+    Ptr(Box<Node<K, V>>),
+    Leaf(KV<K, V>),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 #[cfg_attr(test, derive(Clone))]
-pub struct Node {
+pub struct Node<K, V> {
     /// bitmap, we use U256 replace bigint, for we think the bit_width and HashBits couldn't
     /// more then 256bit
     bitfield: U256,
     /// `Item` is wrapped by `Refcell` due to items would load in immutable `get` call.
-    items: RefCell<Vec<Item>>,
+    items: RefCell<Vec<Item<K, V>>>,
 }
 
 #[inline]
@@ -78,79 +89,141 @@ pub fn bit_to_index(bitmap: &U256, bit_pos: u32) -> usize {
     r.0.iter().fold(0, |a, b| a + b.count_ones() as usize)
 }
 
-impl<B> Hamt<B>
+impl<K, V, B> Hamt<K, V, Murmur3, B>
 where
+    K: AsRef<[u8]> + Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
     B: CborIpldStore,
 {
-    /// create a new empty Hamt with bit_width
+    /// create a new empty Hamt with bit_width, hashed with murmur3 like go-hamt-ipld v1
     pub fn new_with_bitwidth(store: B, bit_width: u32) -> Self {
-        Hamt {
-            root: Node::new(),
-            bs: store,
-            bit_width,
-        }
+        Self::new_with_hash(store, Murmur3, bit_width)
     }
 
-    /// create a new empty Hamt
+    /// create a new empty Hamt, hashed with murmur3 like go-hamt-ipld v1
     pub fn new(store: B) -> Self {
         Self::new_with_bitwidth(store, DEFAULT_BIT_WIDTH)
     }
 
-    /// load Hamt from cid with bitwidth
+    /// load Hamt from cid with bitwidth, hashed with murmur3 like go-hamt-ipld v1
     pub fn load_with_bitwidth(store: B, cid: &Cid, bit_width: u32) -> Result<Self> {
-        let root: Node = store.get(cid)?;
+        Self::load_with_hash(store, cid, Murmur3, bit_width)
+    }
+
+    /// load Hamt from cid, hashed with murmur3 like go-hamt-ipld v1
+    pub fn load(store: B, cid: &Cid) -> Result<Self> {
+        Self::load_with_bitwidth(store, cid, DEFAULT_BIT_WIDTH)
+    }
+}
+
+impl<K, V, H, B> Hamt<K, V, H, B>
+where
+    K: AsRef<[u8]> + Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+    H: HashAlgorithm,
+    B: CborIpldStore,
+{
+    /// create a new empty Hamt with the hash function and bit_width
+    pub fn new_with_hash(store: B, hasher: H, bit_width: u32) -> Self {
+        Hamt {
+            root: Node::new(),
+            bs: store,
+            hasher,
+            bit_width,
+        }
+    }
+
+    /// load Hamt from cid with the hash function and bit_width it was built with
+    pub fn load_with_hash(store: B, cid: &Cid, hasher: H, bit_width: u32) -> Result<Self> {
+        let root: Node<K, V> = store.get(cid)?;
         Ok(Hamt {
             root,
             bs: store,
+            hasher,
             bit_width,
         })
     }
 
-    /// load Hamt from cid
-    pub fn load(store: B, cid: &Cid) -> Result<Self> {
-        Self::load_with_bitwidth(store, cid, DEFAULT_BIT_WIDTH)
-    }
-
     pub fn bit_width(&self) -> u32 {
         self.bit_width
     }
 
-    pub fn root(&self) -> &Node {
+    pub fn root(&self) -> &Node<K, V> {
         &self.root
     }
 
     /// get a value for k, if not find, would return Error::NotFound
-    pub fn find<Output: DeserializeOwned>(&self, k: &str) -> Result<Output> {
-        let hash = hash(k);
+    pub fn find<Q>(&self, k: &Q) -> Result<V>
+    where
+        K: Borrow<Q>,
+        Q: AsRef<[u8]> + Ord + ?Sized,
+    {
+        let hash = self.hasher.hash(k.as_ref());
         let mut hash_bits = HashBits::new(hash.as_ref(), self.bit_width);
-        let v = self.root.get(&self.bs, &mut hash_bits, k, |v| {
-            cbor_value_to_struct(v.clone()).map_err(Error::IpldCbor)
-        })?;
-        Ok(v)
+        self.root
+            .get(&self.bs, &mut hash_bits, k, |v| Ok(v.clone()))
     }
 
     /// set a value for k, if the value is already exist, override it.
-    pub fn set<V: Serialize>(&mut self, k: &str, v: V) -> Result<()> {
-        let hash = hash(k);
+    pub fn set(&mut self, k: K, v: V) -> Result<()> {
+        let hash = self.hasher.hash(k.as_ref());
         let mut hash_bits = HashBits::new(hash.as_ref(), self.bit_width);
-        let b = struct_to_cbor_value(&v).map_err(Error::IpldCbor)?;
-        self.root.set(&self.bs, &mut hash_bits, k, b)
+        self.root.set(&self.bs, &self.hasher, &mut hash_bits, k, v)
     }
 
     /// delete for k, if the k is not exist, return Error::NotFound
-    pub fn delete(&mut self, k: &str) -> Result<()> {
-        let hash = hash(k);
+    pub fn delete<Q>(&mut self, k: &Q) -> Result<()>
+    where
+        K: Borrow<Q>,
+        Q: AsRef<[u8]> + Ord + ?Sized,
+    {
+        let hash = self.hasher.hash(k.as_ref());
         let mut hash_bits = HashBits::new(hash.as_ref(), self.bit_width);
         self.root.remove(&self.bs, &mut hash_bits, k)
     }
 
+    /// iterate the key/values in hash order, the `Link` items are loaded when they're reached.
+    pub fn iter(&mut self) -> Iter<'_, K, V, B> {
+        Iter::new(&self.bs, &mut self.root)
+    }
+
+    /// call `f` for every key/value in hash order, stop at the first error.
+    pub fn for_each<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnMut(&K, &V) -> Result<()>,
+    {
+        self.for_each_ordered(Order::Hash, f)
+    }
+
+    /// call `f` for every key/value in the order, stop at the first error.
+    pub fn for_each_ordered<F>(&mut self, order: Order, mut f: F) -> Result<()>
+    where
+        F: FnMut(&K, &V) -> Result<()>,
+    {
+        match order {
+            Order::Hash => {
+                for kv in self.iter() {
+                    let (k, v) = kv?;
+                    f(k, v)?;
+                }
+            }
+            Order::Key => {
+                let kvs = self.iter().collect::<Result<BTreeMap<_, _>>>()?;
+                for (k, v) in kvs {
+                    f(k, v)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// flush all `Ptr` into `Link`, every flush should treat as `Commit`,
     /// means commit current all changes into database, and generate changed cids.
     /// the operation equals to persistence. if store the root cid, then the Hamt is immutable,
     /// could recover all child status from any root cid.
     pub fn flush(&mut self) -> Result<Cid> {
         self.root.flush(&mut self.bs)?;
-        self.bs.put(&self.root)
+        self.bs.put(&self.root, None)
     }
 
     /// just for test
@@ -161,21 +234,27 @@ where
     }
 }
 
-impl Item {
-    pub fn from_kvs(kvs: Vec<KVT>) -> Self {
+impl<K: Ord, V> Item<K, V> {
+    pub fn from_kvs(kvs: Vec<KVT<K, V>>) -> Self {
         Item::Leaf(kvs.into_iter().collect())
     }
 
     pub fn from_link(cid: Cid) -> Self {
         Item::Link(cid)
     }
+}
 
+impl<K, V> Item<K, V>
+where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+{
     fn load_item<B>(&mut self, bs: &B) -> Result<()>
     where
         B: CborIpldStore,
     {
         if let Item::Link(cid) = self {
-            let node: Node = bs.get(cid)?;
+            let node: Node<K, V> = bs.get(cid)?;
             *self = Item::Ptr(Box::new(node));
         }
         Ok(())
@@ -243,11 +322,11 @@ impl Item {
 }
 
 #[cfg(test)]
-pub fn test_node(bitfield: &str, items: Vec<Item>) -> Node {
+pub fn test_node<K, V>(bitfield: &str, items: Vec<Item<K, V>>) -> Node<K, V> {
     Node::from_raw(U256::from_dec_str(bitfield).unwrap(), items)
 }
 
-impl Node {
+impl<K, V> Node<K, V> {
     fn new() -> Self {
         Node {
             bitfield: U256::zero(),
@@ -255,27 +334,35 @@ impl Node {
         }
     }
 
-    fn from_raw(bitfield: U256, items: Vec<Item>) -> Self {
+    fn from_raw(bitfield: U256, items: Vec<Item<K, V>>) -> Self {
         Node {
             bitfield,
             items: RefCell::new(items),
         }
     }
+}
 
-    fn get<'hash, B, F, Output>(
+impl<K, V> Node<K, V>
+where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+{
+    fn get<'hash, B, Q, F, Output>(
         &self,
         bs: &B,
         hv: &mut HashBits<'hash>,
-        k: &str,
+        k: &Q,
         f: F,
     ) -> Result<Output>
     where
         B: CborIpldStore,
-        F: Fn(&Value) -> Result<Output>,
+        K: Borrow<Q>,
+        Q: AsRef<[u8]> + Ord + ?Sized,
+        F: Fn(&V) -> Result<Output>,
     {
         let idx = hv.next().ok_or(Error::MaxDepth)?;
         if self.bitfield.bit(idx as usize) == false {
-            return Err(Error::NotFound(k.to_string()));
+            return Err(Error::NotFound(k.as_ref().to_vec()));
         }
         let child_index = bit_to_index(&self.bitfield, idx);
         // load_item first
@@ -288,14 +375,23 @@ impl Node {
             Item::Ptr(node) => node.get(bs, hv, k, f),
             Item::Leaf(kvs) => kvs
                 .get(k)
-                .ok_or(Error::NotFound(k.to_string()))
+                .ok_or(Error::NotFound(k.as_ref().to_vec()))
                 .and_then(|v| f(v)),
         }
     }
 
-    fn set<'hash, B>(&mut self, bs: &B, hv: &mut HashBits<'hash>, k: &str, v: Value) -> Result<()>
+    fn set<'hash, B, H>(
+        &mut self,
+        bs: &B,
+        hasher: &H,
+        hv: &mut HashBits<'hash>,
+        k: K,
+        v: V,
+    ) -> Result<()>
     where
         B: CborIpldStore,
+        H: HashAlgorithm,
+        K: AsRef<[u8]>,
     {
         let idx = hv.next().ok_or(Error::MaxDepth)?;
         if self.bitfield.bit(idx as usize) == false {
@@ -313,10 +409,10 @@ impl Node {
             Item::Link(_) => unreachable!("after `load_item`, should not be Link now"),
             Item::Ptr(node) => {
                 // it's branch, recurse to fetch child
-                node.set(bs, hv, k, v)
+                node.set(bs, hasher, hv, k, v)
             }
             Item::Leaf(kvs) => {
-                let leaf_item = kvs.get_mut(k);
+                let leaf_item = kvs.get_mut(&k);
                 if let Some(old_v) = leaf_item {
                     // find item for this key, reset to new value
                     *old_v = v;
@@ -325,7 +421,7 @@ impl Node {
 
                 // a new key/value, if not beyond leaf capacity, insert it directly
                 if kvs.len() < ARRAY_WIDTH {
-                    kvs.insert(k.to_string(), v);
+                    kvs.insert(k, v);
                     return Ok(());
                 }
 
@@ -333,15 +429,15 @@ impl Node {
                 // notice the HashBits use different instance
                 let mut child = Box::new(Node::new());
                 let mut hash_copy = hv.clone();
-                child.set(bs, &mut hash_copy, k, v)?;
+                child.set(bs, hasher, &mut hash_copy, k, v)?;
 
                 for (old_k, old_v) in kvs.iter() {
-                    let new_hash = hash(old_k.as_bytes());
+                    let new_hash = hasher.hash(old_k.as_ref());
                     let mut ch_hv =
                         HashBits::new_with_consumed(new_hash.as_ref(), hv.consumed(), hv.bit_width);
                     // must use clone, not mem::swap, for this `set` function may be failed(e.g. MaxDepth)
                     // if failed, should not change the tree current struct
-                    child.set(bs, &mut ch_hv, old_k, old_v.clone())?;
+                    child.set(bs, hasher, &mut ch_hv, old_k.clone(), old_v.clone())?;
                 }
 
                 let child_item = Item::Ptr(child);
@@ -351,13 +447,15 @@ impl Node {
         }
     }
 
-    pub fn remove<'hash, B>(&mut self, bs: &B, hv: &mut HashBits<'hash>, k: &str) -> Result<()>
+    pub fn remove<'hash, B, Q>(&mut self, bs: &B, hv: &mut HashBits<'hash>, k: &Q) -> Result<()>
     where
         B: CborIpldStore,
+        K: Borrow<Q>,
+        Q: AsRef<[u8]> + Ord + ?Sized,
     {
         let idx = hv.next().ok_or(Error::MaxDepth)?;
         if self.bitfield.bit(idx as usize) == false {
-            return Err(Error::NotFound(k.to_string()));
+            return Err(Error::NotFound(k.as_ref().to_vec()));
         }
         let item_index = bit_to_index(&self.bitfield, idx);
 
@@ -375,7 +473,7 @@ impl Node {
                 item.clean_child()
             }
             Item::Leaf(kvs) => {
-                let _ = kvs.remove(k).ok_or(Error::NotFound(k.to_string()))?;
+                let _ = kvs.remove(k).ok_or(Error::NotFound(k.as_ref().to_vec()))?;
                 if kvs.is_empty() {
                     items.remove(item_index);
                     // set idx pos bit is zero
@@ -394,7 +492,7 @@ impl Node {
         for item in &mut items[..].iter_mut() {
             if let Item::Ptr(node) = item {
                 node.flush(bs)?;
-                let cid = bs.put(&node)?;
+                let cid = bs.put(&node, None)?;
                 // flush current item
                 *item = Item::Link(cid);
             }
@@ -407,9 +505,9 @@ impl Node {
     where
         B: CborIpldStore,
     {
-        let cid = bs.put(&self)?;
-        let node: Node = bs.get(&cid)?;
-        let mut total_size = ipld_core::dump_object(&node)?.len() as u64;
+        let cid = bs.put(&self, None)?;
+        let node: Node<K, V> = bs.get(&cid)?;
+        let mut total_size = ipld_core::dag_cbor::encode(&ipld_core::to_ipld(&node)?)?.len() as u64;
         for item in self.items.borrow_mut().iter_mut() {
             item.load_item(bs)?;
             if let Item::Ptr(node) = item {
@@ -421,11 +519,11 @@ impl Node {
     }
 
     /// insert k,v to this bit position.
-    fn insert_child(&mut self, idx: u32, k: &str, v: Value) -> Result<()> {
+    fn insert_child(&mut self, idx: u32, k: K, v: V) -> Result<()> {
         let i = bit_to_index(&self.bitfield, idx);
         // set bit for index i
         set_bit(&mut self.bitfield, idx);
-        let leaf = Item::from_kvs(vec![(k.to_string(), v)]);
+        let leaf = Item::from_kvs(vec![(k, v)]);
         self.items.borrow_mut().insert(i as usize, leaf);
         Ok(())
     }
@@ -440,8 +538,11 @@ pub struct HamtStats {
 }
 
 #[cfg(test)]
-pub fn stats<B>(hamt: &Hamt<B>) -> HamtStats
+pub fn stats<K, V, H, B>(hamt: &Hamt<K, V, H, B>) -> HamtStats
 where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+    H: HashAlgorithm,
     B: CborIpldStore,
 {
     let mut st = HamtStats::default();
@@ -450,8 +551,10 @@ where
 }
 
 #[cfg(test)]
-fn stats_rec<B>(bs: &B, node: &Node, st: &mut HamtStats)
+fn stats_rec<K, V, B>(bs: &B, node: &Node<K, V>, st: &mut HamtStats)
 where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
     B: CborIpldStore,
 {
     st.total_nodes += 1;
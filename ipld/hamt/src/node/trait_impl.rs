@@ -2,16 +2,21 @@
 
 use std::collections::BTreeMap;
 use std::fmt;
+use std::marker::PhantomData;
 
 use bigint::U256;
-use cid::Cid;
+use ipld_core::IpldLink;
 use serde::{de, ser, Deserialize, Serialize};
 
 use super::{Hamt, Item, Node, KVT};
+use crate::hash::HashAlgorithm;
 use crate::ipld::CborIpldStore;
 
-impl<B> PartialEq for Hamt<B>
+impl<K, V, H, B> PartialEq for Hamt<K, V, H, B>
 where
+    K: PartialEq,
+    V: PartialEq,
+    H: HashAlgorithm,
     B: CborIpldStore,
 {
     fn eq(&self, other: &Self) -> bool {
@@ -19,10 +24,11 @@ where
     }
 }
 
-impl<B> Eq for Hamt<B> where B: CborIpldStore {}
-
-impl<B> fmt::Debug for Hamt<B>
+impl<K, V, H, B> fmt::Debug for Hamt<K, V, H, B>
 where
+    K: fmt::Debug,
+    V: fmt::Debug,
+    H: HashAlgorithm,
     B: CborIpldStore,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -34,7 +40,11 @@ where
     }
 }
 
-impl ser::Serialize for Node {
+impl<K, V> ser::Serialize for Node<K, V>
+where
+    K: Serialize,
+    V: Serialize,
+{
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: ser::Serializer,
@@ -52,14 +62,22 @@ impl ser::Serialize for Node {
     }
 }
 
-impl<'de> de::Deserialize<'de> for Node {
+impl<'de, K, V> de::Deserialize<'de> for Node<K, V>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+{
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: de::Deserializer<'de>,
     {
-        struct TupleVisitor;
-        impl<'de> de::Visitor<'de> for TupleVisitor {
-            type Value = (serde_bytes::ByteBuf, Vec<Item>);
+        struct TupleVisitor<K, V>(PhantomData<(K, V)>);
+        impl<'de, K, V> de::Visitor<'de> for TupleVisitor<K, V>
+        where
+            K: Deserialize<'de> + Ord,
+            V: Deserialize<'de>,
+        {
+            type Value = (serde_bytes::ByteBuf, Vec<Item<K, V>>);
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                 write!(formatter, "tuple must be 2 item, bytes and Vec<Pointer>")
@@ -77,7 +95,7 @@ impl<'de> de::Deserialize<'de> for Node {
                 Ok((first, second))
             }
         }
-        let (byte_buf, items) = deserializer.deserialize_tuple(2, TupleVisitor)?;
+        let (byte_buf, items) = deserializer.deserialize_tuple(2, TupleVisitor(PhantomData))?;
 
         // it's big ending bytes, we copy value from end.
         // the buf is size of `u64` u8 array, notice could not out of bounds.
@@ -96,10 +114,11 @@ impl<'de> de::Deserialize<'de> for Node {
     }
 }
 
-#[derive(Serialize)]
-struct CborCid<'a>(#[serde(serialize_with = "cid::ipld_dag_cbor::serialize")] &'a Cid);
-
-impl ser::Serialize for Item {
+impl<K, V> ser::Serialize for Item<K, V>
+where
+    K: Serialize,
+    V: Serialize,
+{
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: ser::Serializer,
@@ -107,7 +126,7 @@ impl ser::Serialize for Item {
         match self {
             Item::Link(cid) => {
                 let mut m = BTreeMap::new();
-                m.insert("0", CborCid(cid));
+                m.insert("0", IpldLink(cid.clone()));
                 m.serialize(serializer)
             }
             Item::Leaf(kvs) => {
@@ -121,22 +140,25 @@ impl ser::Serialize for Item {
 }
 
 #[derive(Deserialize)]
-enum ItemRef {
+enum ItemRef<K, V> {
     #[serde(rename = "0")]
-    #[serde(deserialize_with = "cid::ipld_dag_cbor::deserialize")]
-    Link(Cid),
+    Link(IpldLink),
     #[serde(rename = "1")]
-    KVs(Vec<KVT>),
+    KVs(Vec<KVT<K, V>>),
 }
 
-impl<'de> de::Deserialize<'de> for Item {
+impl<'de, K, V> de::Deserialize<'de> for Item<K, V>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+{
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: de::Deserializer<'de>,
     {
         let item_ref = ItemRef::deserialize(deserializer)?;
         let i = match item_ref {
-            ItemRef::Link(cid) => Item::from_link(cid),
+            ItemRef::Link(cid) => Item::from_link(cid.into_inner()),
             ItemRef::KVs(kvs) => Item::from_kvs(kvs),
         };
         Ok(i)
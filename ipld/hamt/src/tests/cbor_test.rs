@@ -4,44 +4,44 @@ use super::*;
 
 use bytes::Bytes;
 use cid::{Cid, IntoExt};
-use ipld_core::struct_to_cbor_value;
+use ipld_core::{to_ipld, IpldValue};
 
 #[test]
 fn test_kv() {
     let b: Bytes = vec![1_u8, 2, 3].into();
-    let v = struct_to_cbor_value(&b).unwrap();
-    let kv: KVT = ("123".to_string(), v);
+    let v = to_ipld(&b).unwrap();
+    let kv: KVT<String, IpldValue> = ("123".to_string(), v);
 
-    let r = serde_cbor::to_vec(&kv).unwrap();
+    let r = to_vec(&kv);
     println!("{:?}", r);
 
     let result = vec![130_u8, 99, 49, 50, 51, 67, 1, 2, 3];
     assert_eq!(r, result);
 
-    let kv2: KVT = serde_cbor::from_slice(&r).unwrap();
+    let kv2: KVT<String, IpldValue> = from_slice(&r);
     assert_eq!(kv, kv2);
 }
 
 #[test]
 fn test_pointer_and_node() {
     let b: Bytes = vec![1_u8, 2, 3].into();
-    let v = struct_to_cbor_value(&b).unwrap();
-    let kv: KVT = ("123".to_string(), v.clone());
-    let kv2: KVT = ("124".to_string(), v);
+    let v = to_ipld(&b).unwrap();
+    let kv: KVT<String, IpldValue> = ("123".to_string(), v.clone());
+    let kv2: KVT<String, IpldValue> = ("124".to_string(), v);
     let pointer = Item::from_kvs(vec![kv, kv2]);
-    let r = serde_cbor::to_vec(&pointer).unwrap();
+    let r = to_vec(&pointer);
     println!("{:?}", r);
     assert_eq!(
         r,
         vec![161, 97, 49, 130, 130, 99, 49, 50, 51, 67, 1, 2, 3, 130, 99, 49, 50, 52, 67, 1, 2, 3]
     );
 
-    let p2: Item = serde_cbor::from_slice(&r).unwrap();
+    let p2: Item<String, IpldValue> = from_slice(&r);
     assert_eq!(p2, pointer);
 
     let cid = Cid::new_v0(multihash::Sha2_256::digest(b"something").into_ext()).unwrap();
     let pointer2 = Item::from_link(cid);
-    let r = serde_cbor::to_vec(&pointer2).unwrap();
+    let r = to_vec(&pointer2);
     println!("{:?}", r);
     assert_eq!(
         r,
@@ -54,7 +54,7 @@ fn test_pointer_and_node() {
 
     // bitfield is 0
     let node = test_node("0", vec![pointer.clone(), pointer2.clone()]);
-    let r = serde_cbor::to_vec(&node).unwrap();
+    let r = to_vec(&node);
     println!("{:?}", r);
     assert_eq!(
         r,
@@ -65,12 +65,12 @@ fn test_pointer_and_node() {
             54, 252, 167, 72, 19, 203
         ]
     );
-    let node: Node = serde_cbor::from_slice(&r).unwrap();
+    let node: Node<String, IpldValue> = from_slice(&r);
     println!("{:?}", node);
 
     // bitfield is 9999
     let node = test_node("9999", vec![pointer.clone(), pointer2.clone()]);
-    let r = serde_cbor::to_vec(&node).unwrap();
+    let r = to_vec(&node);
     println!("{:?}", r);
     assert_eq!(
         r,
@@ -81,12 +81,12 @@ fn test_pointer_and_node() {
             170, 165, 54, 252, 167, 72, 19, 203
         ]
     );
-    let node: Node = serde_cbor::from_slice(&r).unwrap();
+    let node: Node<String, IpldValue> = from_slice(&r);
     println!("{:?}", node);
 
     // bitfield is 0x12345678
     let node = test_node("305419896", vec![pointer.clone(), pointer2.clone()]);
-    let r = serde_cbor::to_vec(&node).unwrap();
+    let r = to_vec(&node);
     println!("{:?}", r);
     assert_eq!(
         r,
@@ -97,14 +97,14 @@ fn test_pointer_and_node() {
             224, 1, 170, 165, 54, 252, 167, 72, 19, 203
         ]
     );
-    let node: Node = serde_cbor::from_slice(&r).unwrap();
+    let node: Node<String, IpldValue> = from_slice(&r);
     println!("{:?}", node);
 
     let node = test_node(
         "11579208923731619542357098500868790785326998466564056403945758400791312",
         vec![pointer, pointer2],
     );
-    let r = serde_cbor::to_vec(&node).unwrap();
-    let node: Node = serde_cbor::from_slice(&r).unwrap();
+    let r = to_vec(&node);
+    let node: Node<String, IpldValue> = from_slice(&r);
     println!("{:?}", node);
 }
@@ -7,8 +7,8 @@ use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 
 use super::*;
+use crate::hash::Identity;
 
-#[cfg(not(feature = "test-hash"))]
 fn rand_string() -> String {
     let rand_string: String = thread_rng().sample_iter(&Alphanumeric).take(18).collect();
     rand_string
@@ -19,9 +19,9 @@ fn rand_value() -> Vec<u8> {
     rand_string.into_bytes()
 }
 
-#[cfg(feature = "test-hash")]
-fn node_equal<B>(h1: &mut Hamt<B>, h2: &mut Hamt<B>)
+fn node_equal<H, B>(h1: &mut Hamt<String, Vec<u8>, H, B>, h2: &mut Hamt<String, Vec<u8>, H, B>)
 where
+    H: HashAlgorithm,
     B: CborIpldStore,
 {
     let cid1 = h1.flush().unwrap();
@@ -29,21 +29,20 @@ where
     assert_eq!(cid1, cid2);
 }
 
-#[cfg(feature = "test-hash")]
 fn add_and_remove_keys(bit_width: u32, keys: &[&str], extra_keys: &[&str]) {
     let all: Vec<(&str, Vec<u8>)> = keys.iter().map(|k| (*k, rand_value())).collect();
 
     let cs = new_cbor_store();
-    let mut begin_node = Hamt::new_with_bitwidth(cs.clone(), bit_width);
+    let mut begin_node = Hamt::new_with_hash(cs.clone(), Identity, bit_width);
     for (k, v) in all.iter() {
-        begin_node.set(k, v.clone()).unwrap();
+        begin_node.set(k.to_string(), v.clone()).unwrap();
     }
     println!("start flush");
     let now = Instant::now();
     let cid = begin_node.flush().unwrap();
     println!("flush took: {}", now.elapsed().as_nanos());
 
-    let mut node = Hamt::load_with_bitwidth(cs.clone(), &cid, bit_width).unwrap();
+    let mut node = Hamt::load_with_hash(cs.clone(), &cid, Identity, bit_width).unwrap();
 
     for (k, v) in all {
         let v2: Vec<u8> = node.find(k).unwrap();
@@ -52,17 +51,16 @@ fn add_and_remove_keys(bit_width: u32, keys: &[&str], extra_keys: &[&str]) {
 
     // create second hamt by adding and deleting the extra keys
     for k in extra_keys.iter() {
-        begin_node.set(k, rand_value()).unwrap();
+        begin_node.set(k.to_string(), rand_value()).unwrap();
     }
     for k in extra_keys.iter() {
-        begin_node.delete(k).unwrap();
+        begin_node.delete(*k).unwrap();
     }
     let cid2 = begin_node.flush().unwrap();
-    let mut node2 = Hamt::load(cs, &cid2).unwrap();
+    let mut node2 = Hamt::load_with_hash(cs, &cid2, Identity, bit_width).unwrap();
     node_equal(&mut node, &mut node2);
 }
 
-#[cfg(feature = "test-hash")]
 #[test]
 fn test_hash_canonical_structure() {
     let k1 = ["K"];
@@ -73,7 +71,6 @@ fn test_hash_canonical_structure() {
     add_and_remove_keys(DEFAULT_BIT_WIDTH, &k1, &k2);
 }
 
-#[cfg(feature = "test-hash")]
 #[test]
 fn test_hash_canonical_structure_alternate_bit_width() {
     add_and_remove_keys(7, ["K"].as_ref(), ["B"].as_ref());
@@ -96,7 +93,6 @@ fn test_hash_canonical_structure_alternate_bit_width() {
     );
 }
 
-#[cfg(feature = "test-hash")]
 #[test]
 fn test_hash_overflow() {
     let keys = [
@@ -106,27 +102,25 @@ fn test_hash_overflow() {
         "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA3",
     ];
     let cs = new_cbor_store();
-    let mut node = Hamt::new(cs);
+    let mut node = Hamt::new_with_hash(cs, Identity, DEFAULT_BIT_WIDTH);
     for k in &keys[..3] {
-        node.set(k, b"foobar".to_vec()).unwrap();
+        node.set(k.to_string(), b"foobar".to_vec()).unwrap();
     }
 
-    let res = node.set(keys[3], b"foobar".to_vec());
+    let res = node.set(keys[3].to_string(), b"foobar".to_vec());
     assert!(matches!(res, Err(Error::MaxDepth)));
     // Try forcing the depth beyond 32
-    node.set(&keys[3][1..], b"foobar".to_vec()).unwrap();
+    node.set(keys[3][1..].to_string(), b"foobar".to_vec())
+        .unwrap();
 }
 
-#[cfg(feature = "test-hash")]
 #[test]
 fn test_hash_delete() {
     let cs = new_cbor_store();
-    let mut hamt = Hamt::new(cs);
-    hamt.set("K00", "K00").unwrap();
-    hamt.set("K01", "K01").unwrap();
-    hamt.set("K02", "K02").unwrap();
-    hamt.set("K03", "K03").unwrap();
-    hamt.set("K04", "K04").unwrap();
+    let mut hamt = Hamt::new_with_hash(cs, Identity, DEFAULT_BIT_WIDTH);
+    for k in &["K00", "K01", "K02", "K03", "K04"] {
+        hamt.set(k.to_string(), k.to_string()).unwrap();
+    }
 
     hamt.delete("K00").unwrap();
     let cid = hamt.flush().unwrap();
@@ -164,28 +158,26 @@ fn test_hash_delete() {
     );
 }
 
-#[cfg(not(feature = "test-hash"))]
 #[test]
 fn test_basic() {
     let cs = new_cbor_store();
     let mut begin_node = Hamt::new(cs.clone());
     let val = b"cat dog bear".to_vec();
     let key = "foo";
-    begin_node.set(key, val.clone()).unwrap();
+    begin_node.set(key.to_string(), val.clone()).unwrap();
 
     for _ in 0..1000 {
         let k = rand_string();
-        begin_node.set(&k, rand_value()).unwrap()
+        begin_node.set(k, rand_value()).unwrap()
     }
 
     let cid = begin_node.flush().unwrap();
 
-    let node = Hamt::load(cs, &cid).unwrap();
-    let v: Vec<u8> = node.find(key).unwrap();
+    let node: Hamt<String, Vec<u8>, _, _> = Hamt::load(cs, &cid).unwrap();
+    let v = node.find(key).unwrap();
     assert_eq!(v, val);
 }
 
-#[cfg(not(feature = "test-hash"))]
 #[test]
 fn test_set_get() {
     use crate::node::stats;
@@ -198,7 +190,7 @@ fn test_set_get() {
     let cs = new_cbor_store();
     let mut begin_node = Hamt::new(cs.clone());
     for (k, v) in map.iter() {
-        begin_node.set(k, v.clone()).unwrap();
+        begin_node.set(k.clone(), v.clone()).unwrap();
     }
 
     let size = begin_node.check_size().unwrap();
@@ -229,14 +221,14 @@ fn test_set_get() {
 
     for _ in 0..100 {
         let r = rand_string();
-        let result = node.find::<Vec<u8>>(&r);
+        let result = node.find(&r);
         assert!(matches!(result, Err(Error::NotFound(_))));
     }
 
     map.iter_mut().for_each(|(k, v)| {
         let new_v = rand_value();
         *v = new_v.clone();
-        node.set(k, new_v).unwrap();
+        node.set(k.clone(), new_v).unwrap();
     });
 
     map.iter().for_each(|(k, v)| {
@@ -252,22 +244,20 @@ fn test_set_get() {
 
     for (k, _) in map {
         node.delete(&k).unwrap();
-        let result = node.find::<Vec<u8>>(&k);
+        let result = node.find(&k);
         assert!(matches!(result, Err(Error::NotFound(_))));
     }
 }
 
-#[cfg(not(feature = "test-hash"))]
 #[test]
 fn test_reload_empty() {
     let cs = new_cbor_store();
-    let mut n = Hamt::new(cs.clone());
+    let mut n: Hamt<String, Vec<u8>, _, _> = Hamt::new(cs.clone());
     let c = n.flush().unwrap();
     let mut on = Hamt::load(cs, &c).unwrap();
-    on.set("foo", b"bar".to_vec()).unwrap();
+    on.set("foo".to_string(), b"bar".to_vec()).unwrap();
 }
 
-#[cfg(not(feature = "test-hash"))]
 #[test]
 fn test_value_linking() {
     use std::collections::BTreeMap;
@@ -277,7 +267,7 @@ fn test_value_linking() {
     let mut cs = new_cbor_store();
     let mut thingy1 = HashMap::new();
     thingy1.insert("cat".to_string(), "dog".to_string());
-    let c1 = cs.put(thingy1).unwrap();
+    let c1 = cs.put(thingy1, None).unwrap();
 
     let c = IpldValue::Link(c1);
     let mut hash = BTreeMap::new();
@@ -286,7 +276,7 @@ fn test_value_linking() {
     let thingy2 = IpldValue::Map(hash);
 
     let mut n = Hamt::new(cs);
-    n.set("cat", thingy2).unwrap();
+    n.set("cat".to_string(), thingy2).unwrap();
     let tcid = n.flush().unwrap();
 
     assert_eq!(
@@ -294,3 +284,146 @@ fn test_value_linking() {
         "bafy2bzacedrwmwbquhdfs2ivq4dhejgyo2jxhlw2xdrrjwcarbimpxuvmx3e4"
     );
 }
+
+fn entries<H, B>(hamt: &mut Hamt<String, Vec<u8>, H, B>) -> Vec<(String, Vec<u8>)>
+where
+    H: HashAlgorithm,
+    B: CborIpldStore,
+{
+    hamt.iter()
+        .map(|kv| {
+            let (k, v) = kv.unwrap();
+            (k.clone(), v.clone())
+        })
+        .collect()
+}
+
+#[test]
+fn test_iter_and_for_each() {
+    use std::collections::BTreeMap;
+
+    let mut map: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    for _ in 0..1000 {
+        map.insert(rand_string(), rand_value());
+    }
+
+    let cs = new_cbor_store();
+    let mut begin_node = Hamt::new(cs.clone());
+    for (k, v) in map.iter() {
+        begin_node.set(k.clone(), v.clone()).unwrap();
+    }
+
+    let kvs = entries(&mut begin_node);
+    assert_eq!(kvs.len(), map.len());
+    // hash order, the first level is indexed by the first byte of the hash
+    let first_byte = |k: &str| Murmur3.hash(k.as_bytes())[0];
+    assert!(kvs
+        .windows(2)
+        .all(|w| first_byte(&w[0].0) <= first_byte(&w[1].0)));
+    let mut sorted = kvs.clone();
+    sorted.sort();
+    assert_eq!(sorted, map.clone().into_iter().collect::<Vec<_>>());
+
+    // the links of the loaded hamt are loaded lazily
+    let cid = begin_node.flush().unwrap();
+    let mut node = Hamt::load(cs, &cid).unwrap();
+    assert_eq!(entries(&mut node), kvs);
+
+    let mut keys = vec![];
+    node.for_each_ordered(Order::Key, |k, _| {
+        keys.push(k.to_string());
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(keys, map.keys().cloned().collect::<Vec<_>>());
+
+    let mut count = 0;
+    let result = node.for_each(|k, _| {
+        count += 1;
+        if count == 10 {
+            return Err(Error::NotFound(k.clone().into_bytes()));
+        }
+        Ok(())
+    });
+    assert!(matches!(result, Err(Error::NotFound(_))));
+    assert_eq!(count, 10);
+}
+
+#[test]
+fn test_iter_missing_link() {
+    let cs = new_cbor_store();
+    let mut begin_node = Hamt::new(cs.clone());
+    for i in 0..1000 {
+        begin_node.set(format!("key{}", i), i).unwrap();
+    }
+    let cid = begin_node.flush().unwrap();
+
+    // the links are missing in another store
+    let mut other = new_cbor_store();
+    let root: Node<String, i32> = cs.get(&cid).unwrap();
+    let cid = other.put(&root, None).unwrap();
+    let mut node: Hamt<String, i32, _, _> = Hamt::load(other, &cid).unwrap();
+    // the iteration stops at the first missing link
+    let mut results = node.iter().collect::<Vec<_>>();
+    assert!(matches!(results.pop(), Some(Err(Error::NotFoundForCid(_)))));
+    assert!(results.iter().all(|kv| kv.is_ok()));
+}
+
+#[test]
+fn test_diff() {
+    let cs = new_cbor_store();
+    let mut hamt = Hamt::new(cs.clone());
+    for i in 0..10 {
+        hamt.set(format!("key{}", i), i).unwrap();
+    }
+    let small_root = hamt.flush().unwrap();
+    for i in 10..1000 {
+        hamt.set(format!("key{}", i), i).unwrap();
+    }
+    let old_root = hamt.flush().unwrap();
+    assert!(diff::<String, i32, _>(&cs, &old_root, &old_root)
+        .unwrap()
+        .is_empty());
+
+    // the leaves of the small hamt are subtrees in the large one
+    let changes: Vec<Change<String, i32>> = diff(&cs, &small_root, &old_root).unwrap();
+    assert_eq!(changes.len(), 990);
+    assert!(changes
+        .iter()
+        .all(|change| matches!(change, Change::Add { .. })));
+
+    // the same value isn't a change
+    hamt.set("key1".to_string(), 1).unwrap();
+    hamt.set("key2".to_string(), 2000).unwrap();
+    hamt.delete("key3").unwrap();
+    hamt.set("new".to_string(), 1000).unwrap();
+    let new_root = hamt.flush().unwrap();
+
+    let changes = diff(&cs, &old_root, &new_root).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            Change::Modify {
+                key: "key2".to_string(),
+                before: 2,
+                after: 2000,
+            },
+            Change::Remove {
+                key: "key3".to_string(),
+                value: 3,
+            },
+            Change::Add {
+                key: "new".to_string(),
+                value: 1000,
+            },
+        ]
+    );
+
+    let changes: Vec<Change<String, i32>> = diff(&cs, &new_root, &old_root).unwrap();
+    assert_eq!(
+        changes.iter().map(Change::key).collect::<Vec<_>>(),
+        vec!["key2", "key3", "new"]
+    );
+    assert!(matches!(changes[1], Change::Add { .. }));
+    assert!(matches!(changes[2], Change::Remove { .. }));
+}
@@ -1,14 +1,11 @@
 // Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
 
-use crate::hash::HashBits;
+use crate::hash::{HashAlgorithm, HashBits, Murmur3, Sha256};
 
-#[cfg(not(feature = "test-hash"))]
 #[test]
 fn test_hash() {
-    use crate::hash::hash;
-
-    let h1 = hash("abcd");
-    let h2 = hash("abce");
+    let h1 = Murmur3.hash(b"abcd");
+    let h2 = Murmur3.hash(b"abce");
 
     let first = [184_u8, 123, 183, 214, 70, 86, 205, 79];
     let second = [5, 245, 47, 205, 203, 64, 38, 66];
@@ -16,16 +13,33 @@ fn test_hash() {
     assert_eq!(h1, first);
     assert_eq!(h2, second);
 
-    let h3 = hash("hello world");
+    let h3 = Murmur3.hash("hello world".as_bytes());
     assert_eq!(h3, [83, 63, 96, 70, 235, 127, 97, 14]);
 
-    let h4 = hash(b"hello world");
+    let h4 = Murmur3.hash(b"hello world");
     assert_eq!(h4, [83, 63, 96, 70, 235, 127, 97, 14]);
 
-    let h5 = hash("一二三");
+    let h5 = Murmur3.hash("一二三".as_bytes());
     assert_eq!(h5, [37, 41, 47, 183, 147, 168, 177, 225]);
 }
 
+#[test]
+fn test_hash_sha256() {
+    let h1 = Sha256.hash(b"foo");
+    let first = [
+        0x2c, 0x26, 0xb4, 0x6b, 0x68, 0xff, 0xc6, 0x8f, 0xf9, 0x9b, 0x45, 0x3c, 0x1d, 0x30, 0x41,
+        0x34, 0x13, 0x42, 0x2d, 0x70, 0x64, 0x83, 0xbf, 0xa0, 0xf9, 0x8a, 0x5e, 0x88, 0x62, 0x66,
+        0xe7, 0xae,
+    ];
+    assert_eq!(h1, first);
+
+    // the hash could be chosen at runtime
+    let hasher: Box<dyn HashAlgorithm> = Box::new(Sha256);
+    assert_eq!(hasher.hash(b"foo"), h1);
+    let hasher: Box<dyn HashAlgorithm> = Box::new(Murmur3);
+    assert_eq!(hasher.hash(b"abcd"), [184, 123, 183, 214, 70, 86, 205, 79]);
+}
+
 #[test]
 fn test_hash_bits_overflow() {
     let buf = [255_u8];
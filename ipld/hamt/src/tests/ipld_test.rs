@@ -3,7 +3,7 @@
 use std::string::ToString;
 
 use bigint::U256;
-use ipld_core::struct_to_cbor_value;
+use ipld_core::{to_ipld, IpldValue};
 
 use super::*;
 use crate::node::set_bit;
@@ -18,37 +18,60 @@ fn test_roundtrip() {
     set_bit(&mut bitmap, 7);
     set_bit(&mut bitmap, 18);
 
-    let v = struct_to_cbor_value(&vec![0x83_u8, 0x01, 0x02, 0x03]).unwrap();
-    let kv: KVT = ("foo".to_string(), v);
+    let v = to_ipld(&vec![0x83_u8, 0x01, 0x02, 0x03]).unwrap();
+    let kv: KVT<String, IpldValue> = ("foo".to_string(), v);
     let p = Item::from_kvs(vec![kv]);
 
     let n = test_node(&bitmap.to_string(), vec![p]);
 
-    let cid = cs.put(n).unwrap();
-    let n2: Node = cs.get(&cid).unwrap();
+    let cid = cs.put(n, None).unwrap();
+    let n2: Node<String, IpldValue> = cs.get(&cid).unwrap();
 
-    let c2 = cs.put(n2).unwrap();
+    let c2 = cs.put(n2, None).unwrap();
     assert_eq!(cid, c2);
 }
 
 #[test]
 fn test_basic_bytes_loading() {
     let b = b"cats and dogs are taking over".to_vec();
-    let o = ipld_core::dump_object::<Vec<u8>>(b.as_ref()).unwrap();
-    let s: Vec<u8> = ipld_core::decode_into(&o).unwrap();
+    let o = to_vec(&b);
+    let s: Vec<u8> = from_slice(&o);
     assert_eq!(b, s);
 }
 
-#[cfg(not(feature = "test-hash"))]
+#[test]
+fn test_put_expected_cid() {
+    use cid::Codec;
+    use ipld_core::IpldNode;
+    use matches::matches;
+
+    let mut cs = new_cbor_store();
+    let c1 = cs.put("cats", None).unwrap();
+    assert_eq!(cs.put("cats", Some(&c1)).unwrap(), c1);
+
+    // the codec and the hash of the expected cid are used
+    let sha256 =
+        IpldNode::from_object_with_codec(&"cats", multihash::Code::Sha2_256.into(), Codec::DagCBOR)
+            .unwrap()
+            .cid()
+            .clone();
+    assert_ne!(sha256, c1);
+    assert_eq!(cs.put("cats", Some(&sha256)).unwrap(), sha256);
+    let s: String = cs.get(&sha256).unwrap();
+    assert_eq!(s, "cats");
+
+    let res = cs.put("dogs", Some(&c1));
+    assert!(matches!(res, Err(Error::CidMismatch { .. })));
+}
+
 #[test]
 fn test_kv() {
-    use ipld_core::IpldValue;
     use std::collections::BTreeMap;
 
     let mut cs = new_cbor_store();
     let mut thingy1 = HashMap::new();
     thingy1.insert("cat".to_string(), "dog".to_string());
-    let c1 = cs.put(thingy1).unwrap();
+    let c1 = cs.put(thingy1, None).unwrap();
 
     let c = IpldValue::Link(c1);
     let mut hash = BTreeMap::new();
@@ -56,13 +79,13 @@ fn test_kv() {
     hash.insert("foo".into(), IpldValue::String("bar".to_string()));
     let thingy2 = IpldValue::Map(hash);
 
-    let b = ipld_core::dump_object(&thingy2).unwrap();
+    let b = to_vec(&thingy2);
     println!("{:?}", b);
 
     let mut node = Hamt::new(cs);
-    node.set("cat", thingy2).unwrap();
+    node.set("cat".to_string(), thingy2).unwrap();
 
-    let b = ipld_core::dump_object(node.root()).unwrap();
+    let b = to_vec(node.root());
     println!("{:?} {}", b, b.len());
 
     assert_eq!(
@@ -76,3 +99,45 @@ fn test_kv() {
         ]
     )
 }
+
+#[test]
+fn test_v2_format() {
+    // the CID of an empty go-hamt-ipld v2 map with the bit width 5, it's the `EmptyMapCid`
+    // of the Filecoin specs-actors v2, the block is `[h'', []]` hashed with blake2b-256
+    let mut node: Hamt<BytesKey, String, _, _> = Hamt::new_with_hash(new_cbor_store(), Sha256, 5);
+    assert_eq!(
+        node.flush().unwrap().to_string(),
+        "bafy2bzaceamp42wmmgr2g2ymg46euououzfyck7szknvfacqscohrvaikwfay"
+    );
+    // a map that becomes empty again has the same root
+    node.set(BytesKey::from("foo"), "bar".to_string()).unwrap();
+    node.delete(&b"foo"[..]).unwrap();
+    assert_eq!(
+        node.flush().unwrap().to_string(),
+        "bafy2bzaceamp42wmmgr2g2ymg46euououzfyck7szknvfacqscohrvaikwfay"
+    );
+
+    let cs = new_cbor_store();
+    // go-hamt-ipld v2 hashes the keys with sha256, and the keys are byte strings
+    let mut node = Hamt::new_with_hash(cs.clone(), Sha256, 8);
+    node.set(BytesKey::from("foo"), "bar".to_string()).unwrap();
+    // the expected bytes follow the v2 layout: sha256("foo") is 2c26b46b..., so the index
+    // of the first level is 0x2c = 44 and only that bit of the bitfield is set
+    assert_eq!(
+        to_vec(node.root()),
+        vec![
+            130, 70, 16, 0, 0, 0, 0, 0, 129, 161, 97, 49, 129, 130, 67, 102, 111, 111, 99, 98, 97,
+            114
+        ]
+    );
+    let cid = node.flush().unwrap();
+    // the hash function could be chosen at runtime
+    let hasher: Box<dyn HashAlgorithm> = Box::new(Sha256);
+    let node: Hamt<BytesKey, String, _, _> = Hamt::load_with_hash(cs, &cid, hasher, 8).unwrap();
+    assert_eq!(node.find(&b"foo"[..]).unwrap(), "bar");
+
+    // the bit width of Filecoin is 5, the index of the first level is 0b00101 = 5
+    let mut node = Hamt::new_with_hash(new_cbor_store(), Sha256, 5);
+    node.set(BytesKey::from("foo"), "bar".to_string()).unwrap();
+    assert_eq!(to_vec(node.root())[..3], [130, 65, 32]);
+}
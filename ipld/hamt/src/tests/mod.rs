@@ -12,6 +12,9 @@ use std::rc::Rc;
 use block_format::{BasicBlock, Block as BlockT};
 use bytes::Bytes;
 use cid::Cid;
+use serde::{de::DeserializeOwned, Serialize};
+
+use ipld_core::{dag_cbor, from_ipld, to_ipld};
 
 use super::*;
 use crate::error::*;
@@ -46,3 +49,11 @@ impl Blockstore for MockBlocks {
 pub fn new_cbor_store() -> BasicCborIpldStore<MockBlocks> {
     BasicCborIpldStore::new(MockBlocks::default())
 }
+
+pub fn to_vec<T: Serialize>(v: &T) -> Vec<u8> {
+    dag_cbor::encode(&to_ipld(v).unwrap()).unwrap()
+}
+
+pub fn from_slice<T: DeserializeOwned>(b: &[u8]) -> T {
+    from_ipld(dag_cbor::decode(b).unwrap()).unwrap()
+}
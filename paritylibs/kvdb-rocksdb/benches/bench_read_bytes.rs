@@ -0,0 +1,105 @@
+// Copyright 2019-2020 PolkaX. Licensed under MIT or Apache-2.0.
+
+//! Benchmark the block read paths of RocksDB.
+//! The benchmark setup consists in writing `BLOCKS` 32-bytes random keys with random values
+//! `BLOCK_SIZE` bytes long, which is the usual size of an IPFS block, then reading them with
+//! `get` (owned `Vec<u8>`), `get_bytes` (`Bytes`) and `view` (borrowed pinned slice).
+//! Allocations (on the Rust side) are counted and printed.
+
+const BLOCKS: usize = 1_000;
+const BLOCK_SIZE: usize = 256 * 1024;
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use alloc_counter::{count_alloc, AllocCounterSystem};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{seq::SliceRandom, Rng};
+
+use kvdb_rocksdb::{Database, DatabaseConfig, DEFAULT_COLUMN_NAME};
+
+type H256 = [u8; 32];
+
+#[global_allocator]
+static A: AllocCounterSystem = AllocCounterSystem;
+
+criterion_group!(benches, read_blocks);
+criterion_main!(benches);
+
+/// Opens (or creates) a RocksDB database in the `benches/` folder of the crate with default
+/// options. Needs manual cleanup.
+fn open_db() -> Database {
+    let tempdir_str = "./benches/_rocksdb_bench_read_bytes";
+    let cfg = DatabaseConfig::default();
+    Database::open(&cfg, tempdir_str).expect("rocksdb works")
+}
+
+/// Writes `BLOCKS` random blocks to the DB and returns their keys.
+fn populate(db: &Database) -> io::Result<Vec<H256>> {
+    let mut rng = rand::thread_rng();
+    let mut keys = Vec::with_capacity(BLOCKS);
+    let mut batch = db.transaction();
+    for _ in 0..BLOCKS {
+        let key: H256 = rng.gen();
+        let value = (0..BLOCK_SIZE).map(|_| rng.gen()).collect::<Vec<u8>>();
+        batch.put(DEFAULT_COLUMN_NAME, &key, &value);
+        keys.push(key);
+    }
+    db.write(batch)?;
+    // Clear the overlay
+    db.flush()?;
+    Ok(keys)
+}
+
+/// Runs `read` on random keys, counting the allocations.
+fn bench_read<F>(c: &mut Criterion, name: &str, keys: &[H256], mut read: F)
+where
+    F: FnMut(&H256),
+{
+    let mut total_iterations = 0;
+    let mut total_allocs = 0;
+
+    c.bench_function(name, |b| {
+        b.iter_custom(|iterations| {
+            total_iterations += iterations;
+            let mut elapsed = Duration::new(0, 0);
+            // NOTE: counts allocations on the Rust side only
+            let (alloc_stats, _) = count_alloc(|| {
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    let key = keys
+                        .choose(&mut rand::thread_rng())
+                        .expect("keys is not empty");
+                    read(key);
+                }
+                elapsed = start.elapsed();
+            });
+            total_allocs += alloc_stats.0;
+            elapsed
+        });
+    });
+    if total_iterations > 0 {
+        println!(
+            "[{}] total: iterations={}, allocations={}; allocations per iter={:.2}\n",
+            name,
+            total_iterations,
+            total_allocs,
+            total_allocs as f64 / total_iterations as f64
+        );
+    }
+}
+
+fn read_blocks(c: &mut Criterion) {
+    let db = open_db();
+    let keys = populate(&db).expect("rocksdb works");
+
+    bench_read(c, "get block", &keys, |key| {
+        black_box(db.get(DEFAULT_COLUMN_NAME, key).unwrap());
+    });
+    bench_read(c, "get block as bytes", &keys, |key| {
+        black_box(db.get_bytes(DEFAULT_COLUMN_NAME, key).unwrap());
+    });
+    bench_read(c, "view block", &keys, |key| {
+        black_box(db.view(DEFAULT_COLUMN_NAME, key, |v| v[0]).unwrap());
+    });
+}
@@ -33,6 +33,7 @@ use rocksdb::{
 };
 
 use crate::iter::KeyValuePair;
+use bytes::Bytes;
 use fs_swap::{swap, swap_nonatomic};
 use interleaved_ordered::interleave_ordered;
 use kvdb::{init_cache, DBKey, DBOp, DBTransaction, DBValue, KeyValueDB};
@@ -68,11 +69,29 @@ pub const DB_DEFAULT_MEMORY_BUDGET_MB: MiB = 512;
 
 pub const DEFAULT_COLUMN_NAME: &str = "default";
 
+/// The buffered values are kept as `Bytes`, so that `get_bytes` shares them without copying.
 enum KeyState {
-    Insert(DBValue),
+    Insert(Bytes),
     Delete,
 }
 
+/// A value found by `read_value`.
+enum ValueRef<'a> {
+    /// the value is buffered in the overlay or being flushed.
+    Buffered(&'a Bytes),
+    /// the value is pinned in the RocksDB block cache.
+    Pinned(&'a [u8]),
+}
+
+impl<'a> ValueRef<'a> {
+    fn as_slice(&self) -> &[u8] {
+        match *self {
+            ValueRef::Buffered(value) => value,
+            ValueRef::Pinned(value) => value,
+        }
+    }
+}
+
 /// Compaction profile for the database settings
 /// Note, that changing these parameters may trigger
 /// the compaction process of RocksDB on startup.
@@ -507,7 +526,7 @@ impl Database {
                     overlay
                         .get_mut::<str>(&col)
                         .expect("col must in columns")
-                        .insert(key, KeyState::Insert(value));
+                        .insert(key, KeyState::Insert(Bytes::from(value)));
                 }
                 DBOp::Delete { col, key } => {
                     overlay
@@ -626,6 +645,37 @@ impl Database {
 
     /// Get value by key.
     pub fn get(&self, col: &str, key: &[u8]) -> io::Result<Option<DBValue>> {
+        self.view(col, key, |value| value.to_vec())
+    }
+
+    /// Get value by key as `Bytes`, the buffered values are shared without copying,
+    /// the values read from RocksDB are copied once out of the pinned slice.
+    pub fn get_bytes(&self, col: &str, key: &[u8]) -> io::Result<Option<Bytes>> {
+        self.read_value(col, key, |value| match value {
+            ValueRef::Buffered(value) => value.clone(),
+            ValueRef::Pinned(value) => Bytes::copy_from_slice(value),
+        })
+    }
+
+    /// Get value by key and pass it to `f` without copying it,
+    /// the value is borrowed from the overlay or pinned in the RocksDB block cache.
+    ///
+    /// `f` runs while the read locks of the database and of the overlay are held,
+    /// so it must not write to the database, and should return quickly as the writes
+    /// and the flushes are blocked until it returns.
+    pub fn view<F, T>(&self, col: &str, key: &[u8], f: F) -> io::Result<Option<T>>
+    where
+        F: FnOnce(&[u8]) -> T,
+    {
+        self.read_value(col, key, |value| f(value.as_slice()))
+    }
+
+    /// Looks up the value in the overlay, the flushing values and then RocksDB,
+    /// and passes it to `f` while the read locks are held.
+    fn read_value<F, T>(&self, col: &str, key: &[u8], f: F) -> io::Result<Option<T>>
+    where
+        F: FnOnce(ValueRef<'_>) -> T,
+    {
         match *self.db.read() {
             Some(ref cfs) => {
                 self.stats.tally_reads(1);
@@ -634,18 +684,19 @@ impl Database {
                     .get::<str>(col)
                     .ok_or_else(|| other_io_err("kvdb column index is out of bounds"))?;
                 match overlay.get(key) {
-                    Some(&KeyState::Insert(ref value)) => Ok(Some(value.clone())),
+                    Some(&KeyState::Insert(ref value)) => Ok(Some(f(ValueRef::Buffered(value)))),
                     Some(&KeyState::Delete) => Ok(None),
                     None => {
                         let flushing = &self.flushing.read()[col];
                         match flushing.get(key) {
-                            Some(&KeyState::Insert(ref value)) => Ok(Some(value.clone())),
+                            Some(&KeyState::Insert(ref value)) => {
+                                Ok(Some(f(ValueRef::Buffered(value))))
+                            }
                             Some(&KeyState::Delete) => Ok(None),
                             None => {
                                 let acquired_val = cfs
                                     .db
                                     .get_pinned_cf_opt(cfs.cf(col), key, &self.read_opts)
-                                    .map(|r| r.map(|v| v.to_vec()))
                                     .map_err(other_io_err);
 
                                 match acquired_val {
@@ -656,7 +707,7 @@ impl Database {
                                     _ => {}
                                 };
 
-                                acquired_val
+                                acquired_val.map(|v| v.map(|v| f(ValueRef::Pinned(&v))))
                             }
                         }
                     }
@@ -685,7 +736,7 @@ impl Database {
                     .filter_map(|(k, v)| match *v {
                         KeyState::Insert(ref value) => Some((
                             k.clone().into_vec().into_boxed_slice(),
-                            value.clone().into_boxed_slice(),
+                            value.to_vec().into_boxed_slice(),
                         )),
                         KeyState::Delete => None,
                     })
@@ -950,6 +1001,28 @@ mod tests {
         st::test_delete_and_get(&db)
     }
 
+    #[test]
+    fn get_bytes_and_view() -> io::Result<()> {
+        let (db, _) = create(vec![])?;
+        let mut tr = db.transaction();
+        tr.put(DEFAULT_COLUMN_NAME, b"foo", b"bar");
+        db.write_buffered(tr);
+        // read from the overlay, the buffered value is shared
+        let value = db.get_bytes(DEFAULT_COLUMN_NAME, b"foo")?.unwrap();
+        assert_eq!(value, &b"bar"[..]);
+        let shared = db.get_bytes(DEFAULT_COLUMN_NAME, b"foo")?.unwrap();
+        assert_eq!(shared.as_ptr(), value.as_ptr());
+        db.flush()?;
+        // read from the database
+        assert_eq!(
+            db.get_bytes(DEFAULT_COLUMN_NAME, b"foo")?.unwrap(),
+            &b"bar"[..]
+        );
+        assert_eq!(db.view(DEFAULT_COLUMN_NAME, b"foo", |v| v.len())?, Some(3));
+        assert_eq!(db.view(DEFAULT_COLUMN_NAME, b"baz", |v| v.len())?, None);
+        Ok(())
+    }
+
     #[test]
     fn iter() -> io::Result<()> {
         let (db, _) = create(vec![])?;